@@ -0,0 +1,224 @@
+use core::{
+    future,
+    task::{Context, Poll},
+};
+use std::io;
+
+use alloc::{vec, vec::Vec};
+
+use super::{
+    config::ConfigExt,
+    dh::Dh,
+    framing::{LENGTH_PREFIX_LEN, MAX_FRAME_LEN, FrameSender, Framed},
+    handshake_state::HandshakeState,
+    symmetric_state::Hash,
+};
+
+/// The underlying non-blocking io, implemented for each supported runtime.
+pub(crate) trait PollIo {
+    fn poll_read(&mut self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>>;
+
+    fn poll_write(&mut self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>>;
+
+    fn poll_flush(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>>;
+
+    fn poll_close(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>>;
+}
+
+macro_rules! ready {
+    ($e:expr) => {
+        match $e {
+            Poll::Ready(v) => v,
+            Poll::Pending => return Poll::Pending,
+        }
+    };
+}
+
+async fn read_exact<I>(io: &mut I, mut buf: &mut [u8]) -> io::Result<()>
+where
+    I: PollIo,
+{
+    future::poll_fn(|cx| {
+        while !buf.is_empty() {
+            match ready!(io.poll_read(cx, buf))? {
+                0 => return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into())),
+                len => buf = &mut core::mem::take(&mut buf)[len..],
+            }
+        }
+        Poll::Ready(Ok(()))
+    })
+    .await
+}
+
+async fn write_all<I>(io: &mut I, mut buf: &[u8]) -> io::Result<()>
+where
+    I: PollIo,
+{
+    future::poll_fn(|cx| {
+        while !buf.is_empty() {
+            match ready!(io.poll_write(cx, buf))? {
+                0 => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                len => buf = &buf[len..],
+            }
+        }
+        io.poll_flush(cx)
+    })
+    .await
+}
+
+/// The state of the transport, shared by the adaptors for the async runtimes.
+pub(crate) struct Transport<C, const STEP: u64>
+where
+    C: ConfigExt,
+{
+    framed: Framed<C, STEP>,
+    // decrypted, but not yet read
+    plaintext: Vec<u8>,
+    position: usize,
+    // encrypted, but not yet written
+    ciphertext: Vec<u8>,
+    written: usize,
+}
+
+// never pinned, the adaptors are `Unpin` when the io is
+impl<C, const STEP: u64> Unpin for Transport<C, STEP> where C: ConfigExt {}
+
+impl<C, const STEP: u64> Transport<C, STEP>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+{
+    /// Runs the handshake over `io`, the handshake payloads are empty,
+    /// the messages are length-prefixed.
+    pub async fn handshake<I>(io: &mut I, mut handshake: HandshakeState<C>) -> io::Result<Self>
+    where
+        I: PollIo,
+    {
+        let mut buffer = vec![0; LENGTH_PREFIX_LEN + MAX_FRAME_LEN];
+        while !handshake.is_finished() {
+            if handshake.is_write_turn() {
+                let (prefix, message) = buffer.split_at_mut(LENGTH_PREFIX_LEN);
+                let len = handshake.write_message(&[], message)?;
+                prefix.clone_from_slice(&(len as u16).to_be_bytes());
+                write_all(io, &buffer[..(LENGTH_PREFIX_LEN + len)]).await?;
+            } else {
+                let mut prefix = [0; LENGTH_PREFIX_LEN];
+                read_exact(io, &mut prefix).await?;
+                let message = &mut buffer[..(u16::from_be_bytes(prefix) as usize)];
+                read_exact(io, message).await?;
+                handshake.read_message(message)?;
+            }
+        }
+
+        Ok(Transport {
+            framed: handshake.finish()?.into(),
+            plaintext: Vec::new(),
+            position: 0,
+            ciphertext: Vec::new(),
+            written: 0,
+        })
+    }
+}
+
+impl<C, const STEP: u64> Transport<C, STEP>
+where
+    C: ConfigExt,
+{
+    pub fn hash(&self) -> &Hash<C> {
+        &self.framed.hash
+    }
+
+    pub fn poll_read<I>(
+        &mut self,
+        io: &mut I,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>>
+    where
+        I: PollIo,
+    {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        while self.position == self.plaintext.len() {
+            if let Some(plaintext) = self.framed.receiver.decrypt()? {
+                self.plaintext = plaintext;
+                self.position = 0;
+                continue;
+            }
+            // the partial frame stays in the receiver between the calls
+            let mut chunk = vec![0; self.framed.receiver.missing()];
+            let len = ready!(io.poll_read(cx, &mut chunk))?;
+            if len == 0 {
+                return Poll::Ready(if self.framed.receiver.pending() == 0 {
+                    Ok(0)
+                } else {
+                    Err(io::ErrorKind::UnexpectedEof.into())
+                });
+            }
+            self.framed.receiver.push(&chunk[..len]);
+        }
+
+        let plaintext = &self.plaintext[self.position..];
+        let len = plaintext.len().min(buf.len());
+        buf[..len].clone_from_slice(&plaintext[..len]);
+        self.position += len;
+        Poll::Ready(Ok(len))
+    }
+
+    // writes the pending ciphertext
+    fn poll_drain<I>(&mut self, io: &mut I, cx: &mut Context<'_>) -> Poll<io::Result<()>>
+    where
+        I: PollIo,
+    {
+        while self.written < self.ciphertext.len() {
+            match ready!(io.poll_write(cx, &self.ciphertext[self.written..]))? {
+                0 => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                len => self.written += len,
+            }
+        }
+        self.ciphertext.clear();
+        self.written = 0;
+        Poll::Ready(Ok(()))
+    }
+
+    /// Accepts at most one frame of the payload, and only when the previous one is written,
+    /// so the slow peer holds the writer back.
+    pub fn poll_write<I>(
+        &mut self,
+        io: &mut I,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>>
+    where
+        I: PollIo,
+    {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        ready!(self.poll_drain(io, cx))?;
+        let len = buf.len().min(FrameSender::<C, STEP>::MAX_PAYLOAD_LEN);
+        self.framed
+            .sender
+            .encrypt(&buf[..len], &mut self.ciphertext)?;
+        // the payload is accepted anyway, an error will be reported by the next call
+        let _ = self.poll_drain(io, cx);
+        Poll::Ready(Ok(len))
+    }
+
+    pub fn poll_flush<I>(&mut self, io: &mut I, cx: &mut Context<'_>) -> Poll<io::Result<()>>
+    where
+        I: PollIo,
+    {
+        ready!(self.poll_drain(io, cx))?;
+        io.poll_flush(cx)
+    }
+
+    pub fn poll_close<I>(&mut self, io: &mut I, cx: &mut Context<'_>) -> Poll<io::Result<()>>
+    where
+        I: PollIo,
+    {
+        ready!(self.poll_drain(io, cx))?;
+        io.poll_close(cx)
+    }
+}
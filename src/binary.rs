@@ -0,0 +1,196 @@
+use core::fmt;
+
+use generic_array::{GenericArray, ArrayLength, typenum::Bit};
+
+use super::{config::Config, hash::MixHash, kem::Kem, protocol_name::NoiseName};
+
+/// The version of the binary encoding, the first byte of each encoded value.
+pub const BINARY_VERSION: u8 = 1;
+
+/// The version, the kind of the value and the config identifier.
+pub const BINARY_HEADER_LEN: usize = 2 + CONFIG_ID_LEN;
+
+pub const CONFIG_ID_LEN: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryError {
+    BufferTooSmall,
+    /// the input is truncated or has trailing bytes
+    Length,
+    Version(u8),
+    /// the input encodes another kind of value
+    Kind,
+    ConfigMismatch,
+    /// the nonce of the encoded state does not match the type, or is exhausted
+    NonceMismatch,
+}
+
+impl fmt::Display for BinaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BinaryError::BufferTooSmall => write!(f, "the buffer is too small"),
+            BinaryError::Length => write!(f, "wrong length of the encoded value"),
+            BinaryError::Version(v) => write!(f, "unsupported version {}", v),
+            BinaryError::Kind => write!(f, "the value is of another kind"),
+            BinaryError::ConfigMismatch => write!(f, "the value is encoded with another config"),
+            BinaryError::NonceMismatch => write!(f, "the nonce does not match the type"),
+        }
+    }
+}
+
+#[cfg(any(feature = "std", test))]
+impl std::error::Error for BinaryError {}
+
+/// Compact encoding of states, outputs and ciphers, works without `alloc`.
+///
+/// The value starts with `BINARY_VERSION`, a kind byte and the config identifier,
+/// so a value encoded under another `Config` is rejected.
+pub trait Binary
+where
+    Self: Sized,
+{
+    fn encoded_len(&self) -> usize;
+
+    /// Returns the number of bytes written.
+    ///
+    /// # Errors
+    /// the buffer is too small
+    fn encode(&self, buffer: &mut [u8]) -> Result<usize, BinaryError>;
+
+    /// # Errors
+    /// the input is malformed, or was encoded by another version or with another config
+    fn decode(bytes: &[u8]) -> Result<Self, BinaryError>;
+}
+
+/// Identifies every part of the config: the DH function and the KEM, the cipher,
+/// the hash function and the nonce encoding. The keys of the handshake depend on the DH
+/// function, so a state of one DH must not continue under another.
+pub fn config_id<C>() -> [u8; CONFIG_ID_LEN]
+where
+    C: Config,
+    C::Aead: NoiseName,
+    C::MixHash: NoiseName,
+    C::Dh: NoiseName,
+{
+    let hash = C::MixHash::init_parts(&[
+        b"vru-noise config ",
+        <C::Dh as NoiseName>::NAME.as_bytes(),
+        b"+",
+        <C::Kem as Kem>::NAME.unwrap_or_default().as_bytes(),
+        b"_",
+        <C::Aead as NoiseName>::NAME.as_bytes(),
+        b"_",
+        <C::MixHash as NoiseName>::NAME.as_bytes(),
+        &[u8::from(C::BigEndianness::BOOL)],
+    ]);
+    let mut id = [0; CONFIG_ID_LEN];
+    id.clone_from_slice(&hash[..CONFIG_ID_LEN]);
+    id
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub(crate) enum Kind {
+    State = 0,
+    KeyedState = 1,
+    Output = 2,
+    Cipher = 3,
+}
+
+pub(crate) struct Writer<'a> {
+    buffer: &'a mut [u8],
+    pos: usize,
+}
+
+impl<'a> Writer<'a> {
+    pub(crate) fn new<C>(buffer: &'a mut [u8], kind: Kind) -> Result<Self, BinaryError>
+    where
+        C: Config,
+        C::Aead: NoiseName,
+        C::MixHash: NoiseName,
+        C::Dh: NoiseName,
+    {
+        let mut writer = Writer { buffer, pos: 0 };
+        writer.put(&[BINARY_VERSION, kind as u8])?;
+        writer.put(&config_id::<C>())?;
+        Ok(writer)
+    }
+
+    pub(crate) fn put(&mut self, bytes: &[u8]) -> Result<(), BinaryError> {
+        self.buffer
+            .get_mut(self.pos..(self.pos + bytes.len()))
+            .ok_or(BinaryError::BufferTooSmall)?
+            .clone_from_slice(bytes);
+        self.pos += bytes.len();
+        Ok(())
+    }
+
+    pub(crate) fn put_u64(&mut self, v: u64) -> Result<(), BinaryError> {
+        self.put(&v.to_be_bytes())
+    }
+
+    pub(crate) const fn finish(self) -> usize {
+        self.pos
+    }
+}
+
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    /// Returns the reader and the kind, which is one of `kinds`.
+    pub(crate) fn new<C>(bytes: &'a [u8], kinds: &[Kind]) -> Result<(Self, Kind), BinaryError>
+    where
+        C: Config,
+        C::Aead: NoiseName,
+        C::MixHash: NoiseName,
+        C::Dh: NoiseName,
+    {
+        let mut reader = Reader { bytes };
+        let version = reader.take(1)?[0];
+        if version != BINARY_VERSION {
+            return Err(BinaryError::Version(version));
+        }
+        let kind = reader.take(1)?[0];
+        let kind = *kinds
+            .iter()
+            .find(|k| **k as u8 == kind)
+            .ok_or(BinaryError::Kind)?;
+        if reader.take(CONFIG_ID_LEN)? != config_id::<C>() {
+            return Err(BinaryError::ConfigMismatch);
+        }
+        Ok((reader, kind))
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], BinaryError> {
+        if self.bytes.len() < len {
+            return Err(BinaryError::Length);
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    pub(crate) fn array<L>(&mut self) -> Result<GenericArray<u8, L>, BinaryError>
+    where
+        L: ArrayLength<u8>,
+    {
+        self.take(L::USIZE)
+            .map(|v| GenericArray::from_slice(v).clone())
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, BinaryError> {
+        let mut v = [0; 8];
+        v.clone_from_slice(self.take(8)?);
+        Ok(u64::from_be_bytes(v))
+    }
+
+    pub(crate) fn finish(self) -> Result<(), BinaryError> {
+        if self.bytes.is_empty() {
+            Ok(())
+        } else {
+            Err(BinaryError::Length)
+        }
+    }
+}
@@ -0,0 +1,428 @@
+use core::fmt;
+
+use {
+    chacha20poly1305::ChaCha20Poly1305,
+    generic_array::{
+        GenericArray,
+        typenum::{B0, U1},
+    },
+    hkdf::hmac::Hmac,
+    rand_core::{RngCore, CryptoRng},
+    sha2::Sha256,
+    zeroize::Zeroize,
+};
+
+use super::{
+    config::Config,
+    hash::HkdfSplitExt,
+    cipher_state::{Aead, MacMismatch, CipherError, Cipher},
+    symmetric_state::{Hash, Key, ChainingKey, SymmetricState},
+    dh::Dh,
+    secp256k1::Secp256k1,
+};
+
+/// `Noise_XK_secp256k1_ChaChaPoly_SHA256` as the BOLT 8 requires.
+pub type Bolt8Config = (Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, Secp256k1);
+
+const PROTOCOL_NAME: &str = "Noise_XK_secp256k1_ChaChaPoly_SHA256";
+const PROLOGUE: &[u8] = b"lightning";
+
+/// The only known handshake version, the first byte of each act.
+pub const BOLT8_VERSION: u8 = 0;
+
+pub const BOLT8_ACT_ONE_LEN: usize = 50;
+pub const BOLT8_ACT_TWO_LEN: usize = 50;
+pub const BOLT8_ACT_THREE_LEN: usize = 66;
+
+pub const BOLT8_TAG_LEN: usize = 16;
+
+/// The encrypted 2-byte big-endian length of the message and its tag.
+pub const BOLT8_HEADER_LEN: usize = 2 + BOLT8_TAG_LEN;
+
+pub const BOLT8_MAX_MESSAGE_LEN: usize = u16::MAX as usize;
+
+/// The keys rotate after this many nonces, the length and the body take one nonce each.
+pub const BOLT8_ROTATION_INTERVAL: u64 = 1000;
+
+const PUBLIC_LEN: usize = 33;
+
+type State = SymmetricState<Bolt8Config, Key<Bolt8Config, U1>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bolt8Error {
+    /// the act has unknown version
+    UnknownVersion(u8),
+    InvalidSecretKey,
+    InvalidPublicKey,
+    Cipher(CipherError),
+    MessageTooLong,
+    BufferTooSmall,
+}
+
+impl From<CipherError> for Bolt8Error {
+    fn from(v: CipherError) -> Self {
+        Bolt8Error::Cipher(v)
+    }
+}
+
+impl From<MacMismatch> for Bolt8Error {
+    fn from(_: MacMismatch) -> Self {
+        Bolt8Error::Cipher(CipherError::MacMismatch)
+    }
+}
+
+impl fmt::Display for Bolt8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Bolt8Error::UnknownVersion(version) => write!(f, "unknown version {}", version),
+            Bolt8Error::InvalidSecretKey => write!(f, "invalid secret key"),
+            Bolt8Error::InvalidPublicKey => write!(f, "invalid public key"),
+            Bolt8Error::Cipher(error) => write!(f, "{}", error),
+            Bolt8Error::MessageTooLong => write!(f, "message is too long"),
+            Bolt8Error::BufferTooSmall => write!(f, "buffer too small"),
+        }
+    }
+}
+
+#[cfg(any(feature = "std", test))]
+impl std::error::Error for Bolt8Error {}
+
+type Secret = <Secp256k1 as Dh>::Secret;
+
+fn secret(bytes: &[u8; 32]) -> Result<Secret, Bolt8Error> {
+    Secp256k1::secret_from_bytes(bytes).ok_or(Bolt8Error::InvalidSecretKey)
+}
+
+fn public(bytes: &[u8]) -> Result<[u8; PUBLIC_LEN], Bolt8Error> {
+    Secp256k1::public_from_bytes(bytes).ok_or(Bolt8Error::InvalidPublicKey)
+}
+
+fn dh(secret: &Secret, public: &[u8; PUBLIC_LEN]) -> Result<[u8; 32], Bolt8Error> {
+    Secp256k1::dh(secret, public).ok_or(Bolt8Error::InvalidPublicKey)
+}
+
+fn check_version(act: &[u8]) -> Result<(), Bolt8Error> {
+    match act[0] {
+        BOLT8_VERSION => Ok(()),
+        version => Err(Bolt8Error::UnknownVersion(version)),
+    }
+}
+
+fn init(
+    responder_static: &[u8; PUBLIC_LEN],
+) -> SymmetricState<Bolt8Config, ChainingKey<Bolt8Config>> {
+    SymmetricState::new(PROTOCOL_NAME)
+        .mix_hash(PROLOGUE)
+        .mix_hash(responder_static)
+}
+
+/// The initiator between the act one and the act two.
+pub struct Bolt8Initiator {
+    state: State,
+    local_static: Secret,
+    local_ephemeral: Secret,
+}
+
+impl Bolt8Initiator {
+    /// Returns the state and the act one.
+    ///
+    /// # Errors
+    /// invalid keys
+    pub fn new<R>(
+        local_static: &[u8; 32],
+        remote_static: &[u8; PUBLIC_LEN],
+        rng: &mut R,
+    ) -> Result<(Self, [u8; BOLT8_ACT_ONE_LEN]), Bolt8Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let local_ephemeral = Secp256k1::generate(rng);
+        Self::with_ephemeral_secret(secret(local_static)?, local_ephemeral, remote_static)
+    }
+
+    /// Same as `new`, but the ephemeral key is given, for test vectors.
+    ///
+    /// # Errors
+    /// invalid keys
+    pub fn with_ephemeral(
+        local_static: &[u8; 32],
+        local_ephemeral: &[u8; 32],
+        remote_static: &[u8; PUBLIC_LEN],
+    ) -> Result<(Self, [u8; BOLT8_ACT_ONE_LEN]), Bolt8Error> {
+        Self::with_ephemeral_secret(
+            secret(local_static)?,
+            secret(local_ephemeral)?,
+            remote_static,
+        )
+    }
+
+    fn with_ephemeral_secret(
+        local_static: Secret,
+        local_ephemeral: Secret,
+        remote_static: &[u8; PUBLIC_LEN],
+    ) -> Result<(Self, [u8; BOLT8_ACT_ONE_LEN]), Bolt8Error> {
+        let remote_static = public(remote_static)?;
+        let e = Secp256k1::public(&local_ephemeral);
+        let es = dh(&local_ephemeral, &remote_static)?;
+        let (state, tag) = init(&remote_static)
+            .mix_hash(&e)
+            .mix_shared_secret(es)
+            .encrypt(&mut []);
+
+        let mut act = [0; BOLT8_ACT_ONE_LEN];
+        act[0] = BOLT8_VERSION;
+        act[1..34].clone_from_slice(&e);
+        act[34..].clone_from_slice(&tag);
+        let initiator = Bolt8Initiator {
+            state,
+            local_static,
+            local_ephemeral,
+        };
+        Ok((initiator, act))
+    }
+
+    /// Reads the act two, returns the act three and the transport.
+    ///
+    /// # Errors
+    /// unknown version, invalid public key or mac mismatch
+    pub fn read_act_two(
+        self,
+        act: &[u8; BOLT8_ACT_TWO_LEN],
+    ) -> Result<([u8; BOLT8_ACT_THREE_LEN], Bolt8Transport), Bolt8Error> {
+        check_version(act)?;
+        let remote_ephemeral = public(&act[1..34])?;
+        let tag = GenericArray::from_slice(&act[34..]);
+        let ee = dh(&self.local_ephemeral, &remote_ephemeral)?;
+        let state = self
+            .state
+            .mix_hash(&remote_ephemeral)
+            .mix_shared_secret(ee)
+            .decrypt(&mut [], tag)?;
+
+        let mut act = [0; BOLT8_ACT_THREE_LEN];
+        act[0] = BOLT8_VERSION;
+        act[1..34].clone_from_slice(&Secp256k1::public(&self.local_static));
+        let (state, tag) = state.encrypt(&mut act[1..34]);
+        act[34..50].clone_from_slice(&tag);
+        let se = dh(&self.local_static, &remote_ephemeral)?;
+        let (state, tag) = state.mix_shared_secret(se).encrypt(&mut []);
+        act[50..].clone_from_slice(&tag);
+
+        Ok((act, Bolt8Transport::new::<false>(state)))
+    }
+}
+
+/// The responder between the act two and the act three.
+pub struct Bolt8Responder {
+    state: State,
+    local_ephemeral: Secret,
+}
+
+impl Bolt8Responder {
+    /// Reads the act one, returns the state and the act two.
+    ///
+    /// # Errors
+    /// invalid keys, unknown version or mac mismatch
+    pub fn new<R>(
+        local_static: &[u8; 32],
+        act: &[u8; BOLT8_ACT_ONE_LEN],
+        rng: &mut R,
+    ) -> Result<(Self, [u8; BOLT8_ACT_TWO_LEN]), Bolt8Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let local_ephemeral = Secp256k1::generate(rng);
+        Self::with_ephemeral_secret(&secret(local_static)?, local_ephemeral, act)
+    }
+
+    /// Same as `new`, but the ephemeral key is given, for test vectors.
+    ///
+    /// # Errors
+    /// invalid keys, unknown version or mac mismatch
+    pub fn with_ephemeral(
+        local_static: &[u8; 32],
+        local_ephemeral: &[u8; 32],
+        act: &[u8; BOLT8_ACT_ONE_LEN],
+    ) -> Result<(Self, [u8; BOLT8_ACT_TWO_LEN]), Bolt8Error> {
+        Self::with_ephemeral_secret(&secret(local_static)?, secret(local_ephemeral)?, act)
+    }
+
+    fn with_ephemeral_secret(
+        local_static: &Secret,
+        local_ephemeral: Secret,
+        act: &[u8; BOLT8_ACT_ONE_LEN],
+    ) -> Result<(Self, [u8; BOLT8_ACT_TWO_LEN]), Bolt8Error> {
+        check_version(act)?;
+        let remote_ephemeral = public(&act[1..34])?;
+        let tag = GenericArray::from_slice(&act[34..]);
+        let es = dh(local_static, &remote_ephemeral)?;
+        let state = init(&Secp256k1::public(local_static))
+            .mix_hash(&remote_ephemeral)
+            .mix_shared_secret(es)
+            .decrypt(&mut [], tag)?;
+
+        let e = Secp256k1::public(&local_ephemeral);
+        let ee = dh(&local_ephemeral, &remote_ephemeral)?;
+        let (state, tag) = state.mix_hash(&e).mix_shared_secret(ee).encrypt(&mut []);
+
+        let mut act = [0; BOLT8_ACT_TWO_LEN];
+        act[0] = BOLT8_VERSION;
+        act[1..34].clone_from_slice(&e);
+        act[34..].clone_from_slice(&tag);
+        let responder = Bolt8Responder {
+            state,
+            local_ephemeral,
+        };
+        Ok((responder, act))
+    }
+
+    /// Reads the act three, returns the transport and the static key of the initiator.
+    ///
+    /// # Errors
+    /// unknown version, invalid public key or mac mismatch
+    pub fn read_act_three(
+        self,
+        act: &[u8; BOLT8_ACT_THREE_LEN],
+    ) -> Result<(Bolt8Transport, [u8; PUBLIC_LEN]), Bolt8Error> {
+        check_version(act)?;
+        let mut act = *act;
+        let (remote_static, rest) = act[1..].split_at_mut(PUBLIC_LEN);
+        let (tag, last_tag) = rest.split_at(BOLT8_TAG_LEN);
+        let state = self
+            .state
+            .decrypt(remote_static, GenericArray::from_slice(tag))?;
+        let remote_static = public(remote_static)?;
+        let se = dh(&self.local_ephemeral, &remote_static)?;
+        let state = state
+            .mix_shared_secret(se)
+            .decrypt(&mut [], GenericArray::from_slice(last_tag))?;
+
+        Ok((Bolt8Transport::new::<true>(state), remote_static))
+    }
+}
+
+pub struct Bolt8Transport {
+    pub sender: Bolt8Cipher<true>,
+    pub receiver: Bolt8Cipher<false>,
+    pub hash: Hash<Bolt8Config>,
+}
+
+impl Bolt8Transport {
+    fn new<const SWAP: bool>(state: SymmetricState<Bolt8Config, Key<Bolt8Config, U1>>) -> Self {
+        let chaining_key = *state.chaining_key();
+        let mut keys = state.finish_raw::<1, SWAP>();
+        let transport = Bolt8Transport {
+            sender: Bolt8Cipher::new(&chaining_key, &keys.sender),
+            receiver: Bolt8Cipher::new(&chaining_key, &keys.receiver),
+            hash: keys.hash,
+        };
+        keys.sender.zeroize();
+        keys.receiver.zeroize();
+        transport
+    }
+}
+
+/// The cipher of one direction, the key rotates using the chaining key
+/// each `BOLT8_ROTATION_INTERVAL` nonces.
+pub struct Bolt8Cipher<const SEND: bool> {
+    cipher: Cipher<Bolt8Config, 1, SEND>,
+    chaining_key: ChainingKey<Bolt8Config>,
+    key: Aead<Bolt8Config>,
+}
+
+impl<const SEND: bool> Drop for Bolt8Cipher<SEND> {
+    fn drop(&mut self) {
+        self.chaining_key.zeroize();
+        self.key.zeroize();
+    }
+}
+
+impl<const SEND: bool> Bolt8Cipher<SEND> {
+    fn new(chaining_key: &ChainingKey<Bolt8Config>, key: &Aead<Bolt8Config>) -> Self {
+        Bolt8Cipher {
+            cipher: Cipher::new(key),
+            chaining_key: *chaining_key,
+            key: *key,
+        }
+    }
+
+    /// The nonce of the current key.
+    pub const fn nonce(&self) -> u64 {
+        self.cipher.nonce()
+    }
+
+    fn rotate(&mut self) {
+        if self.cipher.nonce() < BOLT8_ROTATION_INTERVAL {
+            return;
+        }
+        let (mut chaining_key, mut key) = <<Bolt8Config as Config>::HkdfSplit as HkdfSplitExt<
+            ChaCha20Poly1305,
+        >>::split_2(&self.chaining_key, &self.key);
+        self.chaining_key.clone_from_slice(&chaining_key);
+        self.key.clone_from_slice(&key);
+        chaining_key.zeroize();
+        key.zeroize();
+        self.cipher = Cipher::new(&self.key);
+    }
+}
+
+impl Bolt8Cipher<true> {
+    /// Writes the header and the body into `out`, returns the length.
+    ///
+    /// # Errors
+    /// the message is too long or the buffer is too small
+    pub fn encrypt(&mut self, message: &[u8], out: &mut [u8]) -> Result<usize, Bolt8Error> {
+        if message.len() > BOLT8_MAX_MESSAGE_LEN {
+            return Err(Bolt8Error::MessageTooLong);
+        }
+        let len = BOLT8_HEADER_LEN + message.len() + BOLT8_TAG_LEN;
+        if out.len() < len {
+            return Err(Bolt8Error::BufferTooSmall);
+        }
+
+        let (header, body) = out[..len].split_at_mut(BOLT8_HEADER_LEN);
+        header[..2].clone_from_slice(&(message.len() as u16).to_be_bytes());
+        let tag = self.cipher.encrypt(&[], &mut header[..2])?;
+        header[2..].clone_from_slice(&tag);
+        self.rotate();
+
+        let (body, body_tag) = body.split_at_mut(message.len());
+        body.clone_from_slice(message);
+        let tag = self.cipher.encrypt(&[], body)?;
+        body_tag.clone_from_slice(&tag);
+        self.rotate();
+
+        Ok(len)
+    }
+}
+
+impl Bolt8Cipher<false> {
+    /// Returns the length of the body excluding its tag.
+    ///
+    /// # Errors
+    /// mac mismatch
+    pub fn decrypt_header(&mut self, header: &[u8; BOLT8_HEADER_LEN]) -> Result<usize, Bolt8Error> {
+        let mut length = [header[0], header[1]];
+        let tag = GenericArray::from_slice(&header[2..]);
+        self.cipher.decrypt(&[], &mut length, tag)?;
+        self.rotate();
+        Ok(u16::from_be_bytes(length).into())
+    }
+
+    /// The `body` is the ciphertext with the tag, the message is decrypted in place,
+    /// returns its length.
+    ///
+    /// # Errors
+    /// mac mismatch or the body is shorter than the tag
+    pub fn decrypt_body(&mut self, body: &mut [u8]) -> Result<usize, Bolt8Error> {
+        let len = body
+            .len()
+            .checked_sub(BOLT8_TAG_LEN)
+            .ok_or(Bolt8Error::BufferTooSmall)?;
+        let (body, tag) = body.split_at_mut(len);
+        self.cipher
+            .decrypt(&[], body, GenericArray::from_slice(tag))?;
+        self.rotate();
+        Ok(len)
+    }
+}
@@ -1,8 +1,9 @@
-use core::fmt;
+use core::{fmt, num::NonZeroU64};
 
 use {
     aead::{KeyInit, KeySizeUser, AeadInPlace, AeadCore},
     generic_array::GenericArray,
+    zeroize::{Zeroize, Zeroizing},
 };
 
 use super::config::{Config, ConfigExt};
@@ -19,14 +20,113 @@ impl fmt::Display for MacMismatch {
     }
 }
 
-#[cfg(feature = "std")]
+#[cfg(any(feature = "std", test))]
 impl std::error::Error for MacMismatch {}
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CipherError {
+    MacMismatch,
+    /// the next nonce would reach 2^64-1, which is reserved for rekey
+    NonceExhausted,
+    /// the nonce is already received
+    Duplicate,
+    /// the nonce is behind the replay window
+    TooOld,
+}
+
+impl From<MacMismatch> for CipherError {
+    fn from(_: MacMismatch) -> Self {
+        CipherError::MacMismatch
+    }
+}
+
+impl fmt::Display for CipherError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CipherError::MacMismatch => write!(f, "mac mismatch"),
+            CipherError::NonceExhausted => write!(f, "nonce exhausted"),
+            CipherError::Duplicate => write!(f, "duplicate nonce"),
+            CipherError::TooOld => write!(f, "nonce is too old"),
+        }
+    }
+}
+
+#[cfg(any(feature = "std", test))]
+impl std::error::Error for CipherError {}
+
+/// Sliding window of received nonces, `WORDS * 64` bits wide,
+/// the default is 2048 bits like WireGuard has.
+#[derive(Clone)]
+pub struct ReplayWindow<const WORDS: usize = 32> {
+    bitmap: [u64; WORDS],
+    // the highest received nonce plus one, zero when nothing is received
+    next: u64,
+}
+
+impl<const WORDS: usize> Default for ReplayWindow<WORDS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const WORDS: usize> ReplayWindow<WORDS> {
+    pub const BITS: u64 = WORDS as u64 * 64;
+
+    pub const fn new() -> Self {
+        ReplayWindow {
+            bitmap: [0; WORDS],
+            next: 0,
+        }
+    }
+
+    fn bit(n: u64) -> (usize, u64) {
+        let position = n % Self::BITS;
+        ((position / 64) as usize, 1 << (position % 64))
+    }
+
+    /// # Errors
+    /// the nonce is duplicate or too old
+    pub fn check(&self, n: u64) -> Result<(), CipherError> {
+        if n >= self.next {
+            Ok(())
+        } else if self.next - n > Self::BITS {
+            Err(CipherError::TooOld)
+        } else {
+            let (word, mask) = Self::bit(n);
+            if self.bitmap[word] & mask != 0 {
+                Err(CipherError::Duplicate)
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    /// Marks the nonce as received, should be called only after the message is authenticated.
+    pub fn update(&mut self, n: u64) {
+        if n >= self.next {
+            if n - self.next >= Self::BITS {
+                self.bitmap = [0; WORDS];
+            } else {
+                for m in self.next..n {
+                    let (word, mask) = Self::bit(m);
+                    self.bitmap[word] &= !mask;
+                }
+            }
+            self.next = n.saturating_add(1);
+        }
+        let (word, mask) = Self::bit(n);
+        self.bitmap[word] |= mask;
+    }
+}
+
 pub struct CipherInner<C, const SEND: bool>
 where
     C: Config,
 {
     key: C::Aead,
+    // kept to export the cipher, the AEAD does not give the key back;
+    // it costs one more key per cipher, zeroized on drop
+    raw_key: Zeroizing<Aead<C>>,
 }
 
 impl<C, const SEND: bool> Clone for CipherInner<C, SEND>
@@ -37,10 +137,46 @@ where
     fn clone(&self) -> Self {
         CipherInner {
             key: self.key.clone(),
+            raw_key: self.raw_key.clone(),
         }
     }
 }
 
+impl<C, const SEND: bool> CipherInner<C, SEND>
+where
+    C: Config,
+{
+    fn new(key: &Aead<C>) -> Self {
+        CipherInner {
+            key: C::Aead::new(key),
+            raw_key: Zeroizing::new(key.clone()),
+        }
+    }
+
+    fn swap<const SWAPPED: bool>(self) -> CipherInner<C, SWAPPED> {
+        CipherInner {
+            key: self.key,
+            raw_key: self.raw_key,
+        }
+    }
+}
+
+impl<C, const SEND: bool> CipherInner<C, SEND>
+where
+    C: ConfigExt,
+{
+    /// `REKEY(k)`, the section 4.2 of the Noise spec.
+    /// Encrypts zeros with the nonce 2^64-1 and takes the result as the new key.
+    pub fn rekey(&mut self) {
+        let mut key = Aead::<C>::default();
+        self.key
+            .encrypt_in_place_detached(&C::prepare_nonce(u64::MAX), &[], &mut key)
+            .unwrap();
+        *self = CipherInner::new(&key);
+        key.zeroize();
+    }
+}
+
 impl<C> CipherInner<C, true>
 where
     C: ConfigExt,
@@ -74,12 +210,24 @@ where
     }
 }
 
+/// The exported `Cipher`, the key is zeroized on drop.
+#[derive(Clone)]
+pub struct CipherRaw<C>
+where
+    C: Config,
+{
+    pub key: Zeroizing<Aead<C>>,
+    pub nonce: u64,
+    pub rekey_interval: Option<NonZeroU64>,
+}
+
 pub struct Cipher<C, const STEP: u64, const SEND: bool>
 where
     C: Config,
 {
     inner: CipherInner<C, SEND>,
     nonce: u64,
+    rekey_interval: Option<NonZeroU64>,
 }
 
 impl<C, const STEP: u64, const SEND: bool> Clone for Cipher<C, STEP, SEND>
@@ -91,6 +239,7 @@ where
         Cipher {
             inner: self.inner.clone(),
             nonce: self.nonce,
+            rekey_interval: self.rekey_interval,
         }
     }
 }
@@ -101,13 +250,42 @@ where
 {
     pub(crate) fn new(key: &Aead<C>) -> Self {
         Cipher {
-            inner: CipherInner {
-                key: C::Aead::new(key),
-            },
+            inner: CipherInner::new(key),
             nonce: 0,
+            rekey_interval: None,
         }
     }
 
+    fn raw(&self) -> CipherRaw<C> {
+        CipherRaw {
+            key: self.inner.raw_key.clone(),
+            nonce: self.nonce,
+            rekey_interval: self.rekey_interval,
+        }
+    }
+
+    /// The `STEP` and the direction should be the same as the exported cipher had.
+    pub fn import(raw: &CipherRaw<C>) -> Self {
+        Cipher {
+            inner: CipherInner::new(&raw.key),
+            nonce: raw.nonce,
+            rekey_interval: raw.rekey_interval,
+        }
+    }
+
+    /// Rekey automatically after each `messages` messages, like BOLT 8 does every 1000.
+    /// The peer should use the same interval.
+    pub fn with_rekey_interval(self, messages: NonZeroU64) -> Self {
+        Cipher {
+            rekey_interval: Some(messages),
+            ..self
+        }
+    }
+
+    pub const fn rekey_interval(&self) -> Option<NonZeroU64> {
+        self.rekey_interval
+    }
+
     const fn inner(&self) -> &CipherInner<C, SEND> {
         &self.inner
     }
@@ -115,20 +293,80 @@ where
     pub const fn nonce(&self) -> u64 {
         self.nonce
     }
+
+    /// `SetNonce()`, the section 5.1 of the Noise spec.
+    /// Setting the nonce of the sender to a used value breaks the security.
+    pub fn set_nonce(&mut self, nonce: u64) {
+        self.nonce = nonce;
+    }
+
+    /// The actual nonce of the message `n`, the messages occupy `STEP` nonces each,
+    /// so the last one must be below 2^64-1.
+    const fn actual_nonce(n: u64) -> Result<u64, CipherError> {
+        match n.checked_mul(STEP) {
+            Some(nonce) if u64::MAX - nonce >= STEP => Ok(nonce),
+            _ => Err(CipherError::NonceExhausted),
+        }
+    }
+}
+
+impl<C, const STEP: u64> Cipher<C, STEP, true>
+where
+    C: Config,
+{
+    /// The key and the nonce, to resume the session in another process.
+    /// The sender is consumed, otherwise it and the imported one would repeat the nonces.
+    pub fn export(self) -> CipherRaw<C> {
+        self.raw()
+    }
+}
+
+impl<C, const STEP: u64> Cipher<C, STEP, false>
+where
+    C: Config,
+{
+    /// The key and the nonce, to resume the session in another process.
+    pub fn export(&self) -> CipherRaw<C> {
+        self.raw()
+    }
+}
+
+impl<C, const STEP: u64, const SEND: bool> Cipher<C, STEP, SEND>
+where
+    C: ConfigExt,
+{
+    /// `Rekey()`, the section 5.1 of the Noise spec, the nonce is not reset.
+    pub fn rekey(&mut self) {
+        self.inner.rekey();
+    }
+
+    fn advance(&mut self) {
+        self.nonce += 1;
+        if let Some(interval) = self.rekey_interval {
+            if self.nonce % interval.get() == 0 {
+                self.rekey();
+            }
+        }
+    }
 }
 
 impl<C, const SEND: bool> Cipher<C, 2, SEND>
 where
     C: Config,
 {
+    /// # Errors
+    /// nonce exhausted
+    ///
     /// # Panics
     ///
     /// when `data` slice is too long (gigabytes)
-    pub fn link(&mut self, nonce: u64, data: &mut [u8]) {
+    pub fn link(&mut self, nonce: u64, data: &mut [u8]) -> Result<(), CipherError> {
+        let nonce = Self::actual_nonce(nonce)? + 1;
         self.inner
             .key
-            .encrypt_in_place_detached(&C::prepare_nonce(nonce * 2 + 1), &[], data)
+            .encrypt_in_place_detached(&C::prepare_nonce(nonce), &[], data)
             .unwrap();
+        Ok(())
     }
 }
 
@@ -136,19 +374,25 @@ impl<const STEP: u64, C> Cipher<C, STEP, true>
 where
     C: Config,
 {
-    pub fn encrypt(&mut self, ad: &[u8], buffer: &mut [u8]) -> Tag<C> {
-        let tag = self.inner().encrypt(self.nonce * STEP, ad, buffer);
-        self.nonce += 1;
-        tag
+    /// # Errors
+    /// nonce exhausted
+    ///
+    /// # Panics
+    ///
+    /// when `buffer` slice is too long (gigabytes)
+    pub fn encrypt(&mut self, ad: &[u8], buffer: &mut [u8]) -> Result<Tag<C>, CipherError> {
+        let nonce = Self::actual_nonce(self.nonce)?;
+        let tag = self.inner().encrypt(nonce, ad, buffer);
+        self.advance();
+        Ok(tag)
     }
 
     // #[cfg(test)]
     pub fn swap(self) -> Cipher<C, STEP, false> {
         Cipher {
-            inner: CipherInner {
-                key: self.inner.key,
-            },
+            inner: self.inner.swap(),
             nonce: self.nonce,
+            rekey_interval: self.rekey_interval,
         }
     }
 }
@@ -158,25 +402,176 @@ where
     C: Config,
 {
     /// # Errors
-    /// mac mismatch
+    /// mac mismatch or nonce exhausted
     pub fn decrypt(
         &mut self,
         ad: &[u8],
         buffer: &mut [u8],
         tag: &Tag<C>,
-    ) -> Result<(), MacMismatch> {
-        self.inner()
-            .decrypt(self.nonce * STEP, ad, buffer, tag)
-            .map(|()| self.nonce += 1)
+    ) -> Result<(), CipherError> {
+        let nonce = Self::actual_nonce(self.nonce)?;
+        self.inner().decrypt(nonce, ad, buffer, tag)?;
+        self.advance();
+        Ok(())
+    }
+
+    /// Decrypts the message with the explicit nonce `n`, the messages may come out of order.
+    /// The `window` rejects replayed messages. The automatic rekey is not applied.
+    ///
+    /// # Errors
+    /// mac mismatch, nonce exhausted, duplicate or too old nonce
+    pub fn decrypt_at<const WORDS: usize>(
+        &mut self,
+        window: &mut ReplayWindow<WORDS>,
+        n: u64,
+        ad: &[u8],
+        buffer: &mut [u8],
+        tag: &Tag<C>,
+    ) -> Result<(), CipherError> {
+        let nonce = Self::actual_nonce(n)?;
+        window.check(n)?;
+        self.inner().decrypt(nonce, ad, buffer, tag)?;
+        window.update(n);
+        self.nonce = self.nonce.max(n + 1);
+        Ok(())
     }
 
     // #[cfg(test)]
     pub fn swap(self) -> Cipher<C, STEP, true> {
         Cipher {
-            inner: CipherInner {
-                key: self.inner.key,
-            },
+            inner: self.inner.swap(),
             nonce: self.nonce,
+            rekey_interval: self.rekey_interval,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_m {
+    use core::num::NonZeroU64;
+    use alloc::string::String;
+
+    use aead::KeySizeUser;
+    use generic_array::{GenericArray, typenum::Unsigned};
+    use serde::{Serialize, Deserialize};
+    use zeroize::{Zeroize, Zeroizing};
+
+    use super::{CipherRaw, Config};
+
+    #[derive(Serialize, Deserialize)]
+    struct Inner {
+        key: String,
+        nonce: u64,
+        rekey_interval: Option<NonZeroU64>,
+    }
+
+    impl Drop for Inner {
+        fn drop(&mut self) {
+            self.key.zeroize();
+        }
+    }
+
+    impl<C> CipherRaw<C>
+    where
+        C: Config,
+    {
+        fn as_inner(&self) -> Inner {
+            Inner {
+                key: hex::encode(&*self.key),
+                nonce: self.nonce,
+                rekey_interval: self.rekey_interval,
+            }
+        }
+    }
+
+    impl<C> TryFrom<Inner> for CipherRaw<C>
+    where
+        C: Config,
+    {
+        type Error = hex::FromHexError;
+
+        fn try_from(inner: Inner) -> Result<Self, Self::Error> {
+            let key = Zeroizing::new(hex::decode(&inner.key)?);
+            if key.len() != <<C::Aead as KeySizeUser>::KeySize as Unsigned>::USIZE {
+                return Err(hex::FromHexError::InvalidStringLength);
+            }
+            Ok(CipherRaw {
+                key: Zeroizing::new(GenericArray::from_slice(&key).clone()),
+                nonce: inner.nonce,
+                rekey_interval: inner.rekey_interval,
+            })
+        }
+    }
+
+    impl<'de, C> Deserialize<'de> for CipherRaw<C>
+    where
+        C: Config,
+    {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            Inner::deserialize(deserializer)?
+                .try_into()
+                .map_err(serde::de::Error::custom)
+        }
+    }
+
+    impl<C> Serialize for CipherRaw<C>
+    where
+        C: Config,
+    {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            self.as_inner().serialize(serializer)
+        }
+    }
+}
+
+mod binary_m {
+    use core::num::NonZeroU64;
+
+    use aead::KeySizeUser;
+    use generic_array::typenum::Unsigned;
+    use zeroize::Zeroizing;
+
+    use super::{CipherRaw, Config};
+    use crate::{
+        binary::{Binary, BinaryError, Kind, Reader, Writer, BINARY_HEADER_LEN},
+        protocol_name::NoiseName,
+    };
+
+    impl<C> Binary for CipherRaw<C>
+    where
+        C: Config,
+        C::Aead: NoiseName,
+        C::MixHash: NoiseName,
+        C::Dh: NoiseName,
+    {
+        fn encoded_len(&self) -> usize {
+            BINARY_HEADER_LEN + <<C::Aead as KeySizeUser>::KeySize as Unsigned>::USIZE + 16
+        }
+
+        /// The missing rekey interval is encoded as zero.
+        fn encode(&self, buffer: &mut [u8]) -> Result<usize, BinaryError> {
+            let mut writer = Writer::new::<C>(buffer, Kind::Cipher)?;
+            writer.put(&self.key)?;
+            writer.put_u64(self.nonce)?;
+            writer.put_u64(self.rekey_interval.map_or(0, NonZeroU64::get))?;
+            Ok(writer.finish())
+        }
+
+        fn decode(bytes: &[u8]) -> Result<Self, BinaryError> {
+            let (mut reader, _) = Reader::new::<C>(bytes, &[Kind::Cipher])?;
+            let raw = CipherRaw {
+                key: Zeroizing::new(reader.array()?),
+                nonce: reader.u64()?,
+                rekey_interval: NonZeroU64::new(reader.u64()?),
+            };
+            reader.finish()?;
+            Ok(raw)
         }
     }
 }
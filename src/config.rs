@@ -5,13 +5,21 @@ use {
     hkdf::HmacImpl,
 };
 
-use super::hash::{MixHash, HkdfSplitExt};
+use super::{
+    hash::{MixHash, HkdfSplitExt},
+    dh::Dh,
+    kem::Kem,
+};
 
 pub trait Config {
     type BigEndianness: Bit; // LittleEndian for chacha20poly1305 and BigEndian for Aes256Gcm
     type Aead: KeyInit + AeadInPlace;
     type MixHash: MixHash;
     type HkdfSplit: HkdfSplitExt<Self::Aead, L = <Self::MixHash as MixHash>::L>;
+    // implements `Dh`, or `()` when only `SymmetricState` is used
+    type Dh;
+    // `()` unless the `hfs` patterns are used
+    type Kem: Kem;
 }
 
 impl<I, D, E, A> Config for (I, D, E, A)
@@ -26,6 +34,43 @@ where
     type Aead = A;
     type MixHash = D;
     type HkdfSplit = (D, I);
+    type Dh = ();
+    type Kem = ();
+}
+
+impl<I, D, E, A, X> Config for (I, D, E, A, X)
+where
+    I: HmacImpl<D>,
+    (D, I): HkdfSplitExt<A, L = <D as MixHash>::L>,
+    D: OutputSizeUser + MixHash,
+    E: Bit,
+    A: KeyInit + AeadInPlace,
+    X: Dh,
+{
+    type BigEndianness = E;
+    type Aead = A;
+    type MixHash = D;
+    type HkdfSplit = (D, I);
+    type Dh = X;
+    type Kem = ();
+}
+
+impl<I, D, E, A, X, K> Config for (I, D, E, A, X, K)
+where
+    I: HmacImpl<D>,
+    (D, I): HkdfSplitExt<A, L = <D as MixHash>::L>,
+    D: OutputSizeUser + MixHash,
+    E: Bit,
+    A: KeyInit + AeadInPlace,
+    X: Dh,
+    K: Kem,
+{
+    type BigEndianness = E;
+    type Aead = A;
+    type MixHash = D;
+    type HkdfSplit = (D, I);
+    type Dh = X;
+    type Kem = K;
 }
 
 pub trait ConfigExt
@@ -0,0 +1,63 @@
+use {
+    curve25519_dalek::montgomery::MontgomeryPoint,
+    rand_core::{RngCore, CryptoRng},
+    zeroize::Zeroize,
+};
+
+use super::dh::Dh;
+
+/// The X25519 function from RFC 7748, `25519` in protocol names.
+pub struct X25519;
+
+/// The scalar is stored unclamped, it is clamped on every multiplication.
+#[derive(Clone)]
+pub struct X25519Secret([u8; 32]);
+
+impl X25519Secret {
+    pub const fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl Drop for X25519Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Dh for X25519 {
+    const DHLEN: usize = 32;
+
+    type Secret = X25519Secret;
+    type Public = [u8; 32];
+    type SharedSecret = [u8; 32];
+
+    fn generate<R>(rng: &mut R) -> Self::Secret
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut secret = X25519Secret([0; 32]);
+        rng.fill_bytes(&mut secret.0);
+        secret
+    }
+
+    fn secret_from_bytes(bytes: &[u8]) -> Option<Self::Secret> {
+        bytes.try_into().ok().map(X25519Secret)
+    }
+
+    fn public(secret: &Self::Secret) -> Self::Public {
+        MontgomeryPoint::mul_base_clamped(secret.0).to_bytes()
+    }
+
+    fn public_from_bytes(bytes: &[u8]) -> Option<Self::Public> {
+        bytes.try_into().ok()
+    }
+
+    /// Returns `None` for a low order point, the shared secret is zero then.
+    fn dh(secret: &Self::Secret, public: &Self::Public) -> Option<Self::SharedSecret> {
+        let shared = MontgomeryPoint(*public).mul_clamped(secret.0).to_bytes();
+        // no early exit, the shared secret is not leaked through the timing
+        let zero = shared.iter().fold(0, |acc, byte| acc | byte) == 0;
+        (!zero).then_some(shared)
+    }
+}
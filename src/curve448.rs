@@ -0,0 +1,58 @@
+use {
+    rand_core::{RngCore, CryptoRng},
+    zeroize::Zeroize,
+};
+
+use super::dh::Dh;
+
+/// The X448 function from RFC 7748, `448` in protocol names.
+pub struct X448;
+
+/// The scalar is stored unclamped, it is clamped on every multiplication.
+pub struct X448Secret([u8; 56]);
+
+impl X448Secret {
+    pub const fn as_bytes(&self) -> &[u8; 56] {
+        &self.0
+    }
+}
+
+impl Drop for X448Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Dh for X448 {
+    const DHLEN: usize = 56;
+
+    type Secret = X448Secret;
+    type Public = [u8; 56];
+    type SharedSecret = [u8; 56];
+
+    fn generate<R>(rng: &mut R) -> Self::Secret
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut secret = X448Secret([0; 56]);
+        rng.fill_bytes(&mut secret.0);
+        secret
+    }
+
+    fn secret_from_bytes(bytes: &[u8]) -> Option<Self::Secret> {
+        bytes.try_into().ok().map(X448Secret)
+    }
+
+    fn public(secret: &Self::Secret) -> Self::Public {
+        x448::x448_unchecked(secret.0, x448::X448_BASEPOINT_BYTES)
+    }
+
+    fn public_from_bytes(bytes: &[u8]) -> Option<Self::Public> {
+        x448::PublicKey::from_bytes(bytes).and_then(|_| bytes.try_into().ok())
+    }
+
+    /// Returns `None` for low order points.
+    fn dh(secret: &Self::Secret, public: &Self::Public) -> Option<Self::SharedSecret> {
+        x448::x448(secret.0, *public)
+    }
+}
@@ -0,0 +1,27 @@
+use {
+    rand_core::{RngCore, CryptoRng},
+    zeroize::Zeroize,
+};
+
+/// Diffie-Hellman function, the section 4.1 of the Noise spec.
+pub trait Dh {
+    /// Size of the public key as it appears in handshake messages.
+    const DHLEN: usize;
+
+    type Secret;
+    type Public: AsRef<[u8]> + Clone;
+    type SharedSecret: AsRef<[u8]> + Zeroize;
+
+    fn generate<R>(rng: &mut R) -> Self::Secret
+    where
+        R: RngCore + CryptoRng;
+
+    fn secret_from_bytes(bytes: &[u8]) -> Option<Self::Secret>;
+
+    fn public(secret: &Self::Secret) -> Self::Public;
+
+    fn public_from_bytes(bytes: &[u8]) -> Option<Self::Public>;
+
+    /// Returns `None` if the public key is invalid.
+    fn dh(secret: &Self::Secret, public: &Self::Public) -> Option<Self::SharedSecret>;
+}
@@ -0,0 +1,303 @@
+use {
+    aead::AeadCore,
+    generic_array::{
+        GenericArray,
+        typenum::{self, Unsigned},
+    },
+    zeroize::Zeroize,
+};
+
+use super::{
+    config::{Config, ConfigExt},
+    cipher_state::{CipherError, Tag},
+    symmetric_state::{SymmetricState, ChainingKey, Key, Hash, Output, OutputRaw},
+    protocol_name::ProtocolName,
+};
+
+enum Inner<C>
+where
+    C: Config,
+{
+    Empty(SymmetricState<C, ChainingKey<C>>),
+    Keyed(SymmetricState<C, Key<C, typenum::U0>>, u64),
+}
+
+/// Same as `SymmetricState`, but the nonce and whether the key is mixed are known at runtime,
+/// so states of different steps have the same type.
+pub struct DynSymmetricState<C>
+where
+    C: Config,
+{
+    inner: Inner<C>,
+}
+
+impl<C> From<SymmetricState<C, ChainingKey<C>>> for DynSymmetricState<C>
+where
+    C: Config,
+{
+    fn from(state: SymmetricState<C, ChainingKey<C>>) -> Self {
+        DynSymmetricState {
+            inner: Inner::Empty(state),
+        }
+    }
+}
+
+impl<C, N> From<SymmetricState<C, Key<C, N>>> for DynSymmetricState<C>
+where
+    C: Config,
+    N: Unsigned,
+{
+    fn from(state: SymmetricState<C, Key<C, N>>) -> Self {
+        DynSymmetricState {
+            inner: Inner::Keyed(state.forget_nonce(), N::U64),
+        }
+    }
+}
+
+impl<C> DynSymmetricState<C>
+where
+    C: ConfigExt,
+{
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        SymmetricState::new(name).into()
+    }
+
+    #[must_use]
+    pub fn from_protocol_name(name: &ProtocolName<'_>) -> Self {
+        SymmetricState::from_protocol_name(name).into()
+    }
+
+    const fn keyed(state: SymmetricState<C, Key<C, typenum::U0>>, nonce: u64) -> Self {
+        DynSymmetricState {
+            inner: Inner::Keyed(state, nonce),
+        }
+    }
+
+    pub fn hash(&self) -> Hash<C> {
+        match &self.inner {
+            Inner::Empty(state) => state.hash(),
+            Inner::Keyed(state, _) => state.hash(),
+        }
+    }
+
+    pub const fn has_key(&self) -> bool {
+        matches!(self.inner, Inner::Keyed(..))
+    }
+
+    /// The nonce of the next encryption, `None` before a key is mixed.
+    pub const fn nonce(&self) -> Option<u64> {
+        match self.inner {
+            Inner::Empty(_) => None,
+            Inner::Keyed(_, n) => Some(n),
+        }
+    }
+
+    /// Size of the tag `encrypt` appends, zero before a key is mixed.
+    pub const fn tag_len(&self) -> usize {
+        match self.inner {
+            Inner::Empty(_) => 0,
+            Inner::Keyed(..) => <<C::Aead as AeadCore>::TagSize as Unsigned>::USIZE,
+        }
+    }
+
+    #[must_use]
+    pub fn mix_hash(self, data: &[u8]) -> Self {
+        match self.inner {
+            Inner::Empty(state) => state.mix_hash(data).into(),
+            Inner::Keyed(state, n) => Self::keyed(state.mix_hash(data), n),
+        }
+    }
+
+    pub(crate) fn mix_key(self, data: &[u8]) -> Self {
+        match self.inner {
+            Inner::Empty(state) => Self::keyed(state.mix_key(data), 0),
+            Inner::Keyed(state, _) => Self::keyed(state.mix_key(data), 0),
+        }
+    }
+
+    #[must_use]
+    pub fn mix_shared_secret<S>(self, data: S) -> Self
+    where
+        S: AsRef<[u8]> + Zeroize,
+    {
+        match self.inner {
+            Inner::Empty(state) => Self::keyed(state.mix_shared_secret(data), 0),
+            Inner::Keyed(state, _) => Self::keyed(state.mix_shared_secret(data), 0),
+        }
+    }
+
+    #[must_use]
+    pub fn mix_psk<S>(self, data: S) -> Self
+    where
+        S: AsRef<[u8]> + Zeroize,
+    {
+        match self.inner {
+            Inner::Empty(state) => Self::keyed(state.mix_psk(data), 0),
+            Inner::Keyed(state, _) => Self::keyed(state.mix_psk(data), 0),
+        }
+    }
+
+    /// Returns `None` before a key is mixed, the data stays plaintext and is only hashed,
+    /// the section 5.2 of the Noise spec.
+    ///
+    /// # Errors
+    /// the nonce reached 2^64-1, which is reserved for rekey
+    ///
+    /// # Panics
+    ///
+    /// when `data` slice is too long (gigabytes)
+    pub fn encrypt(self, data: &mut [u8]) -> Result<(Self, Option<Tag<C>>), CipherError> {
+        match self.inner {
+            Inner::Empty(state) => Ok((state.mix_hash(data).into(), None)),
+            Inner::Keyed(_, u64::MAX) => Err(CipherError::NonceExhausted),
+            Inner::Keyed(mut state, n) => {
+                let tag = state.encrypt_at(n, data);
+                Ok((Self::keyed(state, n + 1), Some(tag)))
+            }
+        }
+    }
+
+    /// The `tag` should be given if and only if the key is mixed.
+    ///
+    /// # Errors
+    /// mac mismatch, the tag presence does not match, or the nonce reached 2^64-1
+    pub fn decrypt(self, data: &mut [u8], tag: Option<&Tag<C>>) -> Result<Self, CipherError> {
+        match (self.inner, tag) {
+            (Inner::Empty(state), None) => Ok(state.mix_hash(data).into()),
+            (Inner::Keyed(_, u64::MAX), Some(_)) => Err(CipherError::NonceExhausted),
+            (Inner::Keyed(mut state, n), Some(tag)) => {
+                state.decrypt_at(n, data, tag)?;
+                Ok(Self::keyed(state, n + 1))
+            }
+            _ => Err(CipherError::MacMismatch),
+        }
+    }
+
+    /// The `buffer` holds the plaintext followed by `tag_len` bytes for the tag.
+    pub(crate) fn encrypt_and_hash(self, buffer: &mut [u8]) -> Result<Self, CipherError> {
+        let (data, tag_space) = buffer.split_at_mut(buffer.len() - self.tag_len());
+        let (state, tag) = self.encrypt(data)?;
+        if let Some(tag) = tag {
+            tag_space.clone_from_slice(&tag);
+        }
+        Ok(state)
+    }
+
+    /// The `buffer` holds the ciphertext followed by `tag_len` bytes of the tag.
+    pub(crate) fn decrypt_and_hash(self, buffer: &mut [u8]) -> Result<Self, CipherError> {
+        let (data, tag) = buffer.split_at_mut(buffer.len() - self.tag_len());
+        let tag = self.has_key().then(|| GenericArray::from_slice(tag));
+        self.decrypt(data, tag)
+    }
+
+    pub fn finish_raw<const STEP: u64, const SWAP: bool>(self) -> OutputRaw<C> {
+        match self.inner {
+            Inner::Empty(state) => state.finish_raw::<STEP, SWAP>(),
+            Inner::Keyed(state, _) => state.finish_raw::<STEP, SWAP>(),
+        }
+    }
+
+    pub fn finish<const STEP: u64, const SWAP: bool>(self) -> Output<C, STEP> {
+        match self.inner {
+            Inner::Empty(state) => state.finish::<STEP, SWAP>(),
+            Inner::Keyed(state, _) => state.finish::<STEP, SWAP>(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_m {
+    use serde::{Serialize, Deserialize};
+
+    use super::{DynSymmetricState, Inner, Config, SymmetricState};
+    use crate::symmetric_state::serde_m::{Inner as EmptyInner, KeyedInner};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum DynInner {
+        Keyed(KeyedInner),
+        Empty(EmptyInner),
+    }
+
+    impl<'de, C> Deserialize<'de> for DynSymmetricState<C>
+    where
+        C: Config,
+    {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            let inner = match DynInner::deserialize(deserializer)? {
+                DynInner::Keyed(inner) if inner.nonce == u64::MAX => {
+                    return Err(serde::de::Error::custom("the nonce is exhausted"));
+                }
+                DynInner::Keyed(inner) => SymmetricState::from_keyed_inner(&inner)
+                    .map(|state| Inner::Keyed(state, inner.nonce)),
+                DynInner::Empty(inner) => SymmetricState::try_from(inner).map(Inner::Empty),
+            };
+            inner
+                .map(|inner| DynSymmetricState { inner })
+                .map_err(serde::de::Error::custom)
+        }
+    }
+
+    impl<C> Serialize for DynSymmetricState<C>
+    where
+        C: Config,
+    {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            match &self.inner {
+                Inner::Empty(state) => state.serialize(serializer),
+                Inner::Keyed(state, n) => state.as_keyed_inner(*n).serialize(serializer),
+            }
+        }
+    }
+}
+
+mod binary_m {
+    use super::{DynSymmetricState, Inner, Config, SymmetricState, Key, typenum};
+    use crate::{
+        binary::{Binary, BinaryError, Kind, Reader},
+        protocol_name::NoiseName,
+    };
+
+    /// Same encoding as the typed states, the nonce of the keyed state is taken at runtime.
+    impl<C> Binary for DynSymmetricState<C>
+    where
+        C: Config,
+        C::Aead: NoiseName,
+        C::MixHash: NoiseName,
+        C::Dh: NoiseName,
+    {
+        fn encoded_len(&self) -> usize {
+            match &self.inner {
+                Inner::Empty(state) => state.encoded_len(),
+                Inner::Keyed(..) => SymmetricState::<C, Key<C, typenum::U0>>::keyed_encoded_len(),
+            }
+        }
+
+        fn encode(&self, buffer: &mut [u8]) -> Result<usize, BinaryError> {
+            match &self.inner {
+                Inner::Empty(state) => state.encode(buffer),
+                Inner::Keyed(state, n) => state.encode_keyed(*n, buffer),
+            }
+        }
+
+        fn decode(bytes: &[u8]) -> Result<Self, BinaryError> {
+            let (reader, kind) = Reader::new::<C>(bytes, &[Kind::State, Kind::KeyedState])?;
+            let inner = if kind == Kind::State {
+                Inner::Empty(SymmetricState::decode(bytes)?)
+            } else {
+                match SymmetricState::decode_keyed(reader)? {
+                    (_, u64::MAX) => return Err(BinaryError::NonceMismatch),
+                    (state, n) => Inner::Keyed(state, n),
+                }
+            };
+            Ok(DynSymmetricState { inner })
+        }
+    }
+}
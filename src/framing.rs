@@ -0,0 +1,210 @@
+use core::fmt;
+
+use alloc::vec::Vec;
+
+use {aead::AeadCore, generic_array::typenum::Unsigned};
+
+use super::{
+    config::{Config, ConfigExt},
+    cipher_state::{Tag, CipherError, Cipher},
+    symmetric_state::{Hash, Output},
+};
+
+/// Size of the big-endian length prefix of a frame.
+pub const LENGTH_PREFIX_LEN: usize = 2;
+
+/// Maximal length of the frame excluding the length prefix, the Noise message maximum.
+pub const MAX_FRAME_LEN: usize = u16::MAX as usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameError {
+    Cipher(CipherError),
+    PayloadTooLong,
+    BufferTooSmall,
+    /// the length prefix is less than the tag length
+    FrameTooShort,
+}
+
+impl From<CipherError> for FrameError {
+    fn from(v: CipherError) -> Self {
+        FrameError::Cipher(v)
+    }
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameError::Cipher(error) => write!(f, "{}", error),
+            FrameError::PayloadTooLong => write!(f, "payload does not fit in a frame"),
+            FrameError::BufferTooSmall => write!(f, "buffer too small"),
+            FrameError::FrameTooShort => write!(f, "frame is shorter than the tag"),
+        }
+    }
+}
+
+#[cfg(any(feature = "std", test))]
+impl std::error::Error for FrameError {}
+
+const fn tag_len<C>() -> usize
+where
+    C: Config,
+{
+    <<C::Aead as AeadCore>::TagSize as Unsigned>::USIZE
+}
+
+/// The transport messages framed like NoiseSocket does:
+/// 2-byte big-endian length, ciphertext, tag.
+pub struct Framed<C, const STEP: u64>
+where
+    C: Config,
+{
+    pub sender: FrameSender<C, STEP>,
+    pub receiver: FrameReceiver<C, STEP>,
+    pub hash: Hash<C>,
+}
+
+impl<C, const STEP: u64> From<Output<C, STEP>> for Framed<C, STEP>
+where
+    C: Config,
+{
+    fn from(v: Output<C, STEP>) -> Self {
+        Framed {
+            sender: FrameSender { cipher: v.sender },
+            receiver: FrameReceiver {
+                cipher: v.receiver,
+                buffer: Vec::new(),
+                offset: 0,
+            },
+            hash: v.hash,
+        }
+    }
+}
+
+pub struct FrameSender<C, const STEP: u64>
+where
+    C: Config,
+{
+    cipher: Cipher<C, STEP, true>,
+}
+
+impl<C, const STEP: u64> FrameSender<C, STEP>
+where
+    C: ConfigExt,
+{
+    /// The largest payload that fits in a single frame.
+    pub const MAX_PAYLOAD_LEN: usize = MAX_FRAME_LEN - tag_len::<C>();
+
+    pub fn cipher(&mut self) -> &mut Cipher<C, STEP, true> {
+        &mut self.cipher
+    }
+
+    /// Writes a single frame into `frame`, returns the length of the frame.
+    ///
+    /// # Errors
+    /// the payload is longer than `MAX_PAYLOAD_LEN`, the buffer is too small
+    /// or the nonce is exhausted
+    pub fn encrypt_frame(&mut self, payload: &[u8], frame: &mut [u8]) -> Result<usize, FrameError> {
+        if payload.len() > Self::MAX_PAYLOAD_LEN {
+            return Err(FrameError::PayloadTooLong);
+        }
+        let len = payload.len() + tag_len::<C>();
+        let frame = frame
+            .get_mut(..(LENGTH_PREFIX_LEN + len))
+            .ok_or(FrameError::BufferTooSmall)?;
+        let (prefix, frame) = frame.split_at_mut(LENGTH_PREFIX_LEN);
+        let (data, tag) = frame.split_at_mut(payload.len());
+        data.clone_from_slice(payload);
+        tag.clone_from_slice(&self.cipher.encrypt(&[], data)?);
+        prefix.clone_from_slice(&(len as u16).to_be_bytes());
+        Ok(LENGTH_PREFIX_LEN + len)
+    }
+
+    /// Appends the frames to `out`, the payload is split in as many frames as needed,
+    /// the empty payload still produces a frame.
+    ///
+    /// # Errors
+    /// nonce exhausted, the frames encrypted before the error stay in `out`
+    pub fn encrypt(&mut self, payload: &[u8], out: &mut Vec<u8>) -> Result<(), FrameError> {
+        let mut chunks = payload.chunks(Self::MAX_PAYLOAD_LEN);
+        let first = chunks.next().unwrap_or_default();
+        for chunk in Some(first).into_iter().chain(chunks) {
+            let start = out.len();
+            out.resize(start + LENGTH_PREFIX_LEN + chunk.len() + tag_len::<C>(), 0);
+            if let Err(error) = self.encrypt_frame(chunk, &mut out[start..]) {
+                out.truncate(start);
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses frames from a byte stream incrementally.
+pub struct FrameReceiver<C, const STEP: u64>
+where
+    C: Config,
+{
+    cipher: Cipher<C, STEP, false>,
+    buffer: Vec<u8>,
+    // the bytes before are parsed, they are removed on the next `push`
+    offset: usize,
+}
+
+impl<C, const STEP: u64> FrameReceiver<C, STEP>
+where
+    C: ConfigExt,
+{
+    pub fn cipher(&mut self) -> &mut Cipher<C, STEP, false> {
+        &mut self.cipher
+    }
+
+    /// Appends the received bytes, they are parsed by `decrypt`.
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.drain(..self.offset);
+        self.offset = 0;
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    fn unparsed(&self) -> &[u8] {
+        &self.buffer[self.offset..]
+    }
+
+    /// The number of bytes received, but not yet parsed.
+    pub fn pending(&self) -> usize {
+        self.unparsed().len()
+    }
+
+    /// The number of bytes needed to complete the next frame.
+    pub fn missing(&self) -> usize {
+        let unparsed = self.unparsed();
+        match unparsed.get(..LENGTH_PREFIX_LEN) {
+            None => LENGTH_PREFIX_LEN - unparsed.len(),
+            Some(prefix) => {
+                let len = u16::from_be_bytes([prefix[0], prefix[1]]) as usize;
+                (LENGTH_PREFIX_LEN + len).saturating_sub(unparsed.len())
+            }
+        }
+    }
+
+    /// Decrypts the next complete frame, returns `None` if more bytes are needed.
+    /// The frame is consumed even if it fails to decrypt.
+    ///
+    /// # Errors
+    /// the frame is shorter than the tag, mac mismatch or nonce exhausted
+    pub fn decrypt(&mut self) -> Result<Option<Vec<u8>>, FrameError> {
+        if self.missing() != 0 {
+            return Ok(None);
+        }
+        let unparsed = self.unparsed();
+        let len = u16::from_be_bytes([unparsed[0], unparsed[1]]) as usize;
+        let mut frame = unparsed[LENGTH_PREFIX_LEN..(LENGTH_PREFIX_LEN + len)].to_vec();
+        self.offset += LENGTH_PREFIX_LEN + len;
+        let payload_len = len
+            .checked_sub(tag_len::<C>())
+            .ok_or(FrameError::FrameTooShort)?;
+        let tag = Tag::<C>::clone_from_slice(&frame[payload_len..]);
+        frame.truncate(payload_len);
+        self.cipher.decrypt(&[], &mut frame, &tag)?;
+        Ok(Some(frame))
+    }
+}
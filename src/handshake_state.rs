@@ -0,0 +1,682 @@
+use core::fmt;
+
+use {
+    aead::AeadCore,
+    generic_array::typenum::Unsigned,
+    rand_core::{RngCore, CryptoRng},
+    zeroize::Zeroizing,
+};
+
+use super::{
+    config::{Config, ConfigExt},
+    cipher_state::{MacMismatch, CipherError},
+    symmetric_state::Output,
+    dyn_symmetric_state::DynSymmetricState,
+    dh::Dh,
+    kem::Kem,
+    pattern::{Token, TokenPosition, HandshakePattern, PatternError, PatternErrorKind},
+    protocol_name::{NoiseName, ProtocolName},
+};
+
+/// Maximal length of a Noise message.
+pub const MAX_MESSAGE_LEN: usize = 65535;
+
+/// How many pre-shared keys a single handshake can consume.
+pub const MAX_PSKS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandshakeError {
+    WrongTurn,
+    Finished,
+    NotFinished,
+    Aborted,
+    InvalidPattern(PatternError),
+    MissingLocalStatic,
+    MissingLocalEphemeral,
+    MissingRemoteStatic,
+    MissingRemoteEphemeral,
+    MissingPsk,
+    TooManyPsks,
+    BufferTooSmall,
+    MessageTooShort,
+    MessageTooLong,
+    InvalidPublicKey,
+    MacMismatch,
+    NonceExhausted,
+    NotFallback,
+    MissingKem,
+}
+
+impl From<MacMismatch> for HandshakeError {
+    fn from(_: MacMismatch) -> Self {
+        HandshakeError::MacMismatch
+    }
+}
+
+impl From<CipherError> for HandshakeError {
+    fn from(v: CipherError) -> Self {
+        match v {
+            CipherError::NonceExhausted => HandshakeError::NonceExhausted,
+            // the handshake state has no replay window, only the mac can fail
+            CipherError::MacMismatch | CipherError::Duplicate | CipherError::TooOld => {
+                HandshakeError::MacMismatch
+            }
+        }
+    }
+}
+
+impl fmt::Display for HandshakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HandshakeError::WrongTurn => write!(f, "the message should be sent by the peer"),
+            HandshakeError::Finished => write!(f, "the handshake is already finished"),
+            HandshakeError::NotFinished => write!(f, "the handshake is not finished yet"),
+            HandshakeError::Aborted => write!(f, "the handshake was aborted by a previous error"),
+            HandshakeError::InvalidPattern(error) => write!(f, "invalid pattern: {}", error),
+            HandshakeError::MissingLocalStatic => write!(f, "missing local static key"),
+            HandshakeError::MissingLocalEphemeral => write!(f, "missing local ephemeral key"),
+            HandshakeError::MissingRemoteStatic => write!(f, "missing remote static key"),
+            HandshakeError::MissingRemoteEphemeral => write!(f, "missing remote ephemeral key"),
+            HandshakeError::MissingPsk => write!(f, "missing pre-shared key"),
+            HandshakeError::TooManyPsks => write!(f, "too many pre-shared keys"),
+            HandshakeError::BufferTooSmall => write!(f, "buffer too small"),
+            HandshakeError::MessageTooShort => write!(f, "message too short"),
+            HandshakeError::MessageTooLong => write!(f, "message too long"),
+            HandshakeError::InvalidPublicKey => write!(f, "invalid public key"),
+            HandshakeError::MacMismatch => write!(f, "mac mismatch"),
+            HandshakeError::NonceExhausted => write!(f, "nonce exhausted"),
+            HandshakeError::NotFallback => write!(f, "the pattern is not a fallback pattern"),
+            HandshakeError::MissingKem => write!(f, "the hfs pattern requires a KEM in the config"),
+        }
+    }
+}
+
+#[cfg(any(feature = "std", test))]
+impl std::error::Error for HandshakeError {}
+
+/// Keys known before the handshake starts.
+pub struct HandshakeKeys<D>
+where
+    D: Dh,
+{
+    s: Option<D::Secret>,
+    e: Option<D::Secret>,
+    rs: Option<D::Public>,
+    re: Option<D::Public>,
+    psks: [Option<Zeroizing<[u8; 32]>>; MAX_PSKS],
+}
+
+impl<D> Default for HandshakeKeys<D>
+where
+    D: Dh,
+{
+    fn default() -> Self {
+        HandshakeKeys {
+            s: None,
+            e: None,
+            rs: None,
+            re: None,
+            psks: Default::default(),
+        }
+    }
+}
+
+impl<D> HandshakeKeys<D>
+where
+    D: Dh,
+{
+    #[must_use]
+    pub fn local_static(mut self, s: D::Secret) -> Self {
+        self.s = Some(s);
+        self
+    }
+
+    /// Normally the ephemeral key is generated, set it only for testing or fallback.
+    #[must_use]
+    pub fn local_ephemeral(mut self, e: D::Secret) -> Self {
+        self.e = Some(e);
+        self
+    }
+
+    #[must_use]
+    pub fn remote_static(mut self, rs: D::Public) -> Self {
+        self.rs = Some(rs);
+        self
+    }
+
+    #[must_use]
+    pub fn remote_ephemeral(mut self, re: D::Public) -> Self {
+        self.re = Some(re);
+        self
+    }
+
+    /// The keys are consumed in the order the `psk` tokens appear in the pattern.
+    ///
+    /// # Errors
+    /// more than `MAX_PSKS` keys are given
+    pub fn psk(mut self, psk: Zeroizing<[u8; 32]>) -> Result<Self, HandshakeError> {
+        let slot = self
+            .psks
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .ok_or(HandshakeError::TooManyPsks)?;
+        *slot = Some(psk);
+        Ok(self)
+    }
+}
+
+type Secret<C> = <<C as Config>::Dh as Dh>::Secret;
+type Public<C> = <<C as Config>::Dh as Dh>::Public;
+type KemSecret<C> = <<C as Config>::Kem as Kem>::Secret;
+type KemPublic<C> = <<C as Config>::Kem as Kem>::Public;
+
+/// Runs the handshake described by a `HandshakePattern`, the section 5.3 of the Noise spec.
+pub struct HandshakeState<C>
+where
+    C: Config,
+    C::Dh: Dh,
+{
+    state: Option<DynSymmetricState<C>>,
+    pattern: HandshakePattern,
+    initiator: bool,
+    index: usize,
+    s: Option<Secret<C>>,
+    e: Option<Secret<C>>,
+    rs: Option<Public<C>>,
+    re: Option<Public<C>>,
+    e1: Option<KemSecret<C>>,
+    re1: Option<KemPublic<C>>,
+    // the randomness of the KEM encapsulation
+    coins: Option<Zeroizing<[u8; 32]>>,
+    psks: [Option<Zeroizing<[u8; 32]>>; MAX_PSKS],
+    psk_index: usize,
+}
+
+impl<C> HandshakeState<C>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+{
+    /// The protocol name is derived from the config and the pattern.
+    /// The local ephemeral key is generated using `rng` unless it is given in `keys`,
+    /// so is the KEM ephemeral key and the randomness of the encapsulation for `hfs` patterns.
+    ///
+    /// # Errors
+    /// the pattern is invalid, a key required by the pre-messages is missing,
+    /// or the pattern is `hfs` and the config has no KEM
+    pub fn new<R>(
+        pattern: HandshakePattern,
+        initiator: bool,
+        prologue: &[u8],
+        keys: HandshakeKeys<C::Dh>,
+        rng: &mut R,
+    ) -> Result<Self, HandshakeError>
+    where
+        R: RngCore + CryptoRng,
+        C::Dh: NoiseName,
+        C::Aead: NoiseName,
+        C::MixHash: NoiseName,
+    {
+        let name = ProtocolName::new::<C>(&pattern);
+        let state = DynSymmetricState::from_protocol_name(&name);
+        Self::init(state, pattern, initiator, prologue, keys, rng)
+    }
+
+    /// Same as `new`, but the protocol name is given, so the config does not have
+    /// to implement `NoiseName`.
+    ///
+    /// # Errors
+    /// the pattern is invalid, a key required by the pre-messages is missing,
+    /// or the pattern is `hfs` and the config has no KEM
+    pub fn with_name<R>(
+        name: &str,
+        pattern: HandshakePattern,
+        initiator: bool,
+        prologue: &[u8],
+        keys: HandshakeKeys<C::Dh>,
+        rng: &mut R,
+    ) -> Result<Self, HandshakeError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let state = DynSymmetricState::new(name);
+        Self::init(state, pattern, initiator, prologue, keys, rng)
+    }
+
+    fn init<R>(
+        state: DynSymmetricState<C>,
+        pattern: HandshakePattern,
+        initiator: bool,
+        prologue: &[u8],
+        keys: HandshakeKeys<C::Dh>,
+        rng: &mut R,
+    ) -> Result<Self, HandshakeError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        pattern.validate().map_err(HandshakeError::InvalidPattern)?;
+        if pattern.is_hfs() && <C::Kem as Kem>::NAME.is_none() {
+            return Err(HandshakeError::MissingKem);
+        }
+        let HandshakeKeys {
+            s,
+            mut e,
+            rs,
+            re,
+            psks,
+        } = keys;
+        let sends = |token| {
+            pattern
+                .messages
+                .iter()
+                .enumerate()
+                .filter(|&(n, _)| HandshakePattern::is_initiator_message(n) == initiator)
+                .any(|(_, tokens)| tokens.contains(&token))
+        };
+        if sends(Token::E) && e.is_none() {
+            e = Some(C::Dh::generate(rng));
+        }
+        let e1 = sends(Token::E1).then(|| C::Kem::generate(rng));
+        let coins = sends(Token::Ekem1).then(|| {
+            let mut coins = Zeroizing::new([0; 32]);
+            rng.fill_bytes(coins.as_mut());
+            coins
+        });
+
+        let mut hs: Self = HandshakeState {
+            state: None,
+            pattern,
+            initiator,
+            index: 0,
+            s,
+            e,
+            rs,
+            re,
+            e1,
+            re1: None,
+            coins,
+            psks,
+            psk_index: 0,
+        };
+
+        let mut state = state.mix_hash(prologue);
+        for (tokens, pre_initiator) in [
+            (pattern.initiator_pre, true),
+            (pattern.responder_pre, false),
+        ] {
+            for (index, &token) in tokens.iter().enumerate() {
+                let public = match (token, pre_initiator == initiator) {
+                    (Token::E, true) => C::Dh::public(hs.local_e()?),
+                    (Token::S, true) => C::Dh::public(hs.local_s()?),
+                    (Token::E, false) => hs.remote_e()?.clone(),
+                    (Token::S, false) => hs.remote_s()?.clone(),
+                    _ => {
+                        return Err(HandshakeError::InvalidPattern(PatternError {
+                            kind: PatternErrorKind::InvalidPreMessage,
+                            token,
+                            position: TokenPosition::PreMessage {
+                                initiator: pre_initiator,
+                                index,
+                            },
+                        }))
+                    }
+                };
+                state = state.mix_hash(public.as_ref());
+                if token == Token::E && pattern.is_psk() {
+                    state = state.mix_key(public.as_ref());
+                }
+            }
+        }
+        hs.state = Some(state);
+
+        Ok(hs)
+    }
+
+    /// Restarts the unfinished handshake with the fallback `pattern`, the section 10.2
+    /// of the Noise spec. The roles are swapped, the initiator's ephemeral key of the failed
+    /// handshake becomes the pre-message. Both parties keep their static keys,
+    /// the remote static key and unused pre-shared keys are dropped.
+    /// The aborted handshake can fall back as well.
+    ///
+    /// # Errors
+    /// the handshake is finished, the pattern is not a fallback pattern,
+    /// or the ephemeral key of the initiator is unknown
+    pub fn fallback<R>(
+        self,
+        pattern: HandshakePattern,
+        prologue: &[u8],
+        rng: &mut R,
+    ) -> Result<Self, HandshakeError>
+    where
+        R: RngCore + CryptoRng,
+        C::Dh: NoiseName,
+        C::Aead: NoiseName,
+        C::MixHash: NoiseName,
+    {
+        if self.is_finished() {
+            return Err(HandshakeError::Finished);
+        }
+        if !pattern.is_fallback() || pattern.responder_pre.first() != Some(&Token::E) {
+            return Err(HandshakeError::NotFallback);
+        }
+
+        let mut keys = HandshakeKeys::default();
+        if let Some(s) = self.s {
+            keys = keys.local_static(s);
+        }
+        if self.initiator {
+            keys = keys.local_ephemeral(self.e.ok_or(HandshakeError::MissingLocalEphemeral)?);
+        } else {
+            // the new initiator generates a fresh ephemeral key
+            keys = keys.remote_ephemeral(self.re.ok_or(HandshakeError::MissingRemoteEphemeral)?);
+        }
+        Self::new(pattern, !self.initiator, prologue, keys, rng)
+    }
+
+    pub const fn is_initiator(&self) -> bool {
+        self.initiator
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.index >= self.pattern.messages.len()
+    }
+
+    /// Whether the next message should be written rather than read.
+    pub fn is_write_turn(&self) -> bool {
+        HandshakePattern::is_initiator_message(self.index) == self.initiator
+    }
+
+    pub const fn remote_static(&self) -> Option<&Public<C>> {
+        self.rs.as_ref()
+    }
+
+    pub fn local_static_public(&self) -> Option<Public<C>> {
+        self.s.as_ref().map(C::Dh::public)
+    }
+
+    fn local_s(&self) -> Result<&Secret<C>, HandshakeError> {
+        self.s.as_ref().ok_or(HandshakeError::MissingLocalStatic)
+    }
+
+    fn local_e(&self) -> Result<&Secret<C>, HandshakeError> {
+        self.e.as_ref().ok_or(HandshakeError::MissingLocalEphemeral)
+    }
+
+    fn remote_s(&self) -> Result<&Public<C>, HandshakeError> {
+        self.rs.as_ref().ok_or(HandshakeError::MissingRemoteStatic)
+    }
+
+    fn remote_e(&self) -> Result<&Public<C>, HandshakeError> {
+        self.re
+            .as_ref()
+            .ok_or(HandshakeError::MissingRemoteEphemeral)
+    }
+
+    fn tokens(&self, write: bool) -> Result<&'static [Token], HandshakeError> {
+        if self.is_finished() {
+            Err(HandshakeError::Finished)
+        } else if self.is_write_turn() != write {
+            Err(HandshakeError::WrongTurn)
+        } else {
+            Ok(self.pattern.messages[self.index])
+        }
+    }
+
+    fn next_psk(&mut self) -> Result<[u8; 32], HandshakeError> {
+        let psk = self
+            .psks
+            .get_mut(self.psk_index)
+            .and_then(Option::take)
+            .ok_or(HandshakeError::MissingPsk)?;
+        self.psk_index += 1;
+        Ok(*psk)
+    }
+
+    fn dh(
+        &self,
+        token: Token,
+        index: usize,
+    ) -> Result<<C::Dh as Dh>::SharedSecret, HandshakeError> {
+        let (local, remote) = match (token, self.initiator) {
+            (Token::Ee, _) => (self.local_e()?, self.remote_e()?),
+            (Token::Ss, _) => (self.local_s()?, self.remote_s()?),
+            (Token::Es, true) | (Token::Se, false) => (self.local_e()?, self.remote_s()?),
+            (Token::Es, false) | (Token::Se, true) => (self.local_s()?, self.remote_e()?),
+            _ => {
+                return Err(HandshakeError::InvalidPattern(PatternError {
+                    kind: PatternErrorKind::NotDh,
+                    token,
+                    position: TokenPosition::Message {
+                        message: self.index,
+                        index,
+                    },
+                }))
+            }
+        };
+        C::Dh::dh(local, remote).ok_or(HandshakeError::InvalidPublicKey)
+    }
+
+    // the length of the message to write, the tags appear once the state has a key
+    fn message_len(&self, tokens: &[Token], payload_len: usize) -> Result<usize, HandshakeError> {
+        let tag_len = <<C::Aead as AeadCore>::TagSize as Unsigned>::USIZE;
+        let mut keyed = self
+            .state
+            .as_ref()
+            .ok_or(HandshakeError::Aborted)?
+            .has_key();
+        let mut len = 0;
+        for &token in tokens {
+            let tag = if keyed { tag_len } else { 0 };
+            match token {
+                Token::E => {
+                    len += C::Dh::DHLEN;
+                    keyed |= self.pattern.is_psk();
+                }
+                Token::S => len += C::Dh::DHLEN + tag,
+                Token::E1 => len += C::Kem::PUBLIC_LEN + tag,
+                Token::Ekem1 => {
+                    len += C::Kem::CIPHERTEXT_LEN + tag;
+                    keyed = true;
+                }
+                Token::Psk | Token::Ee | Token::Es | Token::Se | Token::Ss => keyed = true,
+            }
+        }
+        Ok(len + payload_len + if keyed { tag_len } else { 0 })
+    }
+
+    /// Writes the next handshake message into `message`, returns its length.
+    ///
+    /// The buffer is checked before the state changes, so `BufferTooSmall` and `MessageTooLong`
+    /// do not abort the handshake, neither do `WrongTurn` and `Finished`. Any other error does.
+    ///
+    /// # Errors
+    /// not our turn, a required key is missing, or the `message` buffer is too small
+    pub fn write_message(
+        &mut self,
+        payload: &[u8],
+        message: &mut [u8],
+    ) -> Result<usize, HandshakeError> {
+        let tokens = self.tokens(true)?;
+        let message_len = self.message_len(tokens, payload.len())?;
+        if message_len > MAX_MESSAGE_LEN {
+            return Err(HandshakeError::MessageTooLong);
+        }
+        if message_len > message.len() {
+            return Err(HandshakeError::BufferTooSmall);
+        }
+        let mut state = self.state.take().ok_or(HandshakeError::Aborted)?;
+
+        let mut pos = 0;
+        for (index, &token) in tokens.iter().enumerate() {
+            state = match token {
+                Token::E => {
+                    let e = C::Dh::public(self.local_e()?);
+                    let bytes = message
+                        .get_mut(pos..(pos + C::Dh::DHLEN))
+                        .ok_or(HandshakeError::BufferTooSmall)?;
+                    bytes.clone_from_slice(e.as_ref());
+                    pos += C::Dh::DHLEN;
+                    let state = state.mix_hash(e.as_ref());
+                    if self.pattern.is_psk() {
+                        state.mix_key(e.as_ref())
+                    } else {
+                        state
+                    }
+                }
+                Token::S => {
+                    let s = C::Dh::public(self.local_s()?);
+                    let len = C::Dh::DHLEN + state.tag_len();
+                    let bytes = message
+                        .get_mut(pos..(pos + len))
+                        .ok_or(HandshakeError::BufferTooSmall)?;
+                    bytes[..C::Dh::DHLEN].clone_from_slice(s.as_ref());
+                    pos += len;
+                    state.encrypt_and_hash(bytes)?
+                }
+                Token::Psk => state.mix_psk(self.next_psk()?),
+                Token::E1 => {
+                    let e1 = C::Kem::public(self.e1.as_ref().ok_or(HandshakeError::MissingKem)?);
+                    let len = C::Kem::PUBLIC_LEN + state.tag_len();
+                    let bytes = message
+                        .get_mut(pos..(pos + len))
+                        .ok_or(HandshakeError::BufferTooSmall)?;
+                    bytes[..C::Kem::PUBLIC_LEN].clone_from_slice(e1.as_ref());
+                    pos += len;
+                    state.encrypt_and_hash(bytes)?
+                }
+                Token::Ekem1 => {
+                    let re1 = self
+                        .re1
+                        .as_ref()
+                        .ok_or(HandshakeError::MissingRemoteEphemeral)?;
+                    let coins = self.coins.take().ok_or(HandshakeError::MissingKem)?;
+                    let (ciphertext, shared_secret) =
+                        C::Kem::encapsulate(re1, &coins).ok_or(HandshakeError::InvalidPublicKey)?;
+                    let len = C::Kem::CIPHERTEXT_LEN + state.tag_len();
+                    let bytes = message
+                        .get_mut(pos..(pos + len))
+                        .ok_or(HandshakeError::BufferTooSmall)?;
+                    bytes[..C::Kem::CIPHERTEXT_LEN].clone_from_slice(ciphertext.as_ref());
+                    pos += len;
+                    state
+                        .encrypt_and_hash(bytes)?
+                        .mix_shared_secret(shared_secret)
+                }
+                token => state.mix_shared_secret(self.dh(token, index)?),
+            };
+        }
+
+        let len = payload.len() + state.tag_len();
+        let bytes = message
+            .get_mut(pos..(pos + len))
+            .ok_or(HandshakeError::BufferTooSmall)?;
+        bytes[..payload.len()].clone_from_slice(payload);
+        let state = state.encrypt_and_hash(bytes)?;
+        pos += len;
+        debug_assert_eq!(pos, message_len);
+
+        self.state = Some(state);
+        self.index += 1;
+        Ok(pos)
+    }
+
+    /// Decrypts the next handshake message in place, returns the payload.
+    ///
+    /// Any error except `WrongTurn`, `Finished` and `MessageTooLong` aborts the handshake.
+    ///
+    /// # Errors
+    /// not our turn, a required key is missing, the message is malformed or mac mismatch
+    pub fn read_message<'a>(
+        &mut self,
+        message: &'a mut [u8],
+    ) -> Result<&'a mut [u8], HandshakeError> {
+        let tokens = self.tokens(false)?;
+        if message.len() > MAX_MESSAGE_LEN {
+            return Err(HandshakeError::MessageTooLong);
+        }
+        let mut state = self.state.take().ok_or(HandshakeError::Aborted)?;
+
+        let mut pos = 0;
+        for (index, &token) in tokens.iter().enumerate() {
+            state = match token {
+                Token::E => {
+                    let bytes = message
+                        .get(pos..(pos + C::Dh::DHLEN))
+                        .ok_or(HandshakeError::MessageTooShort)?;
+                    pos += C::Dh::DHLEN;
+                    self.re = Some(
+                        C::Dh::public_from_bytes(bytes).ok_or(HandshakeError::InvalidPublicKey)?,
+                    );
+                    let state = state.mix_hash(bytes);
+                    if self.pattern.is_psk() {
+                        state.mix_key(bytes)
+                    } else {
+                        state
+                    }
+                }
+                Token::S => {
+                    let len = C::Dh::DHLEN + state.tag_len();
+                    let bytes = message
+                        .get_mut(pos..(pos + len))
+                        .ok_or(HandshakeError::MessageTooShort)?;
+                    pos += len;
+                    let state = state.decrypt_and_hash(bytes)?;
+                    let rs = C::Dh::public_from_bytes(&bytes[..C::Dh::DHLEN]);
+                    self.rs = Some(rs.ok_or(HandshakeError::InvalidPublicKey)?);
+                    state
+                }
+                Token::Psk => state.mix_psk(self.next_psk()?),
+                Token::E1 => {
+                    let len = C::Kem::PUBLIC_LEN + state.tag_len();
+                    let bytes = message
+                        .get_mut(pos..(pos + len))
+                        .ok_or(HandshakeError::MessageTooShort)?;
+                    pos += len;
+                    let state = state.decrypt_and_hash(bytes)?;
+                    let re1 = C::Kem::public_from_bytes(&bytes[..C::Kem::PUBLIC_LEN]);
+                    self.re1 = Some(re1.ok_or(HandshakeError::InvalidPublicKey)?);
+                    state
+                }
+                Token::Ekem1 => {
+                    let len = C::Kem::CIPHERTEXT_LEN + state.tag_len();
+                    let bytes = message
+                        .get_mut(pos..(pos + len))
+                        .ok_or(HandshakeError::MessageTooShort)?;
+                    pos += len;
+                    let state = state.decrypt_and_hash(bytes)?;
+                    let e1 = self.e1.as_ref().ok_or(HandshakeError::MissingKem)?;
+                    let shared_secret = C::Kem::decapsulate(e1, &bytes[..C::Kem::CIPHERTEXT_LEN]);
+                    state.mix_shared_secret(shared_secret.ok_or(HandshakeError::InvalidPublicKey)?)
+                }
+                token => state.mix_shared_secret(self.dh(token, index)?),
+            };
+        }
+
+        let tag_len = state.tag_len();
+        if message.len() < pos + tag_len {
+            return Err(HandshakeError::MessageTooShort);
+        }
+        let state = state.decrypt_and_hash(&mut message[pos..])?;
+        let end = message.len() - tag_len;
+
+        self.state = Some(state);
+        self.index += 1;
+        Ok(&mut message[pos..end])
+    }
+
+    /// Splits the final state into transport ciphers.
+    ///
+    /// # Errors
+    /// the handshake is not finished or was aborted
+    pub fn finish<const STEP: u64>(self) -> Result<Output<C, STEP>, HandshakeError> {
+        if !self.is_finished() {
+            return Err(HandshakeError::NotFinished);
+        }
+        let state = self.state.ok_or(HandshakeError::Aborted)?;
+        if self.initiator {
+            Ok(state.finish::<STEP, false>())
+        } else {
+            Ok(state.finish::<STEP, true>())
+        }
+    }
+}
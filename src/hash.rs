@@ -21,6 +21,8 @@ where
 
     fn init(data: &[u8]) -> GenericArray<u8, Self::L>;
 
+    fn init_parts(parts: &[&[u8]]) -> GenericArray<u8, Self::L>;
+
     fn mix_hash(hash: GenericArray<u8, Self::L>, data: &[u8]) -> GenericArray<u8, Self::L>;
 
     fn mix_parts(hash: GenericArray<u8, Self::L>, parts: &[&[u8]]) -> GenericArray<u8, Self::L>;
@@ -37,6 +39,14 @@ where
         D::default().chain(data).finalize_fixed()
     }
 
+    fn init_parts(parts: &[&[u8]]) -> GenericArray<u8, Self::L> {
+        let mut d = D::default();
+        for &part in parts {
+            d.update(part);
+        }
+        d.finalize_fixed()
+    }
+
     fn mix_hash(hash: GenericArray<u8, Self::L>, data: &[u8]) -> GenericArray<u8, Self::L> {
         D::default().chain(hash).chain(data).finalize_fixed()
     }
@@ -0,0 +1,74 @@
+use {
+    rand_core::{RngCore, CryptoRng},
+    zeroize::Zeroize,
+};
+
+/// Key encapsulation mechanism of the `e1` and `ekem1` tokens, the Noise HFS extension.
+///
+/// `()` is no KEM, the `hfs` patterns fail with `HandshakeError::MissingKem`.
+pub trait Kem {
+    /// Appears after `+` in the DH part of protocol names, e.g. `25519+Kyber1024`.
+    const NAME: Option<&'static str>;
+    /// Size of the public key as it appears in handshake messages.
+    const PUBLIC_LEN: usize;
+    const CIPHERTEXT_LEN: usize;
+
+    type Secret;
+    type Public: AsRef<[u8]> + Clone;
+    type Ciphertext: AsRef<[u8]>;
+    type SharedSecret: AsRef<[u8]> + Zeroize;
+
+    fn generate<R>(rng: &mut R) -> Self::Secret
+    where
+        R: RngCore + CryptoRng;
+
+    fn public(secret: &Self::Secret) -> Self::Public;
+
+    fn public_from_bytes(bytes: &[u8]) -> Option<Self::Public>;
+
+    /// The `coins` are 32 random bytes, the encapsulation is deterministic given them.
+    /// Returns `None` if the public key is invalid.
+    fn encapsulate(
+        public: &Self::Public,
+        coins: &[u8; 32],
+    ) -> Option<(Self::Ciphertext, Self::SharedSecret)>;
+
+    /// Returns `None` if the ciphertext is invalid.
+    fn decapsulate(secret: &Self::Secret, ciphertext: &[u8]) -> Option<Self::SharedSecret>;
+}
+
+impl Kem for () {
+    const NAME: Option<&'static str> = None;
+    const PUBLIC_LEN: usize = 0;
+    const CIPHERTEXT_LEN: usize = 0;
+
+    type Secret = ();
+    type Public = [u8; 0];
+    type Ciphertext = [u8; 0];
+    type SharedSecret = [u8; 0];
+
+    fn generate<R>(_: &mut R) -> Self::Secret
+    where
+        R: RngCore + CryptoRng,
+    {
+    }
+
+    fn public(_: &Self::Secret) -> Self::Public {
+        []
+    }
+
+    fn public_from_bytes(_: &[u8]) -> Option<Self::Public> {
+        None
+    }
+
+    fn encapsulate(
+        _: &Self::Public,
+        _: &[u8; 32],
+    ) -> Option<(Self::Ciphertext, Self::SharedSecret)> {
+        None
+    }
+
+    fn decapsulate(_: &Self::Secret, _: &[u8]) -> Option<Self::SharedSecret> {
+        None
+    }
+}
@@ -7,6 +7,9 @@
 #[cfg(any(feature = "alloc", test))]
 extern crate alloc;
 
+#[cfg(any(feature = "std", test))]
+extern crate std;
+
 #[cfg(test)]
 mod tests;
 
@@ -14,11 +17,91 @@ mod config;
 mod hash;
 mod cipher_state;
 mod symmetric_state;
+mod dyn_symmetric_state;
+mod dh;
+mod kem;
+mod pattern;
+pub mod patterns;
+mod handshake_state;
+mod protocol_name;
+mod binary;
+mod pipes;
+pub mod typestate;
+#[cfg(any(feature = "alloc", test))]
+mod framing;
+#[cfg(any(feature = "std", test))]
+mod stream;
+#[cfg(any(feature = "tokio", feature = "futures-io", test))]
+mod async_transport;
+#[cfg(any(feature = "tokio", test))]
+mod tokio_io;
+#[cfg(any(feature = "futures-io", test))]
+mod futures_io;
+#[cfg(any(feature = "libp2p", test))]
+mod libp2p;
+#[cfg(any(feature = "bolt8", test))]
+mod bolt8;
+#[cfg(any(feature = "wireguard", test))]
+mod wireguard;
+#[cfg(any(feature = "x25519", test))]
+mod curve25519;
+#[cfg(any(feature = "x448", test))]
+mod curve448;
+#[cfg(any(feature = "secp256k1", test))]
+mod secp256k1;
 
 pub use self::config::{Config, ConfigExt};
-pub use self::cipher_state::{Tag, Aead, MacMismatch, CipherInner, Cipher};
+pub use self::cipher_state::{
+    Tag, Aead, MacMismatch, CipherError, ReplayWindow, CipherInner, CipherRaw, Cipher,
+};
 pub use self::symmetric_state::{Output, OutputRaw, Key, SymmetricState, ChainingKey};
+pub use self::dyn_symmetric_state::DynSymmetricState;
+pub use self::dh::Dh;
+pub use self::kem::Kem;
+pub use self::pattern::{
+    Token, HandshakePattern, TokenPosition, PatternErrorKind, PatternError, PayloadSecurity,
+};
+pub use self::protocol_name::{NoiseName, ProtocolNameError, Modifier, ProtocolName};
+pub use self::binary::{
+    BINARY_VERSION, BINARY_HEADER_LEN, CONFIG_ID_LEN, BinaryError, Binary, config_id,
+};
+pub use self::handshake_state::{
+    MAX_MESSAGE_LEN, MAX_PSKS, HandshakeError, HandshakeKeys, HandshakeState,
+};
+pub use self::pipes::{PipeMode, NoisePipe};
+#[cfg(any(feature = "alloc", test))]
+pub use self::framing::{
+    LENGTH_PREFIX_LEN, MAX_FRAME_LEN, FrameError, Framed, FrameSender, FrameReceiver,
+};
+#[cfg(any(feature = "std", test))]
+pub use self::stream::NoiseStream;
+#[cfg(any(feature = "tokio", test))]
+pub use self::tokio_io::TokioNoiseStream;
+#[cfg(any(feature = "futures-io", test))]
+pub use self::futures_io::FuturesNoiseStream;
+#[cfg(any(feature = "libp2p", test))]
+pub use self::libp2p::{LIBP2P_STATIC_KEY_DOMAIN, Libp2pConfig, Libp2pError, Libp2pHandshake};
+#[cfg(any(feature = "bolt8", test))]
+pub use self::bolt8::{
+    BOLT8_VERSION, BOLT8_ACT_ONE_LEN, BOLT8_ACT_TWO_LEN, BOLT8_ACT_THREE_LEN, BOLT8_TAG_LEN,
+    BOLT8_HEADER_LEN, BOLT8_MAX_MESSAGE_LEN, BOLT8_ROTATION_INTERVAL, Bolt8Config, Bolt8Error,
+    Bolt8Initiator, Bolt8Responder, Bolt8Transport, Bolt8Cipher,
+};
+#[cfg(any(feature = "wireguard", test))]
+pub use self::wireguard::{
+    WIREGUARD_CONSTRUCTION, WIREGUARD_IDENTIFIER, WIREGUARD_INITIATION_LEN, WIREGUARD_RESPONSE_LEN,
+    WIREGUARD_COOKIE_REPLY_LEN, WireGuardConfig, WireGuardError, Tai64N, WireGuardSession,
+    WireGuardInitiator, WireGuardInitiation, WireGuardResponder, WireGuardCookieGenerator,
+    WireGuardCookieChecker,
+};
+#[cfg(any(feature = "x25519", test))]
+pub use self::curve25519::{X25519, X25519Secret};
+#[cfg(any(feature = "x448", test))]
+pub use self::curve448::{X448, X448Secret};
+#[cfg(any(feature = "secp256k1", test))]
+pub use self::secp256k1::{Secp256k1, Secp256k1Secret};
 
 pub use generic_array;
 pub use digest;
 pub use hkdf;
+pub use rand_core;
@@ -0,0 +1,289 @@
+use core::fmt;
+
+use alloc::vec::Vec;
+
+use {
+    chacha20poly1305::ChaCha20Poly1305,
+    ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey},
+    generic_array::typenum::B0,
+    hkdf::hmac::Hmac,
+    rand_core::{RngCore, CryptoRng},
+    sha2::Sha256,
+};
+
+use super::{
+    curve25519::X25519,
+    framing::{LENGTH_PREFIX_LEN, MAX_FRAME_LEN, Framed},
+    handshake_state::{HandshakeError, HandshakeKeys, HandshakeState},
+    patterns,
+};
+
+/// `Noise_XX_25519_ChaChaPoly_SHA256` as the libp2p noise spec requires.
+pub type Libp2pConfig = (Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519);
+
+/// The identity key signs the noise static key prefixed with this string.
+pub const LIBP2P_STATIC_KEY_DOMAIN: &[u8] = b"noise-libp2p-static-key:";
+
+// the `KeyType` enum of libp2p
+const KEY_TYPE_ED25519: u64 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Libp2pError {
+    Handshake(HandshakeError),
+    /// the length prefix does not match the message
+    Frame,
+    /// malformed protobuf of the handshake payload
+    Payload,
+    /// only ed25519 identity keys are supported
+    UnsupportedKeyType,
+    InvalidSignature,
+}
+
+impl From<HandshakeError> for Libp2pError {
+    fn from(v: HandshakeError) -> Self {
+        Libp2pError::Handshake(v)
+    }
+}
+
+impl fmt::Display for Libp2pError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Libp2pError::Handshake(error) => write!(f, "{}", error),
+            Libp2pError::Frame => write!(f, "the length prefix does not match the message"),
+            Libp2pError::Payload => write!(f, "malformed handshake payload"),
+            Libp2pError::UnsupportedKeyType => write!(f, "unsupported identity key type"),
+            Libp2pError::InvalidSignature => write!(f, "invalid identity signature"),
+        }
+    }
+}
+
+#[cfg(any(feature = "std", test))]
+impl std::error::Error for Libp2pError {}
+
+fn write_varint(out: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+fn write_bytes(out: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    write_varint(out, (field << 3) | 2);
+    write_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn read_varint(input: &mut &[u8]) -> Result<u64, Libp2pError> {
+    let mut v = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = input.split_first().ok_or(Libp2pError::Payload)?;
+        *input = rest;
+        v |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(v);
+        }
+    }
+    Err(Libp2pError::Payload)
+}
+
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+}
+
+// the protobuf fields, the fields of fixed size are skipped
+struct Fields<'a>(&'a [u8]);
+
+impl<'a> Fields<'a> {
+    fn next_field(&mut self) -> Result<Option<(u64, Value<'a>)>, Libp2pError> {
+        while !self.0.is_empty() {
+            let key = read_varint(&mut self.0)?;
+            let value = match key & 7 {
+                0 => Value::Varint(read_varint(&mut self.0)?),
+                2 => {
+                    let len = read_varint(&mut self.0)? as usize;
+                    if len > self.0.len() {
+                        return Err(Libp2pError::Payload);
+                    }
+                    let (bytes, rest) = self.0.split_at(len);
+                    self.0 = rest;
+                    Value::Bytes(bytes)
+                }
+                1 => {
+                    self.0 = self.0.get(8..).ok_or(Libp2pError::Payload)?;
+                    continue;
+                }
+                5 => {
+                    self.0 = self.0.get(4..).ok_or(Libp2pError::Payload)?;
+                    continue;
+                }
+                _ => return Err(Libp2pError::Payload),
+            };
+            return Ok(Some((key >> 3, value)));
+        }
+        Ok(None)
+    }
+}
+
+/// The `NoiseHandshakePayload` protobuf message.
+fn encode_payload(identity: &SigningKey, static_key: &[u8]) -> Vec<u8> {
+    let mut public_key = Vec::new();
+    write_varint(&mut public_key, 1 << 3);
+    write_varint(&mut public_key, KEY_TYPE_ED25519);
+    write_bytes(&mut public_key, 2, identity.verifying_key().as_bytes());
+
+    let signature = identity.sign(&[LIBP2P_STATIC_KEY_DOMAIN, static_key].concat());
+
+    let mut payload = Vec::new();
+    write_bytes(&mut payload, 1, &public_key);
+    write_bytes(&mut payload, 2, &signature.to_bytes());
+    payload
+}
+
+fn decode_payload(payload: &[u8], static_key: &[u8]) -> Result<VerifyingKey, Libp2pError> {
+    let (mut identity_key, mut identity_sig) = (None, None);
+    let mut fields = Fields(payload);
+    while let Some(field) = fields.next_field()? {
+        match field {
+            (1, Value::Bytes(bytes)) => identity_key = Some(bytes),
+            (2, Value::Bytes(bytes)) => identity_sig = Some(bytes),
+            _ => (),
+        }
+    }
+
+    let (mut key_type, mut data) = (None, None);
+    let mut fields = Fields(identity_key.ok_or(Libp2pError::Payload)?);
+    while let Some(field) = fields.next_field()? {
+        match field {
+            (1, Value::Varint(v)) => key_type = Some(v),
+            (2, Value::Bytes(bytes)) => data = Some(bytes),
+            _ => (),
+        }
+    }
+    if key_type.ok_or(Libp2pError::Payload)? != KEY_TYPE_ED25519 {
+        return Err(Libp2pError::UnsupportedKeyType);
+    }
+    let data = data.ok_or(Libp2pError::Payload)?;
+    let identity = data
+        .try_into()
+        .ok()
+        .and_then(|data| VerifyingKey::from_bytes(data).ok())
+        .ok_or(Libp2pError::Payload)?;
+
+    let signature = identity_sig
+        .and_then(|sig| Signature::from_slice(sig).ok())
+        .ok_or(Libp2pError::Payload)?;
+    identity
+        .verify(&[LIBP2P_STATIC_KEY_DOMAIN, static_key].concat(), &signature)
+        .map_err(|_| Libp2pError::InvalidSignature)?;
+    Ok(identity)
+}
+
+/// The XX handshake of the libp2p noise spec, the messages are length-prefixed.
+/// Only ed25519 identity keys are supported.
+pub struct Libp2pHandshake {
+    state: HandshakeState<Libp2pConfig>,
+    payload: Vec<u8>,
+    remote_identity: Option<VerifyingKey>,
+}
+
+impl Libp2pHandshake {
+    /// The `keys` should contain the local static key.
+    ///
+    /// # Errors
+    /// missing local static key
+    pub fn new<R>(
+        initiator: bool,
+        identity: &SigningKey,
+        keys: HandshakeKeys<X25519>,
+        rng: &mut R,
+    ) -> Result<Self, Libp2pError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let state = HandshakeState::new(patterns::XX, initiator, &[], keys, rng)?;
+        let static_key = state
+            .local_static_public()
+            .ok_or(HandshakeError::MissingLocalStatic)?;
+        Ok(Libp2pHandshake {
+            state,
+            payload: encode_payload(identity, &static_key),
+            remote_identity: None,
+        })
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state.is_finished()
+    }
+
+    pub fn is_write_turn(&self) -> bool {
+        self.state.is_write_turn()
+    }
+
+    /// Known once the payload of the peer is verified.
+    pub const fn remote_identity(&self) -> Option<&VerifyingKey> {
+        self.remote_identity.as_ref()
+    }
+
+    pub fn remote_static(&self) -> Option<&[u8; 32]> {
+        self.state.remote_static()
+    }
+
+    /// Appends the length-prefixed message to `out`.
+    ///
+    /// # Errors
+    /// the handshake error
+    pub fn write_message(&mut self, out: &mut Vec<u8>) -> Result<(), Libp2pError> {
+        // the first message is empty, then each party sends its identity
+        let payload = if self.state.is_initiator() && self.state.remote_static().is_none() {
+            &[][..]
+        } else {
+            &self.payload
+        };
+        let start = out.len();
+        out.resize(start + LENGTH_PREFIX_LEN + MAX_FRAME_LEN, 0);
+        let (prefix, message) = out[start..].split_at_mut(LENGTH_PREFIX_LEN);
+        match self.state.write_message(payload, message) {
+            Ok(len) => {
+                prefix.clone_from_slice(&(len as u16).to_be_bytes());
+                out.truncate(start + LENGTH_PREFIX_LEN + len);
+                Ok(())
+            }
+            Err(error) => {
+                out.truncate(start);
+                Err(error.into())
+            }
+        }
+    }
+
+    /// Reads the length-prefixed message, verifies the identity of the peer.
+    ///
+    /// # Errors
+    /// the handshake error, malformed payload or invalid signature
+    pub fn read_message(&mut self, frame: &mut [u8]) -> Result<(), Libp2pError> {
+        if frame.len() < LENGTH_PREFIX_LEN {
+            return Err(Libp2pError::Frame);
+        }
+        let (prefix, message) = frame.split_at_mut(LENGTH_PREFIX_LEN);
+        if u16::from_be_bytes([prefix[0], prefix[1]]) as usize != message.len() {
+            return Err(Libp2pError::Frame);
+        }
+        let payload = self.state.read_message(message)?;
+        if let Some(static_key) = self.state.remote_static() {
+            if self.remote_identity.is_none() {
+                self.remote_identity = Some(decode_payload(payload, static_key)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// The transport uses the length-prefixed frames as well.
+    ///
+    /// # Errors
+    /// the handshake is not finished
+    pub fn finish(self) -> Result<(Framed<Libp2pConfig, 1>, VerifyingKey), Libp2pError> {
+        let remote_identity = self.remote_identity.ok_or(HandshakeError::NotFinished)?;
+        Ok((self.state.finish()?.into(), remote_identity))
+    }
+}
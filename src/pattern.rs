@@ -0,0 +1,402 @@
+use core::fmt;
+
+/// A token of a handshake message pattern, as described in the section 7 of the Noise spec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Token {
+    E,
+    S,
+    Ee,
+    Es,
+    Se,
+    Ss,
+    Psk,
+    /// the KEM public key of the Noise HFS extension
+    E1,
+    /// the KEM ciphertext of the Noise HFS extension
+    Ekem1,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Token::E => "e",
+            Token::S => "s",
+            Token::Ee => "ee",
+            Token::Es => "es",
+            Token::Se => "se",
+            Token::Ss => "ss",
+            Token::Psk => "psk",
+            Token::E1 => "e1",
+            Token::Ekem1 => "ekem1",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// A handshake pattern, the name includes modifiers, e.g. `XNpsk3`.
+///
+/// The first message is always sent by the initiator and the direction alternates,
+/// the first letter of a DH token refers to the initiator's key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HandshakePattern {
+    pub name: &'static str,
+    pub initiator_pre: &'static [Token],
+    pub responder_pre: &'static [Token],
+    pub messages: &'static [&'static [Token]],
+}
+
+impl HandshakePattern {
+    pub fn is_psk(&self) -> bool {
+        self.messages.iter().any(|m| m.contains(&Token::Psk))
+    }
+
+    /// Whether the name has the `fallback` modifier, e.g. `XXfallback`.
+    pub fn is_fallback(&self) -> bool {
+        let base = self
+            .name
+            .find(|c: char| c.is_ascii_lowercase())
+            .unwrap_or(self.name.len());
+        self.name[base..].split('+').any(|m| m == "fallback")
+    }
+
+    pub fn is_hfs(&self) -> bool {
+        self.messages.iter().any(|m| m.contains(&Token::E1))
+    }
+
+    pub fn is_one_way(&self) -> bool {
+        self.messages.len() == 1
+    }
+
+    /// Whether the message with index `n` is sent by the initiator.
+    pub const fn is_initiator_message(n: usize) -> bool {
+        n % 2 == 0
+    }
+}
+
+/// Where a token is located in a handshake pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenPosition {
+    PreMessage { initiator: bool, index: usize },
+    Message { message: usize, index: usize },
+}
+
+impl fmt::Display for TokenPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenPosition::PreMessage { initiator, index } => {
+                let party = if *initiator { "initiator" } else { "responder" };
+                write!(f, "{} pre-message, token {}", party, index)
+            }
+            TokenPosition::Message { message, index } => {
+                write!(f, "message {}, token {}", message, index)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternErrorKind {
+    /// only `e`, `s` or `e, s` are allowed in a pre-message
+    InvalidPreMessage,
+    /// the public key is sent more than once
+    DuplicateKey,
+    /// the DH or the KEM encapsulation is performed more than once
+    DuplicateDh,
+    /// the DH or the KEM encapsulation is performed before the keys are known,
+    /// or `e1` is sent before `e`
+    MissingKey,
+    /// a payload is encrypted after a DH with the local static key,
+    /// but without the corresponding DH with the local ephemeral key
+    MissingEphemeralDh,
+    /// data is encrypted after the `psk` token, but the sender did not send `e` before
+    PskWithoutEphemeral,
+    /// the token is processed as a DH, but it is not a DH token
+    NotDh,
+}
+
+/// A violation of the validity rules, the sections 7.3 and 9.3 of the Noise spec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatternError {
+    pub kind: PatternErrorKind,
+    pub token: Token,
+    pub position: TokenPosition,
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.kind {
+            PatternErrorKind::InvalidPreMessage => "is not allowed in a pre-message",
+            PatternErrorKind::DuplicateKey => "sends the key more than once",
+            PatternErrorKind::DuplicateDh => "performs the DH more than once",
+            PatternErrorKind::MissingKey => "uses a key that is not known yet",
+            PatternErrorKind::MissingEphemeralDh => {
+                "is not followed by the DH with the ephemeral key before encryption"
+            }
+            PatternErrorKind::PskWithoutEphemeral => {
+                "is not preceded by the ephemeral key before encryption"
+            }
+            PatternErrorKind::NotDh => "is not a DH token",
+        };
+        write!(f, "token \"{}\" ({}) {}", self.token, self.position, reason)
+    }
+}
+
+#[cfg(any(feature = "std", test))]
+impl std::error::Error for PatternError {}
+
+#[derive(Default)]
+struct Party {
+    e: bool,
+    s: bool,
+    e1: bool,
+}
+
+#[derive(Default)]
+struct Validator {
+    initiator: Party,
+    responder: Party,
+    // positions of `ee`, `es`, `se`, `ss`
+    dh: [Option<TokenPosition>; 4],
+    psk: Option<TokenPosition>,
+    ekem1: bool,
+}
+
+impl Validator {
+    fn party(&mut self, initiator: bool) -> &mut Party {
+        if initiator {
+            &mut self.initiator
+        } else {
+            &mut self.responder
+        }
+    }
+
+    fn send_key(&mut self, token: Token, initiator: bool) -> Result<(), PatternErrorKind> {
+        let party = self.party(initiator);
+        let sent = match token {
+            Token::E => &mut party.e,
+            Token::S => &mut party.s,
+            _ => &mut party.e1,
+        };
+        if *sent {
+            return Err(PatternErrorKind::DuplicateKey);
+        }
+        *sent = true;
+        Ok(())
+    }
+
+    fn dh(&mut self, token: Token, position: TokenPosition) -> Result<(), PatternErrorKind> {
+        let (i, initiator, responder) = match token {
+            Token::Ee => (0, self.initiator.e, self.responder.e),
+            Token::Es => (1, self.initiator.e, self.responder.s),
+            Token::Se => (2, self.initiator.s, self.responder.e),
+            Token::Ss => (3, self.initiator.s, self.responder.s),
+            Token::E | Token::S | Token::Psk | Token::E1 | Token::Ekem1 => {
+                return Err(PatternErrorKind::NotDh)
+            }
+        };
+        if !(initiator && responder) {
+            return Err(PatternErrorKind::MissingKey);
+        }
+        if self.dh[i].is_some() {
+            return Err(PatternErrorKind::DuplicateDh);
+        }
+        self.dh[i] = Some(position);
+        Ok(())
+    }
+
+    // the party is about to encrypt a payload or its `e1`
+    fn encrypt(&self, initiator: bool) -> Result<(), PatternError> {
+        let [ee, es, se, ss] = self.dh;
+        let rules = if initiator {
+            [(se, ee, Token::Se), (ss, es, Token::Ss)]
+        } else {
+            [(es, ee, Token::Es), (ss, se, Token::Ss)]
+        };
+        for (static_dh, ephemeral_dh, token) in rules {
+            if let (Some(position), None) = (static_dh, ephemeral_dh) {
+                return Err(PatternError {
+                    kind: PatternErrorKind::MissingEphemeralDh,
+                    token,
+                    position,
+                });
+            }
+        }
+        let sent_e = if initiator {
+            self.initiator.e
+        } else {
+            self.responder.e
+        };
+        match self.psk {
+            Some(position) if !sent_e => Err(PatternError {
+                kind: PatternErrorKind::PskWithoutEphemeral,
+                token: Token::Psk,
+                position,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl HandshakePattern {
+    /// Checks the validity rules, the sections 7.3 and 9.3 of the Noise spec.
+    ///
+    /// # Errors
+    /// the first offending token
+    pub fn validate(&self) -> Result<(), PatternError> {
+        let mut v = Validator::default();
+
+        for (tokens, initiator) in [(self.initiator_pre, true), (self.responder_pre, false)] {
+            for (index, &token) in tokens.iter().enumerate() {
+                let error = |kind| PatternError {
+                    kind,
+                    token,
+                    position: TokenPosition::PreMessage { initiator, index },
+                };
+                let allowed = match token {
+                    Token::E => index == 0,
+                    Token::S => true,
+                    _ => false,
+                };
+                if !allowed {
+                    return Err(error(PatternErrorKind::InvalidPreMessage));
+                }
+                v.send_key(token, initiator).map_err(error)?;
+            }
+        }
+
+        for (message, tokens) in self.messages.iter().enumerate() {
+            let initiator = Self::is_initiator_message(message);
+            for (index, &token) in tokens.iter().enumerate() {
+                let position = TokenPosition::Message { message, index };
+                let error = |kind| PatternError {
+                    kind,
+                    token,
+                    position,
+                };
+                match token {
+                    Token::E => v.send_key(token, initiator).map_err(error)?,
+                    Token::S => {
+                        v.send_key(token, initiator).map_err(error)?;
+                        if v.psk.is_some() && !v.party(initiator).e {
+                            return Err(error(PatternErrorKind::PskWithoutEphemeral));
+                        }
+                    }
+                    Token::Psk => v.psk = v.psk.or(Some(position)),
+                    Token::E1 => {
+                        if !v.party(initiator).e {
+                            return Err(error(PatternErrorKind::MissingKey));
+                        }
+                        v.send_key(token, initiator).map_err(error)?;
+                        // the key is encrypted like the payload
+                        v.encrypt(initiator)?;
+                    }
+                    Token::Ekem1 => {
+                        if !v.party(!initiator).e1 {
+                            return Err(error(PatternErrorKind::MissingKey));
+                        }
+                        if v.ekem1 {
+                            return Err(error(PatternErrorKind::DuplicateDh));
+                        }
+                        v.ekem1 = true;
+                    }
+                    Token::Ee | Token::Es | Token::Se | Token::Ss => {
+                        v.dh(token, position).map_err(error)?
+                    }
+                }
+            }
+            v.encrypt(initiator)?;
+        }
+
+        // transport messages
+        v.encrypt(true)?;
+        if !self.is_one_way() {
+            v.encrypt(false)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Security properties of a message payload, the section 7.7 of the Noise spec.
+///
+/// `psk` tokens are not taken into account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PayloadSecurity {
+    /// Source property, from 0 to 2.
+    pub authentication: u8,
+    /// Destination property, from 0 to 5.
+    pub confidentiality: u8,
+}
+
+impl PayloadSecurity {
+    /// Whether `self` gives at least the guarantees of `other`.
+    pub fn at_least(&self, other: &Self) -> bool {
+        self.authentication >= other.authentication && self.confidentiality >= other.confidentiality
+    }
+}
+
+impl HandshakePattern {
+    /// Whether the message with index `n` is sent by the initiator,
+    /// `n` may point past the handshake at a transport message.
+    fn is_sent_by_initiator(&self, n: usize) -> bool {
+        self.is_one_way() || Self::is_initiator_message(n)
+    }
+
+    // `ee`, `es`, `se`, `ss` performed before the payload of the message `n`
+    fn dh_before(&self, n: usize) -> [bool; 4] {
+        let mut dh = [false; 4];
+        let tokens = self.messages.iter().take(n + 1).flat_map(|m| m.iter());
+        for token in tokens {
+            match token {
+                Token::Ee => dh[0] = true,
+                Token::Es => dh[1] = true,
+                Token::Se => dh[2] = true,
+                Token::Ss => dh[3] = true,
+                _ => (),
+            }
+        }
+        dh
+    }
+
+    fn authentication(&self, n: usize) -> u8 {
+        let [_, es, se, ss] = self.dh_before(n);
+        let sender_static_recipient_ephemeral = if self.is_sent_by_initiator(n) { se } else { es };
+        if sender_static_recipient_ephemeral {
+            2
+        } else if ss {
+            1
+        } else {
+            0
+        }
+    }
+
+    /// Security properties of the payload of the message with index `n`,
+    /// `n` may point past the handshake at a transport message.
+    /// Transport messages alternate the direction, unless the pattern is one-way.
+    pub fn payload_security(&self, n: usize) -> PayloadSecurity {
+        let initiator = self.is_sent_by_initiator(n);
+        let [ee, es, se, ss] = self.dh_before(n);
+        let sender_ephemeral_recipient_static = if initiator { es } else { se };
+
+        let confidentiality = if ee && sender_ephemeral_recipient_static {
+            // how the recipient's ephemeral key is bound to its static key
+            let recipient_authentication = (0..n)
+                .filter(|&m| self.is_sent_by_initiator(m) != initiator)
+                .map(|m| self.authentication(m))
+                .max()
+                .unwrap_or(0);
+            3 + recipient_authentication
+        } else if ee {
+            1
+        } else if sender_ephemeral_recipient_static || ss {
+            2
+        } else {
+            0
+        };
+
+        PayloadSecurity {
+            authentication: self.authentication(n),
+            confidentiality,
+        }
+    }
+}
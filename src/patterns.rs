@@ -0,0 +1,728 @@
+use super::pattern::{Token, HandshakePattern};
+
+// One-way patterns, the section 7.4 of the Noise spec.
+
+pub const N: HandshakePattern = HandshakePattern {
+    name: "N",
+    initiator_pre: &[],
+    responder_pre: &[Token::S],
+    messages: &[&[Token::E, Token::Es]],
+};
+
+pub const K: HandshakePattern = HandshakePattern {
+    name: "K",
+    initiator_pre: &[Token::S],
+    responder_pre: &[Token::S],
+    messages: &[&[Token::E, Token::Es, Token::Ss]],
+};
+
+pub const X: HandshakePattern = HandshakePattern {
+    name: "X",
+    initiator_pre: &[],
+    responder_pre: &[Token::S],
+    messages: &[&[Token::E, Token::Es, Token::S, Token::Ss]],
+};
+
+// Fundamental interactive patterns, the section 7.5 of the Noise spec.
+
+pub const NN: HandshakePattern = HandshakePattern {
+    name: "NN",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[&[Token::E], &[Token::E, Token::Ee]],
+};
+
+pub const NK: HandshakePattern = HandshakePattern {
+    name: "NK",
+    initiator_pre: &[],
+    responder_pre: &[Token::S],
+    messages: &[&[Token::E, Token::Es], &[Token::E, Token::Ee]],
+};
+
+pub const NX: HandshakePattern = HandshakePattern {
+    name: "NX",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[&[Token::E], &[Token::E, Token::Ee, Token::S, Token::Es]],
+};
+
+pub const XN: HandshakePattern = HandshakePattern {
+    name: "XN",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[&[Token::E], &[Token::E, Token::Ee], &[Token::S, Token::Se]],
+};
+
+pub const XK: HandshakePattern = HandshakePattern {
+    name: "XK",
+    initiator_pre: &[],
+    responder_pre: &[Token::S],
+    messages: &[
+        &[Token::E, Token::Es],
+        &[Token::E, Token::Ee],
+        &[Token::S, Token::Se],
+    ],
+};
+
+pub const XX: HandshakePattern = HandshakePattern {
+    name: "XX",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E],
+        &[Token::E, Token::Ee, Token::S, Token::Es],
+        &[Token::S, Token::Se],
+    ],
+};
+
+pub const KN: HandshakePattern = HandshakePattern {
+    name: "KN",
+    initiator_pre: &[Token::S],
+    responder_pre: &[],
+    messages: &[&[Token::E], &[Token::E, Token::Ee, Token::Se]],
+};
+
+pub const KK: HandshakePattern = HandshakePattern {
+    name: "KK",
+    initiator_pre: &[Token::S],
+    responder_pre: &[Token::S],
+    messages: &[
+        &[Token::E, Token::Es, Token::Ss],
+        &[Token::E, Token::Ee, Token::Se],
+    ],
+};
+
+pub const KX: HandshakePattern = HandshakePattern {
+    name: "KX",
+    initiator_pre: &[Token::S],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E],
+        &[Token::E, Token::Ee, Token::Se, Token::S, Token::Es],
+    ],
+};
+
+pub const IN: HandshakePattern = HandshakePattern {
+    name: "IN",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[&[Token::E, Token::S], &[Token::E, Token::Ee, Token::Se]],
+};
+
+pub const IK: HandshakePattern = HandshakePattern {
+    name: "IK",
+    initiator_pre: &[],
+    responder_pre: &[Token::S],
+    messages: &[
+        &[Token::E, Token::Es, Token::S, Token::Ss],
+        &[Token::E, Token::Ee, Token::Se],
+    ],
+};
+
+pub const IX: HandshakePattern = HandshakePattern {
+    name: "IX",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E, Token::S],
+        &[Token::E, Token::Ee, Token::Se, Token::S, Token::Es],
+    ],
+};
+
+// Deferred patterns, the section 7.6 of the Noise spec.
+
+pub const NK1: HandshakePattern = HandshakePattern {
+    name: "NK1",
+    initiator_pre: &[],
+    responder_pre: &[Token::S],
+    messages: &[&[Token::E], &[Token::E, Token::Ee, Token::Es]],
+};
+
+pub const NX1: HandshakePattern = HandshakePattern {
+    name: "NX1",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[&[Token::E], &[Token::E, Token::Ee, Token::S], &[Token::Es]],
+};
+
+pub const X1N: HandshakePattern = HandshakePattern {
+    name: "X1N",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E],
+        &[Token::E, Token::Ee],
+        &[Token::S],
+        &[Token::Se],
+    ],
+};
+
+pub const X1K: HandshakePattern = HandshakePattern {
+    name: "X1K",
+    initiator_pre: &[],
+    responder_pre: &[Token::S],
+    messages: &[
+        &[Token::E, Token::Es],
+        &[Token::E, Token::Ee],
+        &[Token::S],
+        &[Token::Se],
+    ],
+};
+
+pub const XK1: HandshakePattern = HandshakePattern {
+    name: "XK1",
+    initiator_pre: &[],
+    responder_pre: &[Token::S],
+    messages: &[
+        &[Token::E],
+        &[Token::E, Token::Ee, Token::Es],
+        &[Token::S, Token::Se],
+    ],
+};
+
+pub const X1K1: HandshakePattern = HandshakePattern {
+    name: "X1K1",
+    initiator_pre: &[],
+    responder_pre: &[Token::S],
+    messages: &[
+        &[Token::E],
+        &[Token::E, Token::Ee, Token::Es],
+        &[Token::S],
+        &[Token::Se],
+    ],
+};
+
+pub const X1X: HandshakePattern = HandshakePattern {
+    name: "X1X",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E],
+        &[Token::E, Token::Ee, Token::S, Token::Es],
+        &[Token::S],
+        &[Token::Se],
+    ],
+};
+
+pub const XX1: HandshakePattern = HandshakePattern {
+    name: "XX1",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E],
+        &[Token::E, Token::Ee, Token::S],
+        &[Token::Es, Token::S, Token::Se],
+    ],
+};
+
+pub const X1X1: HandshakePattern = HandshakePattern {
+    name: "X1X1",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E],
+        &[Token::E, Token::Ee, Token::S],
+        &[Token::Es, Token::S],
+        &[Token::Se],
+    ],
+};
+
+pub const K1N: HandshakePattern = HandshakePattern {
+    name: "K1N",
+    initiator_pre: &[Token::S],
+    responder_pre: &[],
+    messages: &[&[Token::E], &[Token::E, Token::Ee], &[Token::Se]],
+};
+
+pub const K1K: HandshakePattern = HandshakePattern {
+    name: "K1K",
+    initiator_pre: &[Token::S],
+    responder_pre: &[Token::S],
+    messages: &[&[Token::E, Token::Es], &[Token::E, Token::Ee], &[Token::Se]],
+};
+
+pub const KK1: HandshakePattern = HandshakePattern {
+    name: "KK1",
+    initiator_pre: &[Token::S],
+    responder_pre: &[Token::S],
+    messages: &[&[Token::E], &[Token::E, Token::Ee, Token::Se, Token::Es]],
+};
+
+pub const K1K1: HandshakePattern = HandshakePattern {
+    name: "K1K1",
+    initiator_pre: &[Token::S],
+    responder_pre: &[Token::S],
+    messages: &[&[Token::E], &[Token::E, Token::Ee, Token::Es], &[Token::Se]],
+};
+
+pub const K1X: HandshakePattern = HandshakePattern {
+    name: "K1X",
+    initiator_pre: &[Token::S],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E],
+        &[Token::E, Token::Ee, Token::S, Token::Es],
+        &[Token::Se],
+    ],
+};
+
+pub const KX1: HandshakePattern = HandshakePattern {
+    name: "KX1",
+    initiator_pre: &[Token::S],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E],
+        &[Token::E, Token::Ee, Token::Se, Token::S],
+        &[Token::Es],
+    ],
+};
+
+pub const K1X1: HandshakePattern = HandshakePattern {
+    name: "K1X1",
+    initiator_pre: &[Token::S],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E],
+        &[Token::E, Token::Ee, Token::S],
+        &[Token::Se, Token::Es],
+    ],
+};
+
+pub const I1N: HandshakePattern = HandshakePattern {
+    name: "I1N",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[&[Token::E, Token::S], &[Token::E, Token::Ee], &[Token::Se]],
+};
+
+pub const I1K: HandshakePattern = HandshakePattern {
+    name: "I1K",
+    initiator_pre: &[],
+    responder_pre: &[Token::S],
+    messages: &[
+        &[Token::E, Token::Es, Token::S],
+        &[Token::E, Token::Ee],
+        &[Token::Se],
+    ],
+};
+
+pub const IK1: HandshakePattern = HandshakePattern {
+    name: "IK1",
+    initiator_pre: &[],
+    responder_pre: &[Token::S],
+    messages: &[
+        &[Token::E, Token::S],
+        &[Token::E, Token::Ee, Token::Se, Token::Es],
+    ],
+};
+
+pub const I1K1: HandshakePattern = HandshakePattern {
+    name: "I1K1",
+    initiator_pre: &[],
+    responder_pre: &[Token::S],
+    messages: &[
+        &[Token::E, Token::S],
+        &[Token::E, Token::Ee, Token::Es],
+        &[Token::Se],
+    ],
+};
+
+pub const I1X: HandshakePattern = HandshakePattern {
+    name: "I1X",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E, Token::S],
+        &[Token::E, Token::Ee, Token::S, Token::Es],
+        &[Token::Se],
+    ],
+};
+
+pub const IX1: HandshakePattern = HandshakePattern {
+    name: "IX1",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E, Token::S],
+        &[Token::E, Token::Ee, Token::Se, Token::S],
+        &[Token::Es],
+    ],
+};
+
+pub const I1X1: HandshakePattern = HandshakePattern {
+    name: "I1X1",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E, Token::S],
+        &[Token::E, Token::Ee, Token::S],
+        &[Token::Se, Token::Es],
+    ],
+};
+
+// Patterns with pre-shared keys, the section 9.4 of the Noise spec.
+
+pub const N_PSK0: HandshakePattern = HandshakePattern {
+    name: "Npsk0",
+    initiator_pre: &[],
+    responder_pre: &[Token::S],
+    messages: &[&[Token::Psk, Token::E, Token::Es]],
+};
+
+pub const K_PSK0: HandshakePattern = HandshakePattern {
+    name: "Kpsk0",
+    initiator_pre: &[Token::S],
+    responder_pre: &[Token::S],
+    messages: &[&[Token::Psk, Token::E, Token::Es, Token::Ss]],
+};
+
+pub const X_PSK1: HandshakePattern = HandshakePattern {
+    name: "Xpsk1",
+    initiator_pre: &[],
+    responder_pre: &[Token::S],
+    messages: &[&[Token::E, Token::Es, Token::S, Token::Ss, Token::Psk]],
+};
+
+pub const NN_PSK0: HandshakePattern = HandshakePattern {
+    name: "NNpsk0",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[&[Token::Psk, Token::E], &[Token::E, Token::Ee]],
+};
+
+pub const NN_PSK2: HandshakePattern = HandshakePattern {
+    name: "NNpsk2",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[&[Token::E], &[Token::E, Token::Ee, Token::Psk]],
+};
+
+pub const NK_PSK0: HandshakePattern = HandshakePattern {
+    name: "NKpsk0",
+    initiator_pre: &[],
+    responder_pre: &[Token::S],
+    messages: &[&[Token::Psk, Token::E, Token::Es], &[Token::E, Token::Ee]],
+};
+
+pub const NK_PSK2: HandshakePattern = HandshakePattern {
+    name: "NKpsk2",
+    initiator_pre: &[],
+    responder_pre: &[Token::S],
+    messages: &[&[Token::E, Token::Es], &[Token::E, Token::Ee, Token::Psk]],
+};
+
+pub const NX_PSK2: HandshakePattern = HandshakePattern {
+    name: "NXpsk2",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E],
+        &[Token::E, Token::Ee, Token::S, Token::Es, Token::Psk],
+    ],
+};
+
+pub const XN_PSK3: HandshakePattern = HandshakePattern {
+    name: "XNpsk3",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E],
+        &[Token::E, Token::Ee],
+        &[Token::S, Token::Se, Token::Psk],
+    ],
+};
+
+pub const XK_PSK3: HandshakePattern = HandshakePattern {
+    name: "XKpsk3",
+    initiator_pre: &[],
+    responder_pre: &[Token::S],
+    messages: &[
+        &[Token::E, Token::Es],
+        &[Token::E, Token::Ee],
+        &[Token::S, Token::Se, Token::Psk],
+    ],
+};
+
+pub const XX_PSK3: HandshakePattern = HandshakePattern {
+    name: "XXpsk3",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E],
+        &[Token::E, Token::Ee, Token::S, Token::Es],
+        &[Token::S, Token::Se, Token::Psk],
+    ],
+};
+
+pub const KN_PSK0: HandshakePattern = HandshakePattern {
+    name: "KNpsk0",
+    initiator_pre: &[Token::S],
+    responder_pre: &[],
+    messages: &[&[Token::Psk, Token::E], &[Token::E, Token::Ee, Token::Se]],
+};
+
+pub const KN_PSK2: HandshakePattern = HandshakePattern {
+    name: "KNpsk2",
+    initiator_pre: &[Token::S],
+    responder_pre: &[],
+    messages: &[&[Token::E], &[Token::E, Token::Ee, Token::Se, Token::Psk]],
+};
+
+pub const KK_PSK0: HandshakePattern = HandshakePattern {
+    name: "KKpsk0",
+    initiator_pre: &[Token::S],
+    responder_pre: &[Token::S],
+    messages: &[
+        &[Token::Psk, Token::E, Token::Es, Token::Ss],
+        &[Token::E, Token::Ee, Token::Se],
+    ],
+};
+
+pub const KK_PSK2: HandshakePattern = HandshakePattern {
+    name: "KKpsk2",
+    initiator_pre: &[Token::S],
+    responder_pre: &[Token::S],
+    messages: &[
+        &[Token::E, Token::Es, Token::Ss],
+        &[Token::E, Token::Ee, Token::Se, Token::Psk],
+    ],
+};
+
+pub const KX_PSK2: HandshakePattern = HandshakePattern {
+    name: "KXpsk2",
+    initiator_pre: &[Token::S],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E],
+        &[
+            Token::E,
+            Token::Ee,
+            Token::Se,
+            Token::S,
+            Token::Es,
+            Token::Psk,
+        ],
+    ],
+};
+
+pub const IN_PSK1: HandshakePattern = HandshakePattern {
+    name: "INpsk1",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E, Token::S, Token::Psk],
+        &[Token::E, Token::Ee, Token::Se],
+    ],
+};
+
+pub const IN_PSK2: HandshakePattern = HandshakePattern {
+    name: "INpsk2",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E, Token::S],
+        &[Token::E, Token::Ee, Token::Se, Token::Psk],
+    ],
+};
+
+pub const IK_PSK1: HandshakePattern = HandshakePattern {
+    name: "IKpsk1",
+    initiator_pre: &[],
+    responder_pre: &[Token::S],
+    messages: &[
+        &[Token::E, Token::Es, Token::S, Token::Ss, Token::Psk],
+        &[Token::E, Token::Ee, Token::Se],
+    ],
+};
+
+pub const IK_PSK2: HandshakePattern = HandshakePattern {
+    name: "IKpsk2",
+    initiator_pre: &[],
+    responder_pre: &[Token::S],
+    messages: &[
+        &[Token::E, Token::Es, Token::S, Token::Ss],
+        &[Token::E, Token::Ee, Token::Se, Token::Psk],
+    ],
+};
+
+pub const IX_PSK2: HandshakePattern = HandshakePattern {
+    name: "IXpsk2",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E, Token::S],
+        &[
+            Token::E,
+            Token::Ee,
+            Token::Se,
+            Token::S,
+            Token::Es,
+            Token::Psk,
+        ],
+    ],
+};
+
+// Patterns with the hybrid forward secrecy, the section 5 of the Noise HFS spec.
+
+pub const NN_HFS: HandshakePattern = HandshakePattern {
+    name: "NNhfs",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[&[Token::E, Token::E1], &[Token::E, Token::Ee, Token::Ekem1]],
+};
+
+pub const NK_HFS: HandshakePattern = HandshakePattern {
+    name: "NKhfs",
+    initiator_pre: &[],
+    responder_pre: &[Token::S],
+    messages: &[
+        &[Token::E, Token::Es, Token::E1],
+        &[Token::E, Token::Ee, Token::Ekem1],
+    ],
+};
+
+pub const NX_HFS: HandshakePattern = HandshakePattern {
+    name: "NXhfs",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E, Token::E1],
+        &[Token::E, Token::Ee, Token::Ekem1, Token::S, Token::Es],
+    ],
+};
+
+pub const XN_HFS: HandshakePattern = HandshakePattern {
+    name: "XNhfs",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E, Token::E1],
+        &[Token::E, Token::Ee, Token::Ekem1],
+        &[Token::S, Token::Se],
+    ],
+};
+
+pub const XK_HFS: HandshakePattern = HandshakePattern {
+    name: "XKhfs",
+    initiator_pre: &[],
+    responder_pre: &[Token::S],
+    messages: &[
+        &[Token::E, Token::Es, Token::E1],
+        &[Token::E, Token::Ee, Token::Ekem1],
+        &[Token::S, Token::Se],
+    ],
+};
+
+pub const XX_HFS: HandshakePattern = HandshakePattern {
+    name: "XXhfs",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E, Token::E1],
+        &[Token::E, Token::Ee, Token::Ekem1, Token::S, Token::Es],
+        &[Token::S, Token::Se],
+    ],
+};
+
+pub const KN_HFS: HandshakePattern = HandshakePattern {
+    name: "KNhfs",
+    initiator_pre: &[Token::S],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E, Token::E1],
+        &[Token::E, Token::Ee, Token::Ekem1, Token::Se],
+    ],
+};
+
+pub const KK_HFS: HandshakePattern = HandshakePattern {
+    name: "KKhfs",
+    initiator_pre: &[Token::S],
+    responder_pre: &[Token::S],
+    messages: &[
+        &[Token::E, Token::Es, Token::E1, Token::Ss],
+        &[Token::E, Token::Ee, Token::Ekem1, Token::Se],
+    ],
+};
+
+pub const KX_HFS: HandshakePattern = HandshakePattern {
+    name: "KXhfs",
+    initiator_pre: &[Token::S],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E, Token::E1],
+        &[
+            Token::E,
+            Token::Ee,
+            Token::Ekem1,
+            Token::Se,
+            Token::S,
+            Token::Es,
+        ],
+    ],
+};
+
+pub const IN_HFS: HandshakePattern = HandshakePattern {
+    name: "INhfs",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E, Token::E1, Token::S],
+        &[Token::E, Token::Ee, Token::Ekem1, Token::Se],
+    ],
+};
+
+pub const IK_HFS: HandshakePattern = HandshakePattern {
+    name: "IKhfs",
+    initiator_pre: &[],
+    responder_pre: &[Token::S],
+    messages: &[
+        &[Token::E, Token::Es, Token::E1, Token::S, Token::Ss],
+        &[Token::E, Token::Ee, Token::Ekem1, Token::Se],
+    ],
+};
+
+pub const IX_HFS: HandshakePattern = HandshakePattern {
+    name: "IXhfs",
+    initiator_pre: &[],
+    responder_pre: &[],
+    messages: &[
+        &[Token::E, Token::E1, Token::S],
+        &[
+            Token::E,
+            Token::Ee,
+            Token::Ekem1,
+            Token::Se,
+            Token::S,
+            Token::Es,
+        ],
+    ],
+};
+
+// The fallback pattern, the section 10.2 of the Noise spec. Bob, who could not decrypt
+// the first message of Alice, is the initiator here, and the ephemeral key of Alice
+// is the pre-message.
+
+pub const XX_FALLBACK: HandshakePattern = HandshakePattern {
+    name: "XXfallback",
+    initiator_pre: &[],
+    responder_pre: &[Token::E],
+    messages: &[
+        &[Token::E, Token::Ee, Token::S, Token::Se],
+        &[Token::S, Token::Es],
+    ],
+};
+
+#[rustfmt::skip]
+pub const ALL: &[HandshakePattern] = &[
+    N, K, X, NN, NK, NX, XN, XK, XX, KN, KK, KX, IN, IK, IX, NK1, NX1, X1N, X1K, XK1, X1K1, X1X,
+    XX1, X1X1, K1N, K1K, KK1, K1K1, K1X, KX1, K1X1, I1N, I1K, IK1, I1K1, I1X, IX1, I1X1, N_PSK0,
+    K_PSK0, X_PSK1, NN_PSK0, NN_PSK2, NK_PSK0, NK_PSK2, NX_PSK2, XN_PSK3, XK_PSK3, XX_PSK3,
+    KN_PSK0, KN_PSK2, KK_PSK0, KK_PSK2, KX_PSK2, IN_PSK1, IN_PSK2, IK_PSK1, IK_PSK2, IX_PSK2,
+    XX_FALLBACK, NN_HFS, NK_HFS, NX_HFS, XN_HFS, XK_HFS, XX_HFS, KN_HFS, KK_HFS, KX_HFS, IN_HFS,
+    IK_HFS, IX_HFS,
+];
+
+/// Finds the pattern by its name including modifiers, e.g. `XKpsk3`.
+pub fn find(name: &str) -> Option<HandshakePattern> {
+    ALL.iter().find(|pattern| pattern.name == name).copied()
+}
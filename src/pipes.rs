@@ -0,0 +1,206 @@
+use rand_core::{RngCore, CryptoRng};
+
+use super::{
+    config::ConfigExt,
+    dh::Dh,
+    handshake_state::{HandshakeError, HandshakeKeys, HandshakeState},
+    pattern::HandshakePattern,
+    patterns,
+    protocol_name::NoiseName,
+    symmetric_state::Output,
+};
+
+/// The handshake a Noise Pipe runs, the application should tell it to the peer
+/// as the negotiation data, the section 10.4 of the Noise spec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipeMode {
+    /// `XX`, the initiator does not know the responder's static key
+    Full,
+    /// `IK`, the initiator uses the cached responder's static key
+    ZeroRtt,
+    /// `XXfallback`, the responder could not decrypt the `IK` message
+    Fallback,
+}
+
+impl PipeMode {
+    pub const fn pattern(self) -> HandshakePattern {
+        match self {
+            PipeMode::Full => patterns::XX,
+            PipeMode::ZeroRtt => patterns::IK,
+            PipeMode::Fallback => patterns::XX_FALLBACK,
+        }
+    }
+}
+
+/// The Noise Pipes compound protocol, `IK` switches to `XXfallback`
+/// when the responder cannot decrypt the first message.
+pub struct NoisePipe<'a, C>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+{
+    // `None` if the fallback failed
+    state: Option<HandshakeState<C>>,
+    mode: PipeMode,
+    initiator: bool,
+    prologue: &'a [u8],
+}
+
+impl<'a, C> NoisePipe<'a, C>
+where
+    C: ConfigExt,
+    C::Dh: Dh + NoiseName,
+    C::Aead: NoiseName,
+    C::MixHash: NoiseName,
+{
+    /// Runs `IK` if the responder's static key `rs` is cached, `XX` otherwise.
+    ///
+    /// # Errors
+    /// never, the keys required by the pattern are given
+    pub fn initiator<R>(
+        prologue: &'a [u8],
+        s: <C::Dh as Dh>::Secret,
+        rs: Option<<C::Dh as Dh>::Public>,
+        rng: &mut R,
+    ) -> Result<Self, HandshakeError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let keys = HandshakeKeys::default().local_static(s);
+        let (mode, keys) = match rs {
+            Some(rs) => (PipeMode::ZeroRtt, keys.remote_static(rs)),
+            None => (PipeMode::Full, keys),
+        };
+        let state = HandshakeState::new(mode.pattern(), true, prologue, keys, rng)?;
+        Ok(NoisePipe {
+            state: Some(state),
+            mode,
+            initiator: true,
+            prologue,
+        })
+    }
+
+    /// Runs `IK` if `zero_rtt`, `XX` otherwise, as the initiator has chosen.
+    ///
+    /// # Errors
+    /// never, the keys required by the pattern are given
+    pub fn responder<R>(
+        prologue: &'a [u8],
+        zero_rtt: bool,
+        s: <C::Dh as Dh>::Secret,
+        rng: &mut R,
+    ) -> Result<Self, HandshakeError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mode = if zero_rtt {
+            PipeMode::ZeroRtt
+        } else {
+            PipeMode::Full
+        };
+        let keys = HandshakeKeys::default().local_static(s);
+        let state = HandshakeState::new(mode.pattern(), false, prologue, keys, rng)?;
+        Ok(NoisePipe {
+            state: Some(state),
+            mode,
+            initiator: false,
+            prologue,
+        })
+    }
+
+    pub const fn mode(&self) -> PipeMode {
+        self.mode
+    }
+
+    fn state(&self) -> Result<&HandshakeState<C>, HandshakeError> {
+        self.state.as_ref().ok_or(HandshakeError::Aborted)
+    }
+
+    /// Whether this party started the pipe, it stays the same after the fallback.
+    pub const fn is_initiator(&self) -> bool {
+        self.initiator
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state().map_or(false, HandshakeState::is_finished)
+    }
+
+    pub fn is_write_turn(&self) -> bool {
+        self.state().map_or(false, HandshakeState::is_write_turn)
+    }
+
+    pub fn remote_static(&self) -> Option<&<C::Dh as Dh>::Public> {
+        self.state().ok()?.remote_static()
+    }
+
+    /// # Errors
+    /// the handshake error
+    pub fn write_message(
+        &mut self,
+        payload: &[u8],
+        message: &mut [u8],
+    ) -> Result<usize, HandshakeError> {
+        let state = self.state.as_mut().ok_or(HandshakeError::Aborted)?;
+        state.write_message(payload, message)
+    }
+
+    /// Returns `None` if the responder could not decrypt the `IK` message and switched
+    /// to `XXfallback`, it should tell it to the initiator and write the next message.
+    ///
+    /// # Errors
+    /// the handshake error
+    pub fn read_message<'m, R>(
+        &mut self,
+        message: &'m mut [u8],
+        rng: &mut R,
+    ) -> Result<Option<&'m mut [u8]>, HandshakeError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let state = self.state.as_mut().ok_or(HandshakeError::Aborted)?;
+        match state.read_message(message) {
+            Ok(payload) => Ok(Some(payload)),
+            Err(HandshakeError::MacMismatch)
+                if self.mode == PipeMode::ZeroRtt && !self.initiator =>
+            {
+                self.switch(rng)?;
+                Ok(None)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// The initiator switches to `XXfallback` when the responder tells it has done so.
+    ///
+    /// # Errors
+    /// the pipe is not `IK` run by the initiator, or the first message is not sent yet
+    pub fn fallback<R>(&mut self, rng: &mut R) -> Result<(), HandshakeError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if self.mode != PipeMode::ZeroRtt || !self.initiator {
+            return Err(HandshakeError::NotFallback);
+        }
+        if self.state()?.is_write_turn() {
+            return Err(HandshakeError::WrongTurn);
+        }
+        self.switch(rng)
+    }
+
+    fn switch<R>(&mut self, rng: &mut R) -> Result<(), HandshakeError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let state = self.state.take().ok_or(HandshakeError::Aborted)?;
+        let pattern = PipeMode::Fallback.pattern();
+        self.state = Some(state.fallback(pattern, self.prologue, rng)?);
+        self.mode = PipeMode::Fallback;
+        Ok(())
+    }
+
+    /// # Errors
+    /// the handshake is not finished or was aborted
+    pub fn finish<const STEP: u64>(self) -> Result<Output<C, STEP>, HandshakeError> {
+        self.state.ok_or(HandshakeError::Aborted)?.finish()
+    }
+}
@@ -0,0 +1,275 @@
+use core::fmt;
+
+use super::{config::Config, kem::Kem, pattern::HandshakePattern};
+
+/// Name of a DH function, a cipher or a hash function as it appears in protocol names.
+pub trait NoiseName {
+    const NAME: &'static str;
+}
+
+/// The config without a DH function, only `SymmetricState` is used.
+impl NoiseName for () {
+    const NAME: &'static str = "";
+}
+
+#[cfg(any(feature = "x25519", test))]
+impl NoiseName for super::curve25519::X25519 {
+    const NAME: &'static str = "25519";
+}
+
+#[cfg(any(feature = "x448", test))]
+impl NoiseName for super::curve448::X448 {
+    const NAME: &'static str = "448";
+}
+
+#[cfg(any(feature = "secp256k1", test))]
+impl NoiseName for super::secp256k1::Secp256k1 {
+    const NAME: &'static str = "secp256k1";
+}
+
+#[cfg(any(feature = "chacha20poly1305", test))]
+impl NoiseName for chacha20poly1305::ChaCha20Poly1305 {
+    const NAME: &'static str = "ChaChaPoly";
+}
+
+#[cfg(any(feature = "aes-gcm", test))]
+impl NoiseName for aes_gcm::Aes256Gcm {
+    const NAME: &'static str = "AESGCM";
+}
+
+#[cfg(any(feature = "sha2", test))]
+impl NoiseName for sha2::Sha256 {
+    const NAME: &'static str = "SHA256";
+}
+
+#[cfg(any(feature = "sha2", test))]
+impl NoiseName for sha2::Sha512 {
+    const NAME: &'static str = "SHA512";
+}
+
+#[cfg(any(feature = "blake2", test))]
+impl NoiseName for blake2::Blake2s256 {
+    const NAME: &'static str = "BLAKE2s";
+}
+
+#[cfg(any(feature = "blake2", test))]
+impl NoiseName for blake2::Blake2b512 {
+    const NAME: &'static str = "BLAKE2b";
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtocolNameError {
+    Prefix,
+    Parts,
+    Pattern,
+    Modifier,
+    DhMismatch,
+    CipherMismatch,
+    HashMismatch,
+    Kem,
+    KemMismatch,
+}
+
+impl fmt::Display for ProtocolNameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolNameError::Prefix => write!(f, "the name should start with \"Noise_\""),
+            ProtocolNameError::Parts => write!(f, "the name should consist of five parts"),
+            ProtocolNameError::Pattern => write!(f, "malformed pattern name"),
+            ProtocolNameError::Modifier => write!(f, "unknown pattern modifier"),
+            ProtocolNameError::DhMismatch => write!(f, "the DH function does not match"),
+            ProtocolNameError::CipherMismatch => write!(f, "the cipher does not match"),
+            ProtocolNameError::HashMismatch => write!(f, "the hash function does not match"),
+            ProtocolNameError::Kem => {
+                write!(f, "the KEM and the hfs modifier should be given together")
+            }
+            ProtocolNameError::KemMismatch => write!(f, "the KEM does not match"),
+        }
+    }
+}
+
+#[cfg(any(feature = "std", test))]
+impl std::error::Error for ProtocolNameError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Modifier {
+    Psk(u8),
+    Fallback,
+    Hfs,
+}
+
+impl Modifier {
+    fn parse(s: &str) -> Result<Self, ProtocolNameError> {
+        match s {
+            "fallback" => Ok(Modifier::Fallback),
+            "hfs" => Ok(Modifier::Hfs),
+            s => s
+                .strip_prefix("psk")
+                .filter(|n| !n.is_empty() && n.bytes().all(|c| c.is_ascii_digit()))
+                .and_then(|n| n.parse().ok())
+                .map(Modifier::Psk)
+                .ok_or(ProtocolNameError::Modifier),
+        }
+    }
+}
+
+impl fmt::Display for Modifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Modifier::Psk(n) => write!(f, "psk{}", n),
+            Modifier::Fallback => write!(f, "fallback"),
+            Modifier::Hfs => write!(f, "hfs"),
+        }
+    }
+}
+
+/// A protocol name like `Noise_XKpsk3_25519_ChaChaPoly_SHA256`, the section 8 of the Noise spec.
+///
+/// The `pattern` includes the modifiers, exactly as `HandshakePattern::name` does.
+/// The KEM of `hfs` patterns follows the DH function, e.g. `25519+Kyber1024`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtocolName<'a> {
+    pub pattern: &'a str,
+    pub dh: &'a str,
+    pub kem: Option<&'a str>,
+    pub cipher: &'a str,
+    pub hash: &'a str,
+}
+
+impl<'a> ProtocolName<'a> {
+    /// # Errors
+    /// the name is malformed or has an unknown modifier
+    pub fn parse(name: &'a str) -> Result<Self, ProtocolNameError> {
+        let name = name
+            .strip_prefix("Noise_")
+            .ok_or(ProtocolNameError::Prefix)?;
+        let mut parts = name.split('_');
+        let mut next = || {
+            parts
+                .next()
+                .filter(|part| !part.is_empty())
+                .ok_or(ProtocolNameError::Parts)
+        };
+        let pattern = next()?;
+        let dh = next()?;
+        let (dh, kem) = match dh.split_once('+') {
+            Some((dh, kem)) if !dh.is_empty() && !kem.is_empty() => (dh, Some(kem)),
+            Some(_) => return Err(ProtocolNameError::Parts),
+            None => (dh, None),
+        };
+        let s = ProtocolName {
+            pattern,
+            dh,
+            kem,
+            cipher: next()?,
+            hash: next()?,
+        };
+        if parts.next().is_some() {
+            return Err(ProtocolNameError::Parts);
+        }
+
+        let base = s.base_pattern();
+        let valid_base = base
+            .bytes()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+        if base.is_empty() || !valid_base {
+            return Err(ProtocolNameError::Pattern);
+        }
+        if base.len() < s.pattern.len() {
+            for modifier in s.pattern[base.len()..].split('+') {
+                Modifier::parse(modifier)?;
+            }
+        }
+        if s.modifiers().any(|m| m == Modifier::Hfs) != s.kem.is_some() {
+            return Err(ProtocolNameError::Kem);
+        }
+
+        Ok(s)
+    }
+
+    /// The pattern name without modifiers, e.g. `XK` for `XKpsk3`.
+    pub fn base_pattern(&self) -> &'a str {
+        let end = self
+            .pattern
+            .find(|c: char| c.is_ascii_lowercase())
+            .unwrap_or(self.pattern.len());
+        &self.pattern[..end]
+    }
+
+    pub fn modifiers(&self) -> impl Iterator<Item = Modifier> + 'a {
+        let modifiers = &self.pattern[self.base_pattern().len()..];
+        modifiers
+            .split('+')
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| Modifier::parse(s).ok())
+    }
+
+    /// Checks the DH function, the KEM, the cipher and the hash function against the config.
+    ///
+    /// # Errors
+    /// the first mismatched part
+    pub fn check<C>(&self) -> Result<(), ProtocolNameError>
+    where
+        C: Config,
+        C::Dh: NoiseName,
+        C::Aead: NoiseName,
+        C::MixHash: NoiseName,
+    {
+        if self.dh != <C::Dh as NoiseName>::NAME {
+            Err(ProtocolNameError::DhMismatch)
+        } else if self.kem.is_some() && self.kem != <C::Kem as Kem>::NAME {
+            Err(ProtocolNameError::KemMismatch)
+        } else if self.cipher != <C::Aead as NoiseName>::NAME {
+            Err(ProtocolNameError::CipherMismatch)
+        } else if self.hash != <C::MixHash as NoiseName>::NAME {
+            Err(ProtocolNameError::HashMismatch)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub(crate) fn parts(&self) -> [&'a str; 10] {
+        [
+            "Noise_",
+            self.pattern,
+            "_",
+            self.dh,
+            if self.kem.is_some() { "+" } else { "" },
+            self.kem.unwrap_or_default(),
+            "_",
+            self.cipher,
+            "_",
+            self.hash,
+        ]
+    }
+}
+
+impl ProtocolName<'static> {
+    /// The canonical name of the `pattern` used with the config,
+    /// the KEM is given for `hfs` patterns only.
+    pub fn new<C>(pattern: &HandshakePattern) -> Self
+    where
+        C: Config,
+        C::Dh: NoiseName,
+        C::Aead: NoiseName,
+        C::MixHash: NoiseName,
+    {
+        ProtocolName {
+            pattern: pattern.name,
+            dh: <C::Dh as NoiseName>::NAME,
+            kem: if pattern.is_hfs() {
+                <C::Kem as Kem>::NAME
+            } else {
+                None
+            },
+            cipher: <C::Aead as NoiseName>::NAME,
+            hash: <C::MixHash as NoiseName>::NAME,
+        }
+    }
+}
+
+impl<'a> fmt::Display for ProtocolName<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.parts().iter().try_for_each(|part| f.write_str(part))
+    }
+}
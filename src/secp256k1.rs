@@ -0,0 +1,69 @@
+use {
+    k256::{
+        elliptic_curve::sec1::ToEncodedPoint, CompressedPoint, FieldBytes, ProjectivePoint,
+        PublicKey, SecretKey,
+    },
+    rand_core::{RngCore, CryptoRng},
+    sha2::{Digest, Sha256},
+};
+
+use super::dh::Dh;
+
+/// ECDH over secp256k1, `secp256k1` in protocol names. The public keys are 33 bytes
+/// compressed points, the shared secret is SHA256 of the compressed shared point like BOLT 8 has.
+pub struct Secp256k1;
+
+/// Nonzero scalar below the group order, zeroized on drop.
+pub struct Secp256k1Secret(SecretKey);
+
+impl Secp256k1Secret {
+    /// Big-endian scalar, the caller should zeroize it.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_bytes().into()
+    }
+}
+
+impl Dh for Secp256k1 {
+    const DHLEN: usize = 33;
+
+    type Secret = Secp256k1Secret;
+    type Public = [u8; 33];
+    type SharedSecret = [u8; 32];
+
+    fn generate<R>(rng: &mut R) -> Self::Secret
+    where
+        R: RngCore + CryptoRng,
+    {
+        Secp256k1Secret(SecretKey::random(rng))
+    }
+
+    /// Returns `None` if the scalar is zero or not below the group order.
+    fn secret_from_bytes(bytes: &[u8]) -> Option<Self::Secret> {
+        if bytes.len() != 32 {
+            return None;
+        }
+        SecretKey::from_bytes(FieldBytes::from_slice(bytes))
+            .ok()
+            .map(Secp256k1Secret)
+    }
+
+    fn public(secret: &Self::Secret) -> Self::Public {
+        CompressedPoint::from(&secret.0.public_key()).into()
+    }
+
+    /// Only compressed points on the curve are accepted.
+    fn public_from_bytes(bytes: &[u8]) -> Option<Self::Public> {
+        let public: [u8; 33] = bytes.try_into().ok()?;
+        if public[0] != 2 && public[0] != 3 {
+            return None;
+        }
+        PublicKey::from_sec1_bytes(bytes).ok().map(|_| public)
+    }
+
+    fn dh(secret: &Self::Secret, public: &Self::Public) -> Option<Self::SharedSecret> {
+        let public = PublicKey::from_sec1_bytes(public).ok()?;
+        let point = ProjectivePoint::from(*public.as_affine()) * *secret.0.to_nonzero_scalar();
+        let point = point.to_affine().to_encoded_point(true);
+        Some(Sha256::digest(point.as_bytes()).into())
+    }
+}
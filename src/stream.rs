@@ -0,0 +1,186 @@
+use std::io::{self, Read, Write};
+
+use alloc::{vec, vec::Vec};
+
+use super::{
+    config::ConfigExt,
+    dh::Dh,
+    framing::{LENGTH_PREFIX_LEN, MAX_FRAME_LEN, FrameError, FrameSender, Framed},
+    handshake_state::{HandshakeError, HandshakeState},
+    symmetric_state::Hash,
+};
+
+fn invalid_data<E>(error: E) -> io::Error
+where
+    E: Into<std::boxed::Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+impl From<HandshakeError> for io::Error {
+    fn from(v: HandshakeError) -> Self {
+        invalid_data(v)
+    }
+}
+
+impl From<FrameError> for io::Error {
+    fn from(v: FrameError) -> Self {
+        invalid_data(v)
+    }
+}
+
+/// The blocking stream, the handshake and transport messages are length-prefixed.
+pub struct NoiseStream<C, T, const STEP: u64 = 1>
+where
+    C: ConfigExt,
+{
+    inner: T,
+    framed: Framed<C, STEP>,
+    // decrypted, but not yet read
+    plaintext: Vec<u8>,
+    position: usize,
+    // encrypted, but not yet written
+    ciphertext: Vec<u8>,
+    written: usize,
+}
+
+impl<C, T, const STEP: u64> NoiseStream<C, T, STEP>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+    T: Read + Write,
+{
+    /// Runs the handshake over `inner`, the handshake payloads are empty.
+    ///
+    /// # Errors
+    /// io error or the handshake failed
+    pub fn handshake(mut inner: T, mut handshake: HandshakeState<C>) -> io::Result<Self> {
+        let mut buffer = vec![0; LENGTH_PREFIX_LEN + MAX_FRAME_LEN];
+        while !handshake.is_finished() {
+            if handshake.is_write_turn() {
+                let (prefix, message) = buffer.split_at_mut(LENGTH_PREFIX_LEN);
+                let len = handshake.write_message(&[], message)?;
+                prefix.clone_from_slice(&(len as u16).to_be_bytes());
+                inner.write_all(&buffer[..(LENGTH_PREFIX_LEN + len)])?;
+                inner.flush()?;
+            } else {
+                let mut prefix = [0; LENGTH_PREFIX_LEN];
+                inner.read_exact(&mut prefix)?;
+                let message = &mut buffer[..(u16::from_be_bytes(prefix) as usize)];
+                inner.read_exact(message)?;
+                handshake.read_message(message)?;
+            }
+        }
+
+        Ok(NoiseStream {
+            inner,
+            framed: handshake.finish()?.into(),
+            plaintext: Vec::new(),
+            position: 0,
+            ciphertext: Vec::new(),
+            written: 0,
+        })
+    }
+}
+
+impl<C, T, const STEP: u64> NoiseStream<C, T, STEP>
+where
+    C: ConfigExt,
+{
+    /// The handshake hash, the channel binding value.
+    pub fn hash(&self) -> &Hash<C> {
+        &self.framed.hash
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<C, T, const STEP: u64> Read for NoiseStream<C, T, STEP>
+where
+    C: ConfigExt,
+    T: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        while self.position == self.plaintext.len() {
+            if let Some(plaintext) = self.framed.receiver.decrypt()? {
+                self.plaintext = plaintext;
+                self.position = 0;
+                continue;
+            }
+            let mut chunk = vec![0; self.framed.receiver.missing()];
+            let len = self.inner.read(&mut chunk)?;
+            if len == 0 {
+                return if self.framed.receiver.pending() == 0 {
+                    Ok(0)
+                } else {
+                    Err(io::ErrorKind::UnexpectedEof.into())
+                };
+            }
+            self.framed.receiver.push(&chunk[..len]);
+        }
+
+        let plaintext = &self.plaintext[self.position..];
+        let len = plaintext.len().min(buf.len());
+        buf[..len].clone_from_slice(&plaintext[..len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
+impl<C, T, const STEP: u64> Write for NoiseStream<C, T, STEP>
+where
+    C: ConfigExt,
+    T: Write,
+{
+    /// Accepts at most one frame of the payload, and only when the previous one is written,
+    /// so the frame interrupted by an io error is resumed rather than lost.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        self.drain()?;
+        let len = buf.len().min(FrameSender::<C, STEP>::MAX_PAYLOAD_LEN);
+        self.framed
+            .sender
+            .encrypt(&buf[..len], &mut self.ciphertext)?;
+        // the payload is accepted anyway, an error will be reported by the next call
+        let _ = self.drain();
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.drain()?;
+        self.inner.flush()
+    }
+}
+
+impl<C, T, const STEP: u64> NoiseStream<C, T, STEP>
+where
+    C: ConfigExt,
+    T: Write,
+{
+    fn drain(&mut self) -> io::Result<()> {
+        while self.written < self.ciphertext.len() {
+            match self.inner.write(&self.ciphertext[self.written..])? {
+                0 => return Err(io::ErrorKind::WriteZero.into()),
+                len => self.written += len,
+            }
+        }
+        self.ciphertext.clear();
+        self.written = 0;
+        Ok(())
+    }
+}
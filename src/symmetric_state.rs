@@ -6,13 +6,14 @@ use {
         GenericArray,
         typenum::{self, Unsigned},
     },
-    zeroize::Zeroize,
+    zeroize::{Zeroize, Zeroizing},
 };
 
 use super::{
     config::{Config, ConfigExt},
     hash::{MixHash, HkdfSplitExt},
     cipher_state::{MacMismatch, Tag, Cipher},
+    protocol_name::{NoiseName, ProtocolName, ProtocolNameError},
 };
 
 pub struct Output<C, const STEP: u64>
@@ -41,6 +42,8 @@ where
 {
     chaining_key: ChainingKey<C>,
     aead: C::Aead,
+    // kept to serialize the state
+    aead_key: Zeroizing<AeadKey<C>>,
     nonce: PhantomData<N>,
 }
 
@@ -57,24 +60,65 @@ where
 impl<C, N> Key<C, N>
 where
     C: Config,
-    N: Unsigned + Add<typenum::U1>,
-    <N as Add<typenum::U1>>::Output: Unsigned,
+    N: Unsigned,
 {
+    fn new(chaining_key: ChainingKey<C>, aead_key: &AeadKey<C>) -> Self {
+        Key {
+            chaining_key,
+            aead: C::Aead::new(aead_key),
+            aead_key: Zeroizing::new(aead_key.clone()),
+            nonce: PhantomData,
+        }
+    }
+
     #[allow(clippy::missing_const_for_fn)]
-    fn increase(self) -> Key<C, <N as Add<typenum::U1>>::Output> {
+    fn cast<M>(self) -> Key<C, M>
+    where
+        M: Unsigned,
+    {
         let Key {
-            chaining_key, aead, ..
+            chaining_key,
+            aead,
+            aead_key,
+            ..
         } = self;
         Key {
             chaining_key,
             aead,
+            aead_key,
             nonce: PhantomData,
         }
     }
 }
 
+impl<C, N> Key<C, N>
+where
+    C: Config,
+    N: Unsigned + Add<typenum::U1>,
+    <N as Add<typenum::U1>>::Output: Unsigned,
+{
+    fn increase(self) -> Key<C, <N as Add<typenum::U1>>::Output> {
+        self.cast()
+    }
+}
+
+impl<C, N> SymmetricState<C, Key<C, N>>
+where
+    C: Config,
+    N: Unsigned,
+{
+    // the nonce is tracked at runtime after this, see `DynSymmetricState`
+    pub(crate) fn forget_nonce(self) -> SymmetricState<C, Key<C, typenum::U0>> {
+        SymmetricState {
+            key: self.key.cast(),
+            hash: self.hash,
+        }
+    }
+}
+
 type SymmetricStateNext<C, N> = SymmetricState<C, Key<C, <N as Add<typenum::U1>>::Output>>;
-type Hash<C> = GenericArray<u8, <<C as Config>::MixHash as MixHash>::L>;
+type AeadKey<C> = GenericArray<u8, <<C as Config>::Aead as KeySizeUser>::KeySize>;
+pub(crate) type Hash<C> = GenericArray<u8, <<C as Config>::MixHash as MixHash>::L>;
 pub type ChainingKey<C> = GenericArray<u8, <<C as Config>::MixHash as MixHash>::L>;
 
 #[derive(Clone)]
@@ -92,14 +136,41 @@ where
 {
     #[must_use]
     pub fn new(name: &str) -> Self {
-        let length = name.as_bytes().len();
+        Self::init(&[name.as_bytes()])
+    }
+
+    /// Same as `new`, but refuses the name if it does not match the config.
+    ///
+    /// # Errors
+    /// the name is malformed or does not match the config
+    pub fn new_checked(name: &str) -> Result<Self, ProtocolNameError>
+    where
+        C::Dh: NoiseName,
+        C::Aead: NoiseName,
+        C::MixHash: NoiseName,
+    {
+        ProtocolName::parse(name)?.check::<C>()?;
+        Ok(Self::new(name))
+    }
+
+    #[must_use]
+    pub fn from_protocol_name(name: &ProtocolName<'_>) -> Self {
+        Self::init(&name.parts().map(str::as_bytes))
+    }
+
+    fn init(name: &[&[u8]]) -> Self {
+        let length = name.iter().map(|part| part.len()).sum::<usize>();
         let size = <C::MixHash as MixHash>::L::USIZE;
         let hash = if length <= size {
             let mut array = GenericArray::default();
-            array[0..length].copy_from_slice(name.as_bytes());
+            let mut pos = 0;
+            for part in name {
+                array[pos..(pos + part.len())].copy_from_slice(part);
+                pos += part.len();
+            }
             array
         } else {
-            C::MixHash::init(name.as_bytes())
+            C::MixHash::init_parts(name)
         };
 
         SymmetricState {
@@ -134,16 +205,17 @@ where
     where
         S: AsRef<[u8]> + Zeroize,
     {
+        let state = self.mix_key(data.as_ref());
+        data.zeroize();
+        state
+    }
+
+    pub(crate) fn mix_key(self, data: &[u8]) -> SymmetricState<C, Key<C, typenum::U0>> {
         let SymmetricState { key, hash } = self;
 
         let chaining_key = key.into();
-        let (chaining_key, mut aead) = C::HkdfSplit::split_2(&chaining_key, data.as_ref());
-        data.zeroize();
-        let key = Key {
-            chaining_key,
-            aead: C::Aead::new(&aead),
-            nonce: PhantomData,
-        };
+        let (chaining_key, mut aead) = C::HkdfSplit::split_2(&chaining_key, data);
+        let key = Key::new(chaining_key, &aead);
         aead.zeroize();
         SymmetricState { key, hash }
     }
@@ -157,11 +229,7 @@ where
         let chaining_key = key.into();
         let (chaining_key, middle, mut aead) = C::HkdfSplit::split_3(&chaining_key, data.as_ref());
         data.zeroize();
-        let key = Key {
-            chaining_key,
-            aead: C::Aead::new(&aead),
-            nonce: PhantomData,
-        };
+        let key = Key::new(chaining_key, &aead);
         aead.zeroize();
         let hash = C::MixHash::mix_hash(hash, middle.as_ref());
         SymmetricState { key, hash }
@@ -258,19 +326,9 @@ where
     /// # Panics
     ///
     /// when `data` slice is too long (gigabytes)
-    pub fn encrypt(self, data: &mut [u8]) -> (SymmetricStateNext<C, N>, Tag<C>) {
-        let tag = self
-            .key
-            .aead
-            .encrypt_in_place_detached(&C::prepare_nonce(N::U64), &self.hash, data)
-            .unwrap();
-        (
-            SymmetricState {
-                key: self.key.increase(),
-                hash: C::MixHash::mix_parts(self.hash, &[data, &tag]),
-            },
-            tag,
-        )
+    pub fn encrypt(mut self, data: &mut [u8]) -> (SymmetricStateNext<C, N>, Tag<C>) {
+        let tag = self.encrypt_at(N::U64, data);
+        (self.increase(), tag)
     }
 
     #[cfg(feature = "alloc")]
@@ -283,35 +341,72 @@ where
     /// # Errors
     /// mac mismatch
     pub fn decrypt(
-        self,
+        mut self,
         data: &mut [u8],
         tag: &Tag<C>,
     ) -> Result<SymmetricStateNext<C, N>, MacMismatch> {
+        self.decrypt_at(N::U64, data, tag).map(|()| self.increase())
+    }
+}
+
+impl<C, N> SymmetricState<C, Key<C, N>>
+where
+    C: ConfigExt,
+    N: Unsigned,
+{
+    #[cfg(any(feature = "bolt8", test))]
+    pub(crate) const fn chaining_key(&self) -> &ChainingKey<C> {
+        &self.key.chaining_key
+    }
+
+    /// Same as `encrypt`, but the nonce is given at runtime and the type stays the same.
+    ///
+    /// # Panics
+    ///
+    /// when `data` slice is too long (gigabytes)
+    pub(crate) fn encrypt_at(&mut self, nonce: u64, data: &mut [u8]) -> Tag<C> {
+        let tag = self
+            .key
+            .aead
+            .encrypt_in_place_detached(&C::prepare_nonce(nonce), &self.hash, data)
+            .unwrap();
+        self.hash = C::MixHash::mix_parts(self.hash.clone(), &[data, &tag]);
+        tag
+    }
+
+    /// Same as `decrypt`, but the nonce is given at runtime and the type stays the same.
+    ///
+    /// # Errors
+    /// mac mismatch
+    pub(crate) fn decrypt_at(
+        &mut self,
+        nonce: u64,
+        data: &mut [u8],
+        tag: &Tag<C>,
+    ) -> Result<(), MacMismatch> {
         let hash = C::MixHash::mix_parts(self.hash.clone(), &[data, tag]);
         self.key
             .aead
-            .decrypt_in_place_detached(&C::prepare_nonce(N::U64), &self.hash, data, tag)
-            .map(|()| SymmetricState {
-                key: self.key.increase(),
-                hash,
-            })
+            .decrypt_in_place_detached(&C::prepare_nonce(nonce), &self.hash, data, tag)
+            .map(|()| self.hash = hash)
             .map_err(|_| MacMismatch)
     }
 }
 
 #[cfg(feature = "serde")]
-mod serde_m {
+pub(crate) mod serde_m {
     use core::{mem, fmt};
     use alloc::string::String;
 
     use aead::KeySizeUser;
-    use generic_array::{GenericArray, typenum::Unsigned};
+    use generic_array::{GenericArray, ArrayLength, typenum::Unsigned};
     use serde::{Serialize, Deserialize};
+    use zeroize::{Zeroize, Zeroizing};
 
-    use super::{SymmetricState, ChainingKey, Config, Hash, OutputRaw};
+    use super::{SymmetricState, ChainingKey, Key, Config, Hash, OutputRaw};
 
     #[derive(Serialize, Deserialize, Debug)]
-    struct Inner {
+    pub(crate) struct Inner {
         key: String,
         hash: String,
     }
@@ -328,7 +423,7 @@ mod serde_m {
         }
     }
 
-    impl<'de, C> TryFrom<Inner> for SymmetricState<C, ChainingKey<C>>
+    impl<C> TryFrom<Inner> for SymmetricState<C, ChainingKey<C>>
     where
         C: Config,
     {
@@ -388,6 +483,105 @@ mod serde_m {
         }
     }
 
+    #[derive(Serialize, Deserialize)]
+    pub(crate) struct KeyedInner {
+        key: String,
+        cipher_key: String,
+        pub(crate) nonce: u64,
+        hash: String,
+    }
+
+    impl Drop for KeyedInner {
+        fn drop(&mut self) {
+            self.key.zeroize();
+            self.cipher_key.zeroize();
+        }
+    }
+
+    fn decode<L>(s: &str) -> Result<GenericArray<u8, L>, hex::FromHexError>
+    where
+        L: ArrayLength<u8>,
+    {
+        let v = Zeroizing::new(hex::decode(s)?);
+        if v.len() == L::USIZE {
+            Ok(GenericArray::from_slice(&v).clone())
+        } else {
+            Err(hex::FromHexError::InvalidStringLength)
+        }
+    }
+
+    impl<C, N> SymmetricState<C, Key<C, N>>
+    where
+        C: Config,
+        N: Unsigned,
+    {
+        pub(crate) fn as_keyed_inner(&self, nonce: u64) -> KeyedInner {
+            KeyedInner {
+                key: hex::encode(&self.key.chaining_key),
+                cipher_key: hex::encode(&*self.key.aead_key),
+                nonce,
+                hash: hex::encode(&self.hash),
+            }
+        }
+
+        /// The nonce is not checked.
+        pub(crate) fn from_keyed_inner(inner: &KeyedInner) -> Result<Self, hex::FromHexError> {
+            let mut cipher_key = decode(&inner.cipher_key)?;
+            let key = Key::new(decode(&inner.key)?, &cipher_key);
+            cipher_key.zeroize();
+            Ok(SymmetricState {
+                key,
+                hash: decode(&inner.hash)?,
+            })
+        }
+    }
+
+    impl<'de, C, N> Deserialize<'de> for SymmetricState<C, Key<C, N>>
+    where
+        C: Config,
+        N: Unsigned,
+    {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            let inner = KeyedInner::deserialize(deserializer)?;
+            if inner.nonce != N::U64 {
+                return Err(serde::de::Error::custom(
+                    "the nonce does not match the type",
+                ));
+            }
+            Self::from_keyed_inner(&inner).map_err(serde::de::Error::custom)
+        }
+    }
+
+    impl<C, N> Serialize for SymmetricState<C, Key<C, N>>
+    where
+        C: Config,
+        N: Unsigned,
+    {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            self.as_keyed_inner(N::U64).serialize(serializer)
+        }
+    }
+
+    impl<C, N> fmt::Debug for SymmetricState<C, Key<C, N>>
+    where
+        C: Config,
+        N: Unsigned,
+    {
+        /// Both keys are left out, unlike the serialized form.
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("SymmetricState")
+                .field("nonce", &N::U64)
+                .field("hash", &hex::encode(&self.hash))
+                .finish_non_exhaustive()
+        }
+    }
+
     #[derive(Serialize, Deserialize, Debug)]
     struct OutputInner {
         sender: String,
@@ -408,7 +602,7 @@ mod serde_m {
         }
     }
 
-    impl<'de, C> TryFrom<OutputInner> for OutputRaw<C>
+    impl<C> TryFrom<OutputInner> for OutputRaw<C>
     where
         C: Config,
     {
@@ -487,3 +681,155 @@ mod serde_m {
         }
     }
 }
+
+mod binary_m {
+    use aead::KeySizeUser;
+    use generic_array::typenum::Unsigned;
+    use zeroize::Zeroize;
+
+    use super::{SymmetricState, ChainingKey, Key, Config, OutputRaw, MixHash, AeadKey};
+    use crate::{
+        binary::{Binary, BinaryError, Kind, Reader, Writer, BINARY_HEADER_LEN},
+        protocol_name::NoiseName,
+    };
+
+    const fn hash_len<C>() -> usize
+    where
+        C: Config,
+    {
+        <<C::MixHash as MixHash>::L as Unsigned>::USIZE
+    }
+
+    const fn key_len<C>() -> usize
+    where
+        C: Config,
+    {
+        <<C::Aead as KeySizeUser>::KeySize as Unsigned>::USIZE
+    }
+
+    impl<C> Binary for SymmetricState<C, ChainingKey<C>>
+    where
+        C: Config,
+        C::Aead: NoiseName,
+        C::MixHash: NoiseName,
+        C::Dh: NoiseName,
+    {
+        fn encoded_len(&self) -> usize {
+            BINARY_HEADER_LEN + 2 * hash_len::<C>()
+        }
+
+        fn encode(&self, buffer: &mut [u8]) -> Result<usize, BinaryError> {
+            let mut writer = Writer::new::<C>(buffer, Kind::State)?;
+            writer.put(&self.key)?;
+            writer.put(&self.hash)?;
+            Ok(writer.finish())
+        }
+
+        fn decode(bytes: &[u8]) -> Result<Self, BinaryError> {
+            let (mut reader, _) = Reader::new::<C>(bytes, &[Kind::State])?;
+            let state = SymmetricState {
+                key: reader.array()?,
+                hash: reader.array()?,
+            };
+            reader.finish()?;
+            Ok(state)
+        }
+    }
+
+    impl<C, N> SymmetricState<C, Key<C, N>>
+    where
+        C: Config,
+        C::Aead: NoiseName,
+        C::MixHash: NoiseName,
+        C::Dh: NoiseName,
+        N: Unsigned,
+    {
+        pub(crate) const fn keyed_encoded_len() -> usize {
+            BINARY_HEADER_LEN + 2 * hash_len::<C>() + key_len::<C>() + 8
+        }
+
+        pub(crate) fn encode_keyed(
+            &self,
+            nonce: u64,
+            buffer: &mut [u8],
+        ) -> Result<usize, BinaryError> {
+            let mut writer = Writer::new::<C>(buffer, Kind::KeyedState)?;
+            writer.put(&self.key.chaining_key)?;
+            writer.put(&self.key.aead_key)?;
+            writer.put_u64(nonce)?;
+            writer.put(&self.hash)?;
+            Ok(writer.finish())
+        }
+
+        /// Returns the state and the nonce, the nonce is not checked.
+        pub(crate) fn decode_keyed(mut reader: Reader<'_>) -> Result<(Self, u64), BinaryError> {
+            let chaining_key = reader.array()?;
+            let mut aead_key: AeadKey<C> = reader.array()?;
+            let key = Key::new(chaining_key, &aead_key);
+            aead_key.zeroize();
+            let nonce = reader.u64()?;
+            let state = SymmetricState {
+                key,
+                hash: reader.array()?,
+            };
+            reader.finish()?;
+            Ok((state, nonce))
+        }
+    }
+
+    impl<C, N> Binary for SymmetricState<C, Key<C, N>>
+    where
+        C: Config,
+        C::Aead: NoiseName,
+        C::MixHash: NoiseName,
+        C::Dh: NoiseName,
+        N: Unsigned,
+    {
+        fn encoded_len(&self) -> usize {
+            Self::keyed_encoded_len()
+        }
+
+        fn encode(&self, buffer: &mut [u8]) -> Result<usize, BinaryError> {
+            self.encode_keyed(N::U64, buffer)
+        }
+
+        fn decode(bytes: &[u8]) -> Result<Self, BinaryError> {
+            let (reader, _) = Reader::new::<C>(bytes, &[Kind::KeyedState])?;
+            match Self::decode_keyed(reader)? {
+                (state, nonce) if nonce == N::U64 => Ok(state),
+                _ => Err(BinaryError::NonceMismatch),
+            }
+        }
+    }
+
+    impl<C> Binary for OutputRaw<C>
+    where
+        C: Config,
+        C::Aead: NoiseName,
+        C::MixHash: NoiseName,
+        C::Dh: NoiseName,
+    {
+        fn encoded_len(&self) -> usize {
+            BINARY_HEADER_LEN + 2 * key_len::<C>() + hash_len::<C>()
+        }
+
+        fn encode(&self, buffer: &mut [u8]) -> Result<usize, BinaryError> {
+            let mut writer = Writer::new::<C>(buffer, Kind::Output)?;
+            writer.put(&self.sender)?;
+            writer.put(&self.receiver)?;
+            writer.put(&self.hash)?;
+            Ok(writer.finish())
+        }
+
+        fn decode(bytes: &[u8]) -> Result<Self, BinaryError> {
+            let (mut reader, _) = Reader::new::<C>(bytes, &[Kind::Output])?;
+            let output = OutputRaw {
+                sender: reader.array()?,
+                receiver: reader.array()?,
+                hash: reader.array()?,
+            };
+            reader.finish()?;
+            Ok(output)
+        }
+    }
+}
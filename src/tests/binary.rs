@@ -0,0 +1,158 @@
+use alloc::{vec, vec::Vec};
+
+use generic_array::typenum::{U0, U1};
+
+use crate::{
+    Aead, Binary, BinaryError, ChainingKey, Cipher, CipherRaw, ConfigExt, DynSymmetricState, Key,
+    NoiseName, OutputRaw, SymmetricState, BINARY_HEADER_LEN, BINARY_VERSION,
+};
+
+fn encode<T>(value: &T) -> Vec<u8>
+where
+    T: Binary,
+{
+    let mut buffer = vec![0; value.encoded_len()];
+    assert_eq!(value.encode(&mut buffer), Ok(buffer.len()));
+    buffer
+}
+
+/// Each value is encoded, decoded and encoded again to the same bytes.
+pub fn round_trip<C>(name: &str)
+where
+    C: ConfigExt,
+    C::Aead: NoiseName,
+    C::MixHash: NoiseName,
+    C::Dh: NoiseName,
+{
+    let state = SymmetricState::<C, _>::new(name).mix_hash(b"prologue");
+    let bytes = encode(&state);
+    assert_eq!(bytes[..2], [BINARY_VERSION, 0]);
+    let restored = SymmetricState::<C, ChainingKey<C>>::decode(&bytes).unwrap();
+    assert_eq!(restored.hash(), state.hash());
+    // the same encoding as the dynamic state
+    let dynamic = DynSymmetricState::<C>::decode(&bytes).unwrap();
+    assert!(!dynamic.has_key());
+    assert_eq!(encode(&dynamic), bytes);
+
+    let (state, tag) = state.mix_shared_secret([1; 32]).encrypt(&mut [0; 4]);
+    let bytes = encode(&state);
+    let restored = SymmetricState::<C, Key<C, U1>>::decode(&bytes).unwrap();
+    assert_eq!(encode(&restored), bytes);
+    let dynamic = DynSymmetricState::<C>::decode(&bytes).unwrap();
+    assert_eq!(dynamic.nonce(), Some(1));
+    assert_eq!(encode(&dynamic), bytes);
+
+    // the restored states continue equally
+    let (state, tag_typed) = restored.encrypt(&mut [0; 4]);
+    let (dynamic, tag_dyn) = dynamic.encrypt(&mut [0; 4]).unwrap();
+    assert_ne!(tag_typed, tag);
+    assert_eq!(Some(tag_typed), tag_dyn);
+    assert_eq!(state.hash(), dynamic.hash());
+
+    let output = state.finish_raw::<1, false>();
+    let bytes = encode(&output);
+    let restored = OutputRaw::<C>::decode(&bytes).unwrap();
+    assert_eq!(restored.sender, output.sender);
+    assert_eq!(restored.receiver, output.receiver);
+    assert_eq!(restored.hash, output.hash);
+
+    let mut sender = Cipher::<C, 1, true>::new(&output.sender);
+    let mut receiver = Cipher::<C, 1, false>::new(&output.sender);
+    let mut buffer = *b"hello";
+    let tag = sender.encrypt(&[], &mut buffer).unwrap();
+    let raw = CipherRaw::<C>::decode(&encode(&sender.export())).unwrap();
+    assert_eq!(*raw.key, output.sender);
+    assert_eq!((raw.nonce, raw.rekey_interval), (1, None));
+    let mut sender = Cipher::<C, 1, true>::import(&raw);
+    receiver.decrypt(&[], &mut buffer, &tag).unwrap();
+    let tag = sender.encrypt(&[], &mut buffer).unwrap();
+    receiver.decrypt(&[], &mut buffer, &tag).unwrap();
+    assert_eq!(&buffer, b"hello");
+}
+
+/// The value encoded with `C` is rejected by `D`.
+pub fn config_mismatch<C, D>(name: &str)
+where
+    C: ConfigExt,
+    C::Aead: NoiseName,
+    C::MixHash: NoiseName,
+    C::Dh: NoiseName,
+    D: ConfigExt,
+    D::Aead: NoiseName,
+    D::MixHash: NoiseName,
+    D::Dh: NoiseName,
+{
+    let state = SymmetricState::<C, _>::new(name);
+    let bytes = encode(&state);
+    assert_eq!(
+        SymmetricState::<D, ChainingKey<D>>::decode(&bytes).err(),
+        Some(BinaryError::ConfigMismatch),
+    );
+    assert_eq!(
+        DynSymmetricState::<D>::decode(&bytes).err(),
+        Some(BinaryError::ConfigMismatch),
+    );
+    let raw = Cipher::<C, 1, true>::new(&Aead::<C>::default()).export();
+    assert_eq!(
+        CipherRaw::<D>::decode(&encode(&raw)).err(),
+        Some(BinaryError::ConfigMismatch),
+    );
+}
+
+pub fn errors<C>(name: &str)
+where
+    C: ConfigExt,
+    C::Aead: NoiseName,
+    C::MixHash: NoiseName,
+    C::Dh: NoiseName,
+{
+    let state = SymmetricState::<C, _>::new(name).mix_shared_secret([1; 32]);
+    let bytes = encode(&state);
+    assert_eq!(
+        SymmetricState::<C, Key<C, U1>>::decode(&bytes).err(),
+        Some(BinaryError::NonceMismatch),
+    );
+    assert!(SymmetricState::<C, Key<C, U0>>::decode(&bytes).is_ok());
+    assert_eq!(
+        SymmetricState::<C, ChainingKey<C>>::decode(&bytes).err(),
+        Some(BinaryError::Kind),
+    );
+    assert_eq!(
+        OutputRaw::<C>::decode(&bytes).err(),
+        Some(BinaryError::Kind)
+    );
+
+    let mut exhausted = bytes.clone();
+    let end = bytes.len() - state.hash().len();
+    exhausted[(end - 8)..end].fill(0xff);
+    assert_eq!(
+        DynSymmetricState::<C>::decode(&exhausted).err(),
+        Some(BinaryError::NonceMismatch),
+    );
+
+    let mut other = bytes.clone();
+    other[0] = BINARY_VERSION + 1;
+    assert_eq!(
+        DynSymmetricState::<C>::decode(&other).err(),
+        Some(BinaryError::Version(BINARY_VERSION + 1)),
+    );
+    for other in [
+        &bytes[..(bytes.len() - 1)],
+        &[bytes.as_slice(), &[0]].concat(),
+    ] {
+        assert_eq!(
+            DynSymmetricState::<C>::decode(other).err(),
+            Some(BinaryError::Length),
+        );
+    }
+    assert_eq!(
+        DynSymmetricState::<C>::decode(&bytes[..BINARY_HEADER_LEN]).err(),
+        Some(BinaryError::Length),
+    );
+
+    let mut buffer = vec![0; bytes.len() - 1];
+    assert_eq!(state.encode(&mut buffer), Err(BinaryError::BufferTooSmall));
+    let mut buffer = vec![0; bytes.len() + 1];
+    assert_eq!(state.encode(&mut buffer), Ok(bytes.len()));
+    assert_eq!(buffer[..bytes.len()], bytes);
+}
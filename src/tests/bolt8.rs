@@ -0,0 +1,245 @@
+use rand_core::OsRng;
+
+use crate::{
+    Bolt8Config, Bolt8Error, Bolt8Initiator, Bolt8Responder, Bolt8Transport, CipherError,
+    BOLT8_HEADER_LEN, BOLT8_TAG_LEN,
+};
+
+// the test vectors from the BOLT 8
+const INITIATOR_STATIC: [u8; 32] = [0x11; 32];
+const INITIATOR_EPHEMERAL: [u8; 32] = [0x12; 32];
+const RESPONDER_STATIC: [u8; 32] = [0x21; 32];
+const RESPONDER_EPHEMERAL: [u8; 32] = [0x22; 32];
+const RESPONDER_STATIC_PUBLIC: &str =
+    "028d7500dd4c12685d1f568b4c2b5048e8534b873319f3a8daa612b469132ec7f7";
+const INITIATOR_STATIC_PUBLIC: &str =
+    "034f355bdcb7cc0af728ef3cceb9615d90684bb5b2ca5f859ab0f0b704075871aa";
+
+const ACT_ONE: &str = "00036360e856310ce5d294e8be33fc807077dc56ac80d95d9cd4ddbd21325eff73f70df6086551151f58b8afe6c195782c6a";
+const ACT_TWO: &str = "0002466d7fcae563e5cb09a0d1870bb580344804617879a14949cf22285f1bae3f276e2470b93aac583c9ef6eafca3f730ae";
+const ACT_THREE: &str = "00b9e3a702e93e3a9948c2ed6e5fd7590a6e1c3a0344cfc9d5b57357049aa22355361aa02e55a8fc28fef5bd6d71ad0c38228dc68b1c466263b47fdf31e560e139ba";
+
+const MESSAGES: [(usize, &str); 6] = [
+    (
+        0,
+        "cf2b30ddf0cf3f80e7c35a6e6730b59fe802473180f396d88a8fb0db8cbcf25d2f214cf9ea1d95",
+    ),
+    (
+        1,
+        "72887022101f0b6753e0c7de21657d35a4cb2a1f5cde2650528bbc8f837d0f0d7ad833b1a256a1",
+    ),
+    (
+        500,
+        "178cb9d7387190fa34db9c2d50027d21793c9bc2d40b1e14dcf30ebeeeb220f48364f7a4c68bf8",
+    ),
+    (
+        501,
+        "1b186c57d44eb6de4c057c49940d79bb838a145cb528d6e8fd26dbe50a60ca2c104b56b60e45bd",
+    ),
+    (
+        1000,
+        "4a2f3cc3b5e78ddb83dcb426d9863d9d9a723b0337c89dd0b005d89f8d3c05c52b76b29b740f09",
+    ),
+    (
+        1001,
+        "2ecd8c8a5629d0d02ab457a0fdd0f7b90a192cd46be5ecb6ca570bfc5e268338b1a16cf4ef2d36",
+    ),
+];
+
+fn array<const N: usize>(hex: &str) -> [u8; N] {
+    hex::decode(hex).unwrap().try_into().unwrap()
+}
+
+fn handshake() -> (Bolt8Transport, Bolt8Transport) {
+    let rs = array(RESPONDER_STATIC_PUBLIC);
+    let (initiator, act_one) =
+        Bolt8Initiator::with_ephemeral(&INITIATOR_STATIC, &INITIATOR_EPHEMERAL, &rs).unwrap();
+    assert_eq!(hex::encode(act_one), ACT_ONE);
+
+    let (responder, act_two) =
+        Bolt8Responder::with_ephemeral(&RESPONDER_STATIC, &RESPONDER_EPHEMERAL, &act_one).unwrap();
+    assert_eq!(hex::encode(act_two), ACT_TWO);
+
+    let (act_three, initiator) = initiator.read_act_two(&act_two).unwrap();
+    assert_eq!(hex::encode(act_three), ACT_THREE);
+
+    let (responder, remote_static) = responder.read_act_three(&act_three).unwrap();
+    assert_eq!(hex::encode(remote_static), INITIATOR_STATIC_PUBLIC);
+    assert_eq!(initiator.hash, responder.hash);
+
+    (initiator, responder)
+}
+
+pub fn vectors() {
+    let (mut initiator, mut responder) = handshake();
+
+    let mut expected = MESSAGES.iter().peekable();
+    let mut buffer = [0; 64];
+    for n in 0..=1001 {
+        let len = initiator.sender.encrypt(b"hello", &mut buffer).unwrap();
+        assert_eq!(len, BOLT8_HEADER_LEN + 5 + BOLT8_TAG_LEN);
+        if let Some((_, ciphertext)) = expected.next_if(|(m, _)| *m == n) {
+            assert_eq!(hex::encode(&buffer[..len]), *ciphertext);
+        }
+
+        let (header, body) = buffer[..len].split_at_mut(BOLT8_HEADER_LEN);
+        let body_len = responder
+            .receiver
+            .decrypt_header(&header.try_into().unwrap())
+            .unwrap();
+        assert_eq!(body_len, 5);
+        let len = responder.receiver.decrypt_body(body).unwrap();
+        assert_eq!(&body[..len], b"hello");
+    }
+    assert!(expected.next().is_none());
+}
+
+pub fn errors() {
+    let rs = array(RESPONDER_STATIC_PUBLIC);
+    let act_one = array::<50>(ACT_ONE);
+    let act_two = array::<50>(ACT_TWO);
+    let act_three = array::<66>(ACT_THREE);
+    let initiator = || {
+        Bolt8Initiator::with_ephemeral(&INITIATOR_STATIC, &INITIATOR_EPHEMERAL, &rs)
+            .unwrap()
+            .0
+    };
+    let responder = || {
+        Bolt8Responder::with_ephemeral(&RESPONDER_STATIC, &RESPONDER_EPHEMERAL, &act_one)
+            .unwrap()
+            .0
+    };
+
+    let mut act = act_two;
+    act[0] = 1;
+    assert_eq!(
+        initiator().read_act_two(&act).err(),
+        Some(Bolt8Error::UnknownVersion(1))
+    );
+    let mut act = act_two;
+    act[1] = 4;
+    assert_eq!(
+        initiator().read_act_two(&act).err(),
+        Some(Bolt8Error::InvalidPublicKey)
+    );
+    let mut act = act_two;
+    act[49] ^= 1;
+    assert_eq!(
+        initiator().read_act_two(&act).err(),
+        Some(Bolt8Error::Cipher(CipherError::MacMismatch))
+    );
+
+    let mut act = act_one;
+    act[0] = 1;
+    assert_eq!(
+        Bolt8Responder::new(&RESPONDER_STATIC, &act, &mut OsRng).err(),
+        Some(Bolt8Error::UnknownVersion(1))
+    );
+    let mut act = act_one;
+    act[49] ^= 1;
+    assert_eq!(
+        Bolt8Responder::new(&RESPONDER_STATIC, &act, &mut OsRng).err(),
+        Some(Bolt8Error::Cipher(CipherError::MacMismatch))
+    );
+
+    let mut act = act_three;
+    act[0] = 1;
+    assert_eq!(
+        responder().read_act_three(&act).err(),
+        Some(Bolt8Error::UnknownVersion(1))
+    );
+    let mut act = act_three;
+    act[49] ^= 1;
+    assert_eq!(
+        responder().read_act_three(&act).err(),
+        Some(Bolt8Error::Cipher(CipherError::MacMismatch))
+    );
+    let mut act = act_three;
+    act[65] ^= 1;
+    assert_eq!(
+        responder().read_act_three(&act).err(),
+        Some(Bolt8Error::Cipher(CipherError::MacMismatch))
+    );
+
+    let (mut initiator, mut responder) = handshake();
+    let mut buffer = [0; 64];
+    assert_eq!(
+        initiator.sender.encrypt(&[0; 40], &mut buffer),
+        Err(Bolt8Error::BufferTooSmall)
+    );
+    let len = initiator.sender.encrypt(b"hello", &mut buffer).unwrap();
+    buffer[len - 1] ^= 1;
+    let (header, body) = buffer[..len].split_at_mut(BOLT8_HEADER_LEN);
+    responder
+        .receiver
+        .decrypt_header(&header.try_into().unwrap())
+        .unwrap();
+    assert_eq!(
+        responder.receiver.decrypt_body(body),
+        Err(Bolt8Error::Cipher(CipherError::MacMismatch))
+    );
+}
+
+pub fn random() {
+    let (initiator, act_one) = Bolt8Initiator::new(
+        &INITIATOR_STATIC,
+        &array(RESPONDER_STATIC_PUBLIC),
+        &mut OsRng,
+    )
+    .unwrap();
+    let (responder, act_two) =
+        Bolt8Responder::new(&RESPONDER_STATIC, &act_one, &mut OsRng).unwrap();
+    let (act_three, mut initiator) = initiator.read_act_two(&act_two).unwrap();
+    let (mut responder, _) = responder.read_act_three(&act_three).unwrap();
+
+    let mut buffer = [0; 64];
+    let len = responder.sender.encrypt(b"ping", &mut buffer).unwrap();
+    let (header, body) = buffer[..len].split_at_mut(BOLT8_HEADER_LEN);
+    initiator
+        .receiver
+        .decrypt_header(&header.try_into().unwrap())
+        .unwrap();
+    let len = initiator.receiver.decrypt_body(body).unwrap();
+    assert_eq!(&body[..len], b"ping");
+}
+
+/// The generic handshake with the secp256k1 backend produces the acts without the version byte.
+pub fn handshake_state() {
+    use crate::{patterns, Dh, HandshakeKeys, HandshakeState, Secp256k1};
+
+    let secret = |bytes: &[u8; 32]| Secp256k1::secret_from_bytes(bytes).unwrap();
+    let rs = Secp256k1::public_from_bytes(&array::<33>(RESPONDER_STATIC_PUBLIC)).unwrap();
+    let initiator_keys = HandshakeKeys::default()
+        .local_static(secret(&INITIATOR_STATIC))
+        .local_ephemeral(secret(&INITIATOR_EPHEMERAL))
+        .remote_static(rs);
+    let responder_keys = HandshakeKeys::default()
+        .local_static(secret(&RESPONDER_STATIC))
+        .local_ephemeral(secret(&RESPONDER_EPHEMERAL));
+    let new = |initiator, keys| {
+        HandshakeState::<Bolt8Config>::new(patterns::XK, initiator, b"lightning", keys, &mut OsRng)
+            .unwrap()
+    };
+    let mut initiator = new(true, initiator_keys);
+    let mut responder = new(false, responder_keys);
+
+    let mut buffer = [0; 128];
+    for (n, act) in [ACT_ONE, ACT_TWO, ACT_THREE].into_iter().enumerate() {
+        let (sender, receiver) = if n % 2 == 0 {
+            (&mut initiator, &mut responder)
+        } else {
+            (&mut responder, &mut initiator)
+        };
+        let len = sender.write_message(&[], &mut buffer).unwrap();
+        assert_eq!(hex::encode(&buffer[..len]), act[2..]);
+        receiver.read_message(&mut buffer[..len]).unwrap();
+    }
+
+    let mut initiator = initiator.finish::<1>().unwrap();
+    let mut length = 5u16.to_be_bytes();
+    let tag = initiator.sender.encrypt(&[], &mut length).unwrap();
+    assert_eq!(
+        hex::encode([&length[..], &tag].concat()),
+        MESSAGES[0].1[..(BOLT8_HEADER_LEN * 2)]
+    );
+}
@@ -0,0 +1,204 @@
+use core::num::NonZeroU64;
+use alloc::vec::Vec;
+
+use aead::{AeadInPlace, KeyInit};
+
+use crate::{Aead, Cipher, CipherError, CipherRaw, ConfigExt, ReplayWindow, Tag};
+
+fn pair<C>(key: &Aead<C>) -> (Cipher<C, 1, true>, Cipher<C, 1, false>)
+where
+    C: ConfigExt,
+{
+    (Cipher::new(key), Cipher::new(key))
+}
+
+fn round_trip<C>(sender: &mut Cipher<C, 1, true>, receiver: &mut Cipher<C, 1, false>) -> bool
+where
+    C: ConfigExt,
+{
+    let mut buffer = *b"hello";
+    let tag = sender.encrypt(b"ad", &mut buffer).unwrap();
+    receiver.decrypt(b"ad", &mut buffer, &tag).is_ok() && buffer.eq(b"hello")
+}
+
+pub fn rekey<C>()
+where
+    C: ConfigExt,
+{
+    let key = Aead::<C>::from_slice(&[0x42; 32]).clone();
+    let (mut sender, mut receiver) = pair::<C>(&key);
+    assert!(round_trip(&mut sender, &mut receiver));
+
+    // the definition of `REKEY(k)`
+    let mut new_key = Aead::<C>::default();
+    C::Aead::new(&key)
+        .encrypt_in_place_detached(&C::prepare_nonce(u64::MAX), &[], &mut new_key)
+        .unwrap();
+    let mut expected = *b"hello";
+    let expected_tag = C::Aead::new(&new_key)
+        .encrypt_in_place_detached(&C::prepare_nonce(1), b"ad", &mut expected)
+        .unwrap();
+
+    sender.rekey();
+    let mut buffer = *b"hello";
+    let tag = sender.encrypt(b"ad", &mut buffer).unwrap();
+    assert_eq!((buffer, &tag), (expected, &expected_tag));
+    assert_eq!(sender.nonce(), 2);
+
+    assert!(receiver.decrypt(b"ad", &mut buffer, &tag).is_err());
+    receiver.rekey();
+    assert!(receiver.decrypt(b"ad", &mut buffer, &tag).is_ok());
+    assert!(round_trip(&mut sender, &mut receiver));
+}
+
+pub fn rekey_interval<C>()
+where
+    C: ConfigExt,
+{
+    let key = Aead::<C>::from_slice(&[0x42; 32]).clone();
+    let interval = NonZeroU64::new(3).unwrap();
+    let (sender, receiver) = pair::<C>(&key);
+    let mut sender = sender.with_rekey_interval(interval);
+    let mut receiver = receiver.with_rekey_interval(interval);
+    for _ in 0..10 {
+        assert!(round_trip(&mut sender, &mut receiver));
+    }
+
+    let (sender, mut receiver) = pair::<C>(&key);
+    let mut sender = sender.with_rekey_interval(interval);
+    for _ in 0..3 {
+        assert!(round_trip(&mut sender, &mut receiver));
+    }
+    assert!(!round_trip(&mut sender, &mut receiver));
+}
+
+pub fn nonce_exhausted<C>()
+where
+    C: ConfigExt,
+{
+    let key = Aead::<C>::from_slice(&[0x42; 32]).clone();
+    let (mut sender, mut receiver) = pair::<C>(&key);
+    sender.set_nonce(u64::MAX - 2);
+    receiver.set_nonce(u64::MAX - 2);
+    assert!(round_trip(&mut sender, &mut receiver));
+    assert!(round_trip(&mut sender, &mut receiver));
+    assert_eq!(sender.nonce(), u64::MAX);
+
+    let mut buffer = *b"hello";
+    assert_eq!(
+        sender.encrypt(b"ad", &mut buffer),
+        Err(CipherError::NonceExhausted)
+    );
+    assert_eq!(buffer, *b"hello");
+    let tag = Tag::<C>::default();
+    assert_eq!(
+        receiver.decrypt(b"ad", &mut buffer, &tag),
+        Err(CipherError::NonceExhausted)
+    );
+
+    // the nonce is multiplied by the step
+    let mut sender = Cipher::<C, 2, true>::new(&key);
+    sender.set_nonce(u64::MAX / 2 - 1);
+    assert!(sender.encrypt(b"ad", &mut buffer).is_ok());
+    assert!(sender.link(u64::MAX / 2 - 1, &mut buffer).is_ok());
+    assert_eq!(
+        sender.encrypt(b"ad", &mut buffer),
+        Err(CipherError::NonceExhausted)
+    );
+    assert_eq!(
+        sender.link(u64::MAX / 2, &mut buffer),
+        Err(CipherError::NonceExhausted)
+    );
+}
+
+pub fn replay_window<C>()
+where
+    C: ConfigExt,
+{
+    let key = Aead::<C>::from_slice(&[0x42; 32]).clone();
+    let (mut sender, mut receiver) = pair::<C>(&key);
+    let mut window = ReplayWindow::<1>::new();
+    let send = |sender: &mut Cipher<C, 1, true>| {
+        let n = sender.nonce();
+        let mut buffer = *b"hello";
+        let tag = sender.encrypt(b"ad", &mut buffer).unwrap();
+        (n, buffer, tag)
+    };
+    let messages = (0..5).map(|_| send(&mut sender)).collect::<Vec<_>>();
+
+    let mut receive = |window: &mut ReplayWindow<1>,
+                       (n, mut buffer, tag): (u64, [u8; 5], Tag<C>)| {
+        receiver
+            .decrypt_at(window, n, b"ad", &mut buffer, &tag)
+            .map(|()| assert_eq!(buffer, *b"hello"))
+    };
+    for i in [3, 1, 0, 4] {
+        receive(&mut window, messages[i].clone()).unwrap();
+    }
+    assert_eq!(
+        receive(&mut window, messages[1].clone()),
+        Err(CipherError::Duplicate)
+    );
+
+    // the forged message does not touch the window
+    let (n, buffer, _) = messages[2].clone();
+    let forged = (n, buffer, Tag::<C>::default());
+    assert_eq!(receive(&mut window, forged), Err(CipherError::MacMismatch));
+    receive(&mut window, messages[2].clone()).unwrap();
+
+    sender.set_nonce(ReplayWindow::<1>::BITS + 4);
+    let late = send(&mut sender);
+    receive(&mut window, late.clone()).unwrap();
+    assert_eq!(receive(&mut window, late), Err(CipherError::Duplicate));
+    assert_eq!(
+        receive(&mut window, messages[4].clone()),
+        Err(CipherError::TooOld)
+    );
+    let next = send(&mut sender);
+    receive(&mut window, next).unwrap();
+}
+
+/// The session continues after both sides are restored, the rekey is kept.
+pub fn export_import<C>()
+where
+    C: ConfigExt,
+{
+    let key = Aead::<C>::from_slice(&[0x42; 32]).clone();
+    let interval = NonZeroU64::new(3).unwrap();
+    let (sender, receiver) = pair::<C>(&key);
+    let mut sender = sender.with_rekey_interval(interval);
+    let mut receiver = receiver.with_rekey_interval(interval);
+    for _ in 0..4 {
+        assert!(round_trip(&mut sender, &mut receiver));
+    }
+
+    let raw = sender.export();
+    assert_eq!((raw.nonce, raw.rekey_interval), (4, Some(interval)));
+    // rekeyed after the third message
+    assert_ne!(*raw.key, key);
+    let mut sender = Cipher::<C, 1, true>::import(&raw);
+    let mut receiver = Cipher::<C, 1, false>::import(&receiver.export());
+    for _ in 0..4 {
+        assert!(round_trip(&mut sender, &mut receiver));
+    }
+    assert_eq!(sender.nonce(), 8);
+}
+
+#[cfg(feature = "serde")]
+pub fn export_serde<C>()
+where
+    C: ConfigExt,
+{
+    let key = Aead::<C>::from_slice(&[0x42; 32]).clone();
+    let (mut sender, mut receiver) = pair::<C>(&key);
+    assert!(round_trip(&mut sender, &mut receiver));
+
+    let saved = serde_json::to_string(&sender.export()).unwrap();
+    assert!(saved.contains(&hex::encode(key)) && saved.contains("\"nonce\":1"));
+    let raw = serde_json::from_str::<CipherRaw<C>>(&saved).unwrap();
+    let mut sender = Cipher::<C, 1, true>::import(&raw);
+    assert!(round_trip(&mut sender, &mut receiver));
+
+    let short = saved.replacen("\"key\":\"42", "\"key\":\"", 1);
+    assert!(serde_json::from_str::<CipherRaw<C>>(&short).is_err());
+}
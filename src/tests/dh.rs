@@ -0,0 +1,16 @@
+use rand_core::OsRng;
+
+use crate::Dh;
+
+pub fn agreement<D>()
+where
+    D: Dh,
+    D::SharedSecret: PartialEq + core::fmt::Debug,
+{
+    let a = D::generate(&mut OsRng);
+    let b = D::generate(&mut OsRng);
+    let a_public = D::public_from_bytes(D::public(&a).as_ref()).unwrap();
+    let b_public = D::public_from_bytes(D::public(&b).as_ref()).unwrap();
+    assert_eq!(a_public.as_ref().len(), D::DHLEN);
+    assert_eq!(D::dh(&a, &b_public).unwrap(), D::dh(&b, &a_public).unwrap());
+}
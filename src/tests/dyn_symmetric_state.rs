@@ -0,0 +1,161 @@
+use alloc::vec::Vec;
+
+use super::test_vector::TestVector;
+use crate::{DynSymmetricState, SymmetricState, ConfigExt, Dh, CipherError, Tag};
+
+fn encrypt<C>(
+    state: DynSymmetricState<C>,
+    prefix: &[u8],
+    data: &mut Vec<u8>,
+) -> (DynSymmetricState<C>, Vec<u8>)
+where
+    C: ConfigExt,
+{
+    let (state, tag) = state.encrypt(data).unwrap();
+    if let Some(tag) = tag {
+        data.extend_from_slice(&tag);
+    }
+    (state, [prefix, data].concat())
+}
+
+/// Runs the `XK` handshake of the vector, the initiator and the responder
+/// are kept in one `Vec`.
+pub fn xk<C>(v: &TestVector<'_>)
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+{
+    fn pair<D>(hex: &str) -> (D::Secret, D::Public)
+    where
+        D: Dh,
+    {
+        let secret = D::secret_from_bytes(&hex::decode(hex).unwrap()).unwrap();
+        let public = D::public(&secret);
+        (secret, public)
+    }
+    let dh = |secret, public| C::Dh::dh(secret, public).unwrap();
+
+    let init_ephemeral = pair::<C::Dh>(v.init_ephemeral);
+    let resp_ephemeral = pair::<C::Dh>(v.resp_ephemeral.unwrap());
+    let init_static = pair::<C::Dh>(v.init_static.unwrap());
+    let resp_static = pair::<C::Dh>(v.resp_static.unwrap());
+    let payload = |n: usize| hex::decode(v.messages[n].payload).unwrap();
+
+    let prologue = hex::decode(v.prologue).unwrap();
+    let mut states = (0..2)
+        .map(|_| {
+            DynSymmetricState::<C>::new(v.name)
+                .mix_hash(&prologue)
+                // <- s
+                .mix_hash(resp_static.1.as_ref())
+        })
+        .collect::<Vec<_>>();
+    assert!(states.iter().all(|state| !state.has_key()));
+    let responder = states.pop().unwrap();
+    let initiator = states.pop().unwrap();
+
+    // -> e, es
+    let initiator = initiator
+        .mix_hash(init_ephemeral.1.as_ref())
+        .mix_shared_secret(dh(&init_ephemeral.0, &resp_static.1));
+    let (initiator, ct) = encrypt(initiator, init_ephemeral.1.as_ref(), &mut payload(0));
+    assert_eq!(v.messages[0].ciphertext, hex::encode(&ct));
+    assert_eq!(initiator.nonce(), Some(1));
+    let responder = responder
+        .mix_hash(init_ephemeral.1.as_ref())
+        .mix_shared_secret(dh(&resp_static.0, &init_ephemeral.1));
+    let mut data = ct[init_ephemeral.1.as_ref().len()..].to_vec();
+    let responder = responder.decrypt_and_hash(&mut data).unwrap();
+    assert_eq!(data[..(data.len() - responder.tag_len())], payload(0));
+    let mut states = [initiator, responder].into_iter().collect::<Vec<_>>();
+    assert_eq!(states[0].hash(), states[1].hash());
+
+    // <- e, ee
+    let responder = states.pop().unwrap();
+    let initiator = states.pop().unwrap();
+    let responder = responder
+        .mix_hash(resp_ephemeral.1.as_ref())
+        .mix_shared_secret(dh(&resp_ephemeral.0, &init_ephemeral.1));
+    let (responder, ct) = encrypt(responder, resp_ephemeral.1.as_ref(), &mut payload(1));
+    assert_eq!(v.messages[1].ciphertext, hex::encode(&ct));
+    let initiator = initiator
+        .mix_hash(resp_ephemeral.1.as_ref())
+        .mix_shared_secret(dh(&init_ephemeral.0, &resp_ephemeral.1));
+    let mut data = ct[resp_ephemeral.1.as_ref().len()..].to_vec();
+    let initiator = initiator.decrypt_and_hash(&mut data).unwrap();
+    assert_eq!(data[..(data.len() - initiator.tag_len())], payload(1));
+
+    // -> s, se
+    let mut s = init_static.1.as_ref().to_vec();
+    let (initiator, mut ct) = encrypt(initiator, &[], &mut s);
+    let initiator = initiator.mix_shared_secret(dh(&init_static.0, &resp_ephemeral.1));
+    let (initiator, ct_payload) = encrypt(initiator, &[], &mut payload(2));
+    ct.extend_from_slice(&ct_payload);
+    assert_eq!(v.messages[2].ciphertext, hex::encode(&ct));
+
+    let mut buffer = ct;
+    let (static_part, payload_part) =
+        buffer.split_at_mut(init_static.1.as_ref().len() + responder.tag_len());
+    let responder = responder.decrypt_and_hash(static_part).unwrap();
+    let responder = responder.mix_shared_secret(dh(&resp_ephemeral.0, &init_static.1));
+    let responder = responder.decrypt_and_hash(payload_part).unwrap();
+    assert_eq!(initiator.nonce(), responder.nonce());
+
+    let initiator = initiator.finish::<1, false>();
+    let responder = responder.finish::<1, true>();
+    assert_eq!(v.handshake_hash, hex::encode(&initiator.hash));
+    assert_eq!(initiator.hash, responder.hash);
+
+    let mut sender = initiator.sender;
+    let mut receiver = responder.receiver;
+    let mut buffer = hex::decode(v.messages[3].payload).unwrap();
+    let tag = sender.encrypt(&[], &mut buffer).unwrap();
+    receiver.decrypt(&[], &mut buffer, &tag).unwrap();
+    assert_eq!(hex::encode(buffer), v.messages[3].payload);
+}
+
+/// The typed state keeps its nonce after the conversion.
+pub fn conversion<C>(v: &TestVector<'_>)
+where
+    C: ConfigExt,
+{
+    let typed = SymmetricState::<C, _>::new(v.name).mix_shared_secret([1; 32]);
+    let (typed, _) = typed.encrypt(&mut [0; 4]);
+    let mut dynamic = DynSymmetricState::<C>::new(v.name).mix_shared_secret([1; 32]);
+    assert!(dynamic.has_key());
+    assert_eq!(dynamic.nonce(), Some(0));
+    dynamic = dynamic.encrypt(&mut [0; 4]).unwrap().0;
+
+    let (typed, typed_tag) = typed.encrypt(&mut [0; 4]);
+    let converted = DynSymmetricState::from(typed);
+    assert_eq!(converted.nonce(), Some(2));
+    let (dynamic, dynamic_tag) = dynamic.encrypt(&mut [0; 4]).unwrap();
+    assert_eq!(Some(typed_tag), dynamic_tag);
+    assert_eq!(converted.hash(), dynamic.hash());
+}
+
+pub fn errors<C>(v: &TestVector<'_>)
+where
+    C: ConfigExt,
+{
+    let state = DynSymmetricState::<C>::new(v.name);
+    assert_eq!(state.nonce(), None);
+    assert_eq!(state.tag_len(), 0);
+    let (state, tag) = state.encrypt(&mut [0; 4]).unwrap();
+    assert!(tag.is_none());
+    assert!(matches!(
+        state.decrypt(&mut [0; 4], Some(&Tag::<C>::default())),
+        Err(CipherError::MacMismatch)
+    ));
+
+    let state = DynSymmetricState::<C>::new(v.name).mix_psk([2; 32]);
+    assert!(matches!(
+        state.decrypt(&mut [0; 4], None),
+        Err(CipherError::MacMismatch)
+    ));
+    let state = DynSymmetricState::<C>::new(v.name).mix_psk([2; 32]);
+    assert!(matches!(
+        state.decrypt(&mut [0; 4], Some(&Tag::<C>::default())),
+        Err(CipherError::MacMismatch)
+    ));
+}
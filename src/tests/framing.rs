@@ -0,0 +1,81 @@
+use alloc::vec::Vec;
+
+use crate::{
+    Aead, Cipher, ConfigExt, Output, Framed, FrameSender, FrameError, CipherError,
+    LENGTH_PREFIX_LEN,
+};
+
+fn pair<C>() -> (Framed<C, 1>, Framed<C, 1>)
+where
+    C: ConfigExt,
+{
+    let a = Aead::<C>::from_slice(&[1; 32]).clone();
+    let b = Aead::<C>::from_slice(&[2; 32]).clone();
+    let initiator = Output {
+        sender: Cipher::new(&a),
+        receiver: Cipher::new(&b),
+        hash: Default::default(),
+    };
+    let responder = Output {
+        sender: Cipher::new(&b),
+        receiver: Cipher::new(&a),
+        hash: Default::default(),
+    };
+    (initiator.into(), responder.into())
+}
+
+pub fn f<C>()
+where
+    C: ConfigExt,
+{
+    let (mut initiator, mut responder) = pair::<C>();
+    let max = FrameSender::<C, 1>::MAX_PAYLOAD_LEN;
+
+    let long = (0..(max * 2 + 10)).map(|i| i as u8).collect::<Vec<u8>>();
+    let payloads: [&[u8]; 4] = [b"hello", &[], &long, b"world"];
+    let mut stream = Vec::new();
+    for payload in payloads {
+        initiator.sender.encrypt(payload, &mut stream).unwrap();
+    }
+    assert_eq!(&stream[..LENGTH_PREFIX_LEN], &[0, 5 + 16]);
+    assert_eq!(initiator.sender.cipher().nonce(), 6);
+
+    let mut received = Vec::new();
+    for chunk in stream.chunks(1000) {
+        responder.receiver.push(chunk);
+        while let Some(payload) = responder.receiver.decrypt().unwrap() {
+            received.push(payload);
+        }
+    }
+    assert_eq!(responder.receiver.pending(), 0);
+    assert_eq!(received.len(), 6);
+    assert_eq!(received[0], b"hello");
+    assert!(received[1].is_empty());
+    assert_eq!(received[2..5].concat(), long);
+    assert_eq!(received[2].len(), max);
+    assert_eq!(received[5], b"world");
+
+    let mut frame = [0; 64];
+    assert_eq!(
+        responder.sender.encrypt_frame(&long, &mut frame),
+        Err(FrameError::PayloadTooLong)
+    );
+    assert_eq!(
+        responder.sender.encrypt_frame(&[0; 64], &mut frame),
+        Err(FrameError::BufferTooSmall)
+    );
+    let len = responder.sender.encrypt_frame(b"ok", &mut frame).unwrap();
+    frame[LENGTH_PREFIX_LEN] ^= 1;
+    initiator.receiver.push(&frame[..(len - 1)]);
+    assert_eq!(initiator.receiver.missing(), 1);
+    assert_eq!(initiator.receiver.decrypt(), Ok(None));
+    initiator.receiver.push(&frame[(len - 1)..len]);
+    assert_eq!(
+        initiator.receiver.decrypt(),
+        Err(FrameError::Cipher(CipherError::MacMismatch))
+    );
+
+    initiator.receiver.push(&[0, 15]);
+    initiator.receiver.push(&[0; 15]);
+    assert_eq!(initiator.receiver.decrypt(), Err(FrameError::FrameTooShort));
+}
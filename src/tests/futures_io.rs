@@ -0,0 +1,92 @@
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::io;
+
+use alloc::vec::Vec;
+
+use futures::{
+    executor,
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+};
+use rand_core::OsRng;
+use tokio::io::{DuplexStream, ReadBuf};
+
+use crate::{patterns, ConfigExt, Dh, NoiseName, FuturesNoiseStream, HandshakeKeys, HandshakeState};
+
+// the in-memory pipe from tokio does not need the tokio runtime
+struct Pipe(DuplexStream);
+
+impl AsyncRead for Pipe {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut buf = ReadBuf::new(buf);
+        tokio::io::AsyncRead::poll_read(Pin::new(&mut self.0), cx, &mut buf)
+            .map_ok(|()| buf.filled().len())
+    }
+}
+
+impl AsyncWrite for Pipe {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        tokio::io::AsyncWrite::poll_write(Pin::new(&mut self.0), cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        tokio::io::AsyncWrite::poll_flush(Pin::new(&mut self.0), cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        tokio::io::AsyncWrite::poll_shutdown(Pin::new(&mut self.0), cx)
+    }
+}
+
+pub fn f<C>()
+where
+    C: ConfigExt,
+    C::Dh: Dh + NoiseName,
+    C::Aead: NoiseName,
+    C::MixHash: NoiseName,
+{
+    let new = |initiator| {
+        let keys = HandshakeKeys::default().local_static(C::Dh::generate(&mut OsRng));
+        HandshakeState::<C>::new(patterns::XX, initiator, b"prologue", keys, &mut OsRng).unwrap()
+    };
+    let initiator = new(true);
+    let responder = new(false);
+    let (a, b) = tokio::io::duplex(100);
+
+    let data = (0..200_000).map(|i| (i % 251) as u8).collect::<Vec<u8>>();
+    let initiator = async {
+        let mut stream = FuturesNoiseStream::<C, _>::handshake(Pipe(a), initiator)
+            .await
+            .unwrap();
+        stream.write_all(&data).await.unwrap();
+        stream.close().await.unwrap();
+        let mut received = Vec::new();
+        stream.read_to_end(&mut received).await.unwrap();
+        assert_eq!(received, b"bye");
+        stream.hash().clone()
+    };
+    let responder = async {
+        let mut stream = FuturesNoiseStream::<C, _>::handshake(Pipe(b), responder)
+            .await
+            .unwrap();
+        let mut received = Vec::new();
+        stream.read_to_end(&mut received).await.unwrap();
+        assert_eq!(received, data);
+        stream.write_all(b"bye").await.unwrap();
+        stream.close().await.unwrap();
+        stream.hash().clone()
+    };
+
+    let (a, b) = executor::block_on(async { futures::join!(initiator, responder) });
+    assert_eq!(a, b);
+}
@@ -0,0 +1,237 @@
+use alloc::{vec, string::ToString};
+
+use rand_core::OsRng;
+use zeroize::Zeroizing;
+
+use aes_gcm::Aes256Gcm;
+use blake2::{Blake2b512, Blake2s256};
+use chacha20poly1305::ChaCha20Poly1305;
+use generic_array::typenum::{B0, B1, Bit};
+use hkdf::hmac::{Hmac, SimpleHmac};
+use sha2::{Sha256, Sha512};
+use aead::{KeyInit, AeadInPlace};
+
+use super::test_vector::TestVector;
+use crate::{
+    patterns, X25519, X448, ConfigExt, Dh, NoiseName, ProtocolName, HandshakePattern,
+    HandshakeKeys, HandshakeState, HandshakeError, MAX_MESSAGE_LEN, MAX_PSKS,
+};
+
+fn keys<D>(
+    v: &TestVector<'_>,
+    s: Option<&str>,
+    e: Option<&str>,
+    rs: Option<&str>,
+) -> HandshakeKeys<D>
+where
+    D: Dh,
+{
+    let secret = |hex: &str| D::secret_from_bytes(&hex::decode(hex).unwrap()).unwrap();
+    let public = |hex: &str| D::public_from_bytes(&hex::decode(hex).unwrap()).unwrap();
+
+    let mut keys = HandshakeKeys::default();
+    if let Some(s) = s {
+        keys = keys.local_static(secret(s));
+    }
+    if let Some(e) = e {
+        keys = keys.local_ephemeral(secret(e));
+    }
+    if let Some(rs) = rs {
+        keys = keys.remote_static(public(rs));
+    }
+    for psk in &v.psks {
+        let psk = hex::decode(psk).unwrap().try_into().unwrap();
+        keys = keys.psk(Zeroizing::new(psk)).unwrap();
+    }
+    keys
+}
+
+pub fn f<C>(v: &TestVector<'_>, pattern: HandshakePattern)
+where
+    C: ConfigExt,
+    C::Dh: Dh + NoiseName,
+    C::Aead: NoiseName,
+    C::MixHash: NoiseName,
+{
+    assert_eq!(ProtocolName::new::<C>(&pattern).to_string(), v.name);
+    let prologue = hex::decode(v.prologue).unwrap();
+    let init_keys = keys::<C::Dh>(
+        v,
+        v.init_static,
+        Some(v.init_ephemeral),
+        v.init_remote_static,
+    );
+    let resp_keys = keys::<C::Dh>(v, v.resp_static, v.resp_ephemeral, v.resp_remote_static);
+    let mut initiator =
+        HandshakeState::<C>::new(pattern, true, &prologue, init_keys, &mut OsRng).unwrap();
+    // the explicit name is the same as the derived one
+    let mut responder =
+        HandshakeState::<C>::with_name(v.name, pattern, false, &prologue, resp_keys, &mut OsRng)
+            .unwrap();
+
+    let mut buffer = [0; 1024];
+    let handshake_len = pattern.messages.len();
+    for (n, pair) in v.messages[..handshake_len].iter().enumerate() {
+        let (sender, receiver) = if n % 2 == 0 {
+            (&mut initiator, &mut responder)
+        } else {
+            (&mut responder, &mut initiator)
+        };
+        assert!(sender.is_write_turn() && !receiver.is_write_turn());
+        assert_eq!(
+            receiver.write_message(&[], &mut buffer),
+            Err(HandshakeError::WrongTurn)
+        );
+
+        let payload = hex::decode(pair.payload).unwrap();
+        let len = sender.write_message(&payload, &mut buffer).unwrap();
+        assert_eq!(pair.ciphertext, hex::encode(&buffer[..len]));
+        let received = receiver.read_message(&mut buffer[..len]).unwrap();
+        assert_eq!(payload, received);
+    }
+    assert!(initiator.is_finished() && responder.is_finished());
+    assert_eq!(
+        initiator.write_message(&[], &mut buffer),
+        Err(HandshakeError::Finished)
+    );
+
+    let mut initiator = initiator.finish::<1>().unwrap();
+    let mut responder = responder.finish::<1>().unwrap();
+    assert_eq!(v.handshake_hash, hex::encode(&initiator.hash));
+    assert_eq!(initiator.hash, responder.hash);
+
+    for (n, pair) in v.messages[handshake_len..].iter().enumerate() {
+        let (sender, receiver) = if pattern.is_one_way() || (handshake_len + n) % 2 == 0 {
+            (&mut initiator.sender, &mut responder.receiver)
+        } else {
+            (&mut responder.sender, &mut initiator.receiver)
+        };
+        let payload = hex::decode(pair.payload).unwrap();
+        let mut buffer = payload.clone();
+        let tag = sender.encrypt(&[], &mut buffer).unwrap();
+        let mut ct = buffer.clone();
+        ct.extend_from_slice(&tag);
+        assert_eq!(pair.ciphertext, hex::encode(ct));
+        receiver.decrypt(&[], &mut buffer, &tag).unwrap();
+        assert_eq!(payload, buffer);
+    }
+}
+
+pub fn errors<C>(v: &TestVector<'_>, pattern: HandshakePattern)
+where
+    C: ConfigExt,
+    C::Dh: Dh + NoiseName,
+    C::Aead: NoiseName,
+    C::MixHash: NoiseName,
+{
+    let new = |initiator: bool, prologue: &[u8]| {
+        let keys = if initiator {
+            keys::<C::Dh>(v, v.init_static, None, v.init_remote_static)
+        } else {
+            keys::<C::Dh>(v, v.resp_static, None, v.resp_remote_static)
+        };
+        HandshakeState::<C>::new(pattern, initiator, prologue, keys, &mut OsRng).unwrap()
+    };
+    let prologue = hex::decode(v.prologue).unwrap();
+
+    let keys = (0..MAX_PSKS).try_fold(HandshakeKeys::<C::Dh>::default(), |keys, _| {
+        keys.psk(Zeroizing::new([0; 32]))
+    });
+    assert!(matches!(
+        keys.unwrap().psk(Zeroizing::new([0; 32])),
+        Err(HandshakeError::TooManyPsks)
+    ));
+
+    // the buffer is checked before the state changes, the handshake goes on
+    let mut buffer = vec![0; MAX_MESSAGE_LEN * 2];
+    let mut initiator = new(true, &prologue);
+    let mut responder = new(false, &prologue);
+    let len = new(true, &prologue)
+        .write_message(b"payload", &mut buffer)
+        .unwrap();
+    assert_eq!(
+        initiator.write_message(b"payload", &mut buffer[..(len - 1)]),
+        Err(HandshakeError::BufferTooSmall)
+    );
+    assert_eq!(
+        initiator.write_message(&[0; MAX_MESSAGE_LEN], &mut buffer),
+        Err(HandshakeError::MessageTooLong)
+    );
+    assert_eq!(
+        initiator.write_message(b"payload", &mut buffer[..len]),
+        Ok(len)
+    );
+    assert_eq!(
+        responder.read_message(&mut buffer[..(MAX_MESSAGE_LEN + 1)]),
+        Err(HandshakeError::MessageTooLong)
+    );
+    assert_eq!(
+        responder.read_message(&mut buffer[..len]).unwrap(),
+        b"payload"
+    );
+    // any other error aborts
+    assert_eq!(
+        responder.read_message(&mut buffer[..len]),
+        Err(HandshakeError::WrongTurn)
+    );
+    if !responder.is_finished() {
+        let len = responder.write_message(&[], &mut buffer).unwrap();
+        buffer[len - 1] ^= 1;
+        assert_eq!(
+            initiator.read_message(&mut buffer[..len]),
+            Err(HandshakeError::MacMismatch)
+        );
+        assert_eq!(
+            initiator.read_message(&mut buffer[..len]),
+            Err(HandshakeError::Aborted)
+        );
+    }
+
+    let mut initiator = new(true, b"another prologue");
+    let mut responder = new(false, &prologue);
+    let len = initiator.write_message(b"payload", &mut buffer).unwrap();
+    assert_eq!(
+        responder.read_message(&mut buffer[..len]),
+        Err(HandshakeError::MacMismatch)
+    );
+    assert!(responder.finish::<1>().is_err());
+}
+
+pub fn all() {
+    for name in TestVector::names() {
+        let v = TestVector::try_load(name).unwrap();
+        let parsed = ProtocolName::parse(name).unwrap();
+        let pattern = patterns::find(parsed.pattern).unwrap();
+        match parsed.dh {
+            "25519" => by_cipher::<X25519>(&v, pattern, parsed),
+            "448" => by_cipher::<X448>(&v, pattern, parsed),
+            dh => panic!("unknown dh {}", dh),
+        }
+    }
+}
+
+fn by_cipher<X>(v: &TestVector<'_>, pattern: HandshakePattern, name: ProtocolName<'_>)
+where
+    X: Dh + NoiseName,
+{
+    match name.cipher {
+        "ChaChaPoly" => by_hash::<X, B0, ChaCha20Poly1305>(v, pattern, name),
+        "AESGCM" => by_hash::<X, B1, Aes256Gcm>(v, pattern, name),
+        cipher => panic!("unknown cipher {}", cipher),
+    }
+}
+
+fn by_hash<X, E, A>(v: &TestVector<'_>, pattern: HandshakePattern, name: ProtocolName<'_>)
+where
+    X: Dh + NoiseName,
+    E: Bit,
+    A: KeyInit + AeadInPlace + NoiseName,
+{
+    match name.hash {
+        "SHA256" => f::<(Hmac<Sha256>, Sha256, E, A, X)>(v, pattern),
+        "SHA512" => f::<(Hmac<Sha512>, Sha512, E, A, X)>(v, pattern),
+        "BLAKE2s" => f::<(SimpleHmac<Blake2s256>, Blake2s256, E, A, X)>(v, pattern),
+        "BLAKE2b" => f::<(SimpleHmac<Blake2b512>, Blake2b512, E, A, X)>(v, pattern),
+        hash => panic!("unknown hash {}", hash),
+    }
+}
@@ -0,0 +1,223 @@
+use alloc::string::ToString;
+
+use rand_core::{CryptoRng, OsRng, RngCore};
+
+use chacha20poly1305::ChaCha20Poly1305;
+use generic_array::typenum::B0;
+use hkdf::hmac::Hmac;
+use sha2::Sha256;
+
+use crate::{
+    patterns, Dh, HandshakeError, HandshakeKeys, HandshakePattern, HandshakeState, Kem,
+    ProtocolName, ProtocolNameError, SymmetricState, X25519, X25519Secret,
+};
+
+/// The DH based KEM over X25519, the ciphertext is the ephemeral public key.
+pub struct DhKem;
+
+impl Kem for DhKem {
+    const NAME: Option<&'static str> = Some("DHKEM25519");
+    const PUBLIC_LEN: usize = 32;
+    const CIPHERTEXT_LEN: usize = 32;
+
+    type Secret = X25519Secret;
+    type Public = [u8; 32];
+    type Ciphertext = [u8; 32];
+    type SharedSecret = [u8; 32];
+
+    fn generate<R>(rng: &mut R) -> Self::Secret
+    where
+        R: RngCore + CryptoRng,
+    {
+        X25519::generate(rng)
+    }
+
+    fn public(secret: &Self::Secret) -> Self::Public {
+        X25519::public(secret)
+    }
+
+    fn public_from_bytes(bytes: &[u8]) -> Option<Self::Public> {
+        X25519::public_from_bytes(bytes)
+    }
+
+    fn encapsulate(
+        public: &Self::Public,
+        coins: &[u8; 32],
+    ) -> Option<(Self::Ciphertext, Self::SharedSecret)> {
+        let secret = X25519::secret_from_bytes(coins)?;
+        let shared_secret = X25519::dh(&secret, public).filter(|s| s != &[0; 32])?;
+        Some((X25519::public(&secret), shared_secret))
+    }
+
+    fn decapsulate(secret: &Self::Secret, ciphertext: &[u8]) -> Option<Self::SharedSecret> {
+        let public = X25519::public_from_bytes(ciphertext)?;
+        X25519::dh(secret, &public).filter(|s| s != &[0; 32])
+    }
+}
+
+type C = (Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519, DhKem);
+
+// recorded with an independent implementation of the Noise HFS spec, the prologue is "hfs"
+const NAME: &str = "Noise_XXhfs_25519+DHKEM25519_ChaChaPoly_SHA256";
+const MSG0: &str = "a4e09292b651c278b9772c569f5fa9bb13d906b46ab68c9df9dc2b4409f8a209ce8d3ad1ccb633ec7b70c17814a5c76ecd029685050d344745ba05870e587d596d736730";
+const MSG1: &str = "5dfedd3b6bd47f6fa28ee15d969d5bb0ea53774d488bdaf9df1c6e0124b3ef22c0a33b46eae214a069be3aec26c667f7cd93bef90794e08e789302bebfd22a6c8686bf429ce442dc5be7449f66b533ed94729f31db8a1e04ebf4217f82281e6d2ff3078303e9559b8eeeed648c7d387a640c2dc352966e6417f19335109e9c26a445a861bc29d7985c443b284c4f9499e85ed363";
+const MSG2: &str = "f5fc099bfc7e8b4ed5cb02df5957d5d09e0fa7eb23eebf149e3b6a3dbd501bb84b34a9e95ff9cd2d91775f8d072538d024aa483ef4a72b920145262e689b162ddd0dd8fc";
+const HASH: &str = "6ba6b942d7727e2e33d215af51a58b1569b2616f51e20528dfb1999a337b7da0";
+// "ping" sent by the initiator
+const PING: &str = "4ef6b154bb5fa87b784180ddc4c04d8970f56e18";
+
+/// Fills the n-th request with the byte `n`, the keys are generated in a known order.
+struct Counter(u8);
+
+impl RngCore for Counter {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        dest.fill(self.0);
+        self.0 += 1;
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for Counter {}
+
+fn static_keys(byte: u8) -> HandshakeKeys<X25519> {
+    HandshakeKeys::default().local_static(X25519::secret_from_bytes(&[byte; 32]).unwrap())
+}
+
+fn transfer(
+    sender: &mut HandshakeState<C>,
+    receiver: &mut HandshakeState<C>,
+    payload: &[u8],
+) -> alloc::vec::Vec<u8> {
+    let mut buffer = [0; 512];
+    let len = sender.write_message(payload, &mut buffer).unwrap();
+    let mut message = buffer[..len].to_vec();
+    assert_eq!(receiver.read_message(&mut message).unwrap(), payload);
+    buffer[..len].to_vec()
+}
+
+/// The initiator generates `e` and `e1`, the responder generates `e` and the encapsulation coins.
+pub fn vectors() {
+    let pattern = patterns::XX_HFS;
+    assert_eq!(ProtocolName::new::<C>(&pattern).to_string(), NAME);
+    let mut initiator =
+        HandshakeState::<C>::new(pattern, true, b"hfs", static_keys(5), &mut Counter(1)).unwrap();
+    let mut responder =
+        HandshakeState::<C>::new(pattern, false, b"hfs", static_keys(6), &mut Counter(3)).unwrap();
+
+    assert_eq!(
+        hex::encode(transfer(&mut initiator, &mut responder, b"msg0")),
+        MSG0
+    );
+    assert_eq!(
+        hex::encode(transfer(&mut responder, &mut initiator, b"msg1")),
+        MSG1
+    );
+    assert_eq!(
+        hex::encode(transfer(&mut initiator, &mut responder, b"msg2")),
+        MSG2
+    );
+
+    let mut initiator = initiator.finish::<1>().unwrap();
+    let responder = responder.finish::<1>().unwrap();
+    assert_eq!(hex::encode(initiator.hash), HASH);
+    assert_eq!(initiator.hash, responder.hash);
+    let mut buffer = *b"ping";
+    let tag = initiator.sender.encrypt(&[], &mut buffer).unwrap();
+    assert_eq!(hex::encode([&buffer[..], &tag].concat()), PING);
+}
+
+fn run(pattern: HandshakePattern) {
+    let public = |byte| X25519::public(&X25519::secret_from_bytes(&[byte; 32]).unwrap());
+    let mut initiator_keys = static_keys(1);
+    let mut responder_keys = static_keys(2);
+    if pattern.initiator_pre.contains(&crate::Token::S) {
+        responder_keys = responder_keys.remote_static(public(1));
+    }
+    if pattern.responder_pre.contains(&crate::Token::S) {
+        initiator_keys = initiator_keys.remote_static(public(2));
+    }
+    let mut initiator =
+        HandshakeState::<C>::new(pattern, true, &[], initiator_keys, &mut OsRng).unwrap();
+    let mut responder =
+        HandshakeState::<C>::new(pattern, false, &[], responder_keys, &mut OsRng).unwrap();
+    while !initiator.is_finished() {
+        if initiator.is_write_turn() {
+            transfer(&mut initiator, &mut responder, pattern.name.as_bytes());
+        } else {
+            transfer(&mut responder, &mut initiator, pattern.name.as_bytes());
+        }
+    }
+    assert_eq!(
+        initiator.finish::<1>().unwrap().hash,
+        responder.finish::<1>().unwrap().hash,
+        "{}",
+        pattern.name
+    );
+}
+
+pub fn patterns() {
+    let hfs = patterns::ALL.iter().filter(|pattern| pattern.is_hfs());
+    assert_eq!(hfs.clone().count(), 12);
+    for &pattern in hfs {
+        assert!(pattern.name.ends_with("hfs"));
+        run(pattern);
+    }
+}
+
+pub fn errors() {
+    // no KEM in the config
+    assert_eq!(
+        HandshakeState::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519)>::new(
+            patterns::XX_HFS,
+            true,
+            &[],
+            static_keys(1),
+            &mut OsRng,
+        )
+        .err(),
+        Some(HandshakeError::MissingKem)
+    );
+
+    // the ciphertext is tampered
+    let mut initiator =
+        HandshakeState::<C>::new(patterns::NN_HFS, true, &[], static_keys(1), &mut OsRng).unwrap();
+    let mut responder =
+        HandshakeState::<C>::new(patterns::NN_HFS, false, &[], static_keys(2), &mut OsRng).unwrap();
+    transfer(&mut initiator, &mut responder, b"");
+    let mut buffer = [0; 512];
+    let len = responder.write_message(b"", &mut buffer).unwrap();
+    buffer[40] ^= 1;
+    assert_eq!(
+        initiator.read_message(&mut buffer[..len]),
+        Err(HandshakeError::MacMismatch)
+    );
+
+    // the protocol name
+    let name = ProtocolName::parse(NAME).unwrap();
+    assert_eq!((name.dh, name.kem), ("25519", Some("DHKEM25519")));
+    assert_eq!(name.check::<C>(), Ok(()));
+    let state = SymmetricState::<C, _>::new_checked(NAME).unwrap();
+    assert_eq!(state.hash(), SymmetricState::<C, _>::new(NAME).hash());
+    assert_eq!(
+        ProtocolName::parse("Noise_XXhfs_25519+Kyber1024_ChaChaPoly_SHA256")
+            .unwrap()
+            .check::<C>(),
+        Err(ProtocolNameError::KemMismatch)
+    );
+    assert_eq!(
+        ProtocolName::new::<C>(&patterns::XX).to_string(),
+        "Noise_XX_25519_ChaChaPoly_SHA256"
+    );
+}
@@ -0,0 +1,97 @@
+use alloc::{string::String, vec::Vec};
+
+use ed25519_dalek::SigningKey;
+use rand_core::OsRng;
+use serde::Deserialize;
+
+use crate::{Dh, HandshakeKeys, Libp2pHandshake, X25519};
+
+/// Recorded against `libp2p-noise` 0.45.
+#[derive(Deserialize)]
+struct Transcript {
+    initiator: bool,
+    identity_secret: String,
+    static_secret: String,
+    ephemeral_secret: String,
+    remote_identity: String,
+    messages: Vec<Message>,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    from: String,
+    data: String,
+}
+
+pub fn transcript(json: &str) {
+    let t = serde_json::from_str::<Transcript>(json).unwrap();
+    let secret = |hex: &str| X25519::secret_from_bytes(&hex::decode(hex).unwrap()).unwrap();
+    let identity =
+        SigningKey::from_bytes(&hex::decode(&t.identity_secret).unwrap().try_into().unwrap());
+    let keys = HandshakeKeys::default()
+        .local_static(secret(&t.static_secret))
+        .local_ephemeral(secret(&t.ephemeral_secret));
+    let mut handshake = Libp2pHandshake::new(t.initiator, &identity, keys, &mut OsRng).unwrap();
+
+    let mut messages = t.messages.iter();
+    while !handshake.is_finished() {
+        let message = messages.next().unwrap();
+        if handshake.is_write_turn() {
+            assert_eq!(message.from, "local");
+            let mut out = Vec::new();
+            handshake.write_message(&mut out).unwrap();
+            assert_eq!(message.data, hex::encode(out));
+        } else {
+            assert_eq!(message.from, "remote");
+            handshake
+                .read_message(&mut hex::decode(&message.data).unwrap())
+                .unwrap();
+        }
+    }
+    let (mut framed, remote_identity) = handshake.finish().unwrap();
+    assert_eq!(t.remote_identity, hex::encode(remote_identity.as_bytes()));
+
+    for message in messages {
+        if message.from == "local" {
+            let mut out = Vec::new();
+            framed
+                .sender
+                .encrypt(b"hello from vru-noise", &mut out)
+                .unwrap();
+            assert_eq!(message.data, hex::encode(out));
+        } else {
+            framed.receiver.push(&hex::decode(&message.data).unwrap());
+            let payload = framed.receiver.decrypt().unwrap().unwrap();
+            assert_eq!(payload, b"hello from libp2p");
+        }
+    }
+}
+
+pub fn pair() {
+    let new = |initiator| {
+        let identity = SigningKey::generate(&mut OsRng);
+        let keys = HandshakeKeys::default().local_static(X25519::generate(&mut OsRng));
+        let handshake = Libp2pHandshake::new(initiator, &identity, keys, &mut OsRng).unwrap();
+        (handshake, identity.verifying_key())
+    };
+    let (mut initiator, initiator_identity) = new(true);
+    let (mut responder, responder_identity) = new(false);
+
+    let mut buffer = Vec::new();
+    for n in 0..3 {
+        let (sender, receiver) = if n % 2 == 0 {
+            (&mut initiator, &mut responder)
+        } else {
+            (&mut responder, &mut initiator)
+        };
+        sender.write_message(&mut buffer).unwrap();
+        receiver.read_message(&mut buffer).unwrap();
+        buffer.clear();
+    }
+
+    assert_eq!(initiator.remote_identity(), Some(&responder_identity));
+    assert_eq!(responder.remote_identity(), Some(&initiator_identity));
+    let (initiator, _) = initiator.finish().unwrap();
+    let (responder, _) = responder.finish().unwrap();
+    assert_eq!(initiator.hash, responder.hash);
+}
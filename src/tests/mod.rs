@@ -2,10 +2,29 @@ mod test_vector;
 use self::test_vector::TestVector;
 
 mod xk;
+mod dyn_symmetric_state;
+#[cfg(feature = "serde")]
+mod persist;
+mod binary;
 mod xn_psk3;
+mod handshake;
 
-mod elliptic;
-use self::elliptic::{C25519Scalar, X448Scalar};
+mod cipher;
+mod dh;
+mod framing;
+mod stream;
+mod tokio_io;
+mod futures_io;
+mod libp2p;
+mod bolt8;
+mod wireguard;
+mod pipes;
+mod hfs;
+mod typestate;
+mod pattern;
+mod protocol_name;
+
+use crate::{X25519, X448, Secp256k1};
 
 use hkdf::hmac::{Hmac, SimpleHmac};
 use sha2::{Sha256, Sha512};
@@ -18,128 +37,160 @@ use aes_gcm::Aes256Gcm;
 #[allow(non_snake_case)]
 fn Noise_XK_25519_ChaChaPoly_SHA512() {
     let vector = TestVector::try_load("Noise_XK_25519_ChaChaPoly_SHA512").unwrap();
-    xk::f::<C25519Scalar, [u8; 32], (Hmac<Sha512>, Sha512, B0, ChaCha20Poly1305)>(&vector);
+    xk::f::<(Hmac<Sha512>, Sha512, B0, ChaCha20Poly1305, X25519)>(&vector);
 }
 
 #[test]
 #[allow(non_snake_case)]
 fn Noise_XK_25519_AESGCM_SHA512() {
     let vector = TestVector::try_load("Noise_XK_25519_AESGCM_SHA512").unwrap();
-    xk::f::<C25519Scalar, [u8; 32], (Hmac<Sha512>, Sha512, B1, Aes256Gcm)>(&vector);
+    xk::f::<(Hmac<Sha512>, Sha512, B1, Aes256Gcm, X25519)>(&vector);
 }
 
 #[test]
 #[allow(non_snake_case)]
 fn Noise_XK_25519_ChaChaPoly_SHA256() {
     let vector = TestVector::try_load("Noise_XK_25519_ChaChaPoly_SHA256").unwrap();
-    xk::f::<C25519Scalar, [u8; 32], (Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305)>(&vector);
+    xk::f::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519)>(&vector);
 }
 
 #[test]
 #[allow(non_snake_case)]
 fn Noise_XK_25519_AESGCM_SHA256() {
     let vector = TestVector::try_load("Noise_XK_25519_AESGCM_SHA256").unwrap();
-    xk::f::<C25519Scalar, [u8; 32], (Hmac<Sha256>, Sha256, B1, Aes256Gcm)>(&vector);
+    xk::f::<(Hmac<Sha256>, Sha256, B1, Aes256Gcm, X25519)>(&vector);
 }
 
 #[test]
 #[allow(non_snake_case)]
 fn Noise_XK_25519_ChaChaPoly_BLAKE2b() {
     let vector = TestVector::try_load("Noise_XK_25519_ChaChaPoly_BLAKE2b").unwrap();
-    xk::f::<C25519Scalar, [u8; 32], (SimpleHmac<Blake2b512>, Blake2b512, B0, ChaCha20Poly1305)>(
-        &vector,
-    );
+    xk::f::<(
+        SimpleHmac<Blake2b512>,
+        Blake2b512,
+        B0,
+        ChaCha20Poly1305,
+        X25519,
+    )>(&vector);
 }
 
 #[test]
 #[allow(non_snake_case)]
 fn Noise_XK_25519_AESGCM_BLAKE2b() {
     let vector = TestVector::try_load("Noise_XK_25519_AESGCM_BLAKE2b").unwrap();
-    xk::f::<C25519Scalar, [u8; 32], (SimpleHmac<Blake2b512>, Blake2b512, B1, aes_gcm::Aes256Gcm)>(
-        &vector,
-    );
+    xk::f::<(
+        SimpleHmac<Blake2b512>,
+        Blake2b512,
+        B1,
+        aes_gcm::Aes256Gcm,
+        X25519,
+    )>(&vector);
 }
 
 #[test]
 #[allow(non_snake_case)]
 fn Noise_XK_25519_ChaChaPoly_BLAKE2s() {
     let vector = TestVector::try_load("Noise_XK_25519_ChaChaPoly_BLAKE2s").unwrap();
-    xk::f::<C25519Scalar, [u8; 32], (SimpleHmac<Blake2s256>, Blake2s256, B0, ChaCha20Poly1305)>(
-        &vector,
-    );
+    xk::f::<(
+        SimpleHmac<Blake2s256>,
+        Blake2s256,
+        B0,
+        ChaCha20Poly1305,
+        X25519,
+    )>(&vector);
 }
 
 #[test]
 #[allow(non_snake_case)]
 fn Noise_XK_25519_AESGCM_BLAKE2s() {
     let vector = TestVector::try_load("Noise_XK_25519_AESGCM_BLAKE2s").unwrap();
-    xk::f::<C25519Scalar, [u8; 32], (SimpleHmac<Blake2s256>, Blake2s256, B1, aes_gcm::Aes256Gcm)>(
-        &vector,
-    );
+    xk::f::<(
+        SimpleHmac<Blake2s256>,
+        Blake2s256,
+        B1,
+        aes_gcm::Aes256Gcm,
+        X25519,
+    )>(&vector);
 }
 
 #[test]
 #[allow(non_snake_case)]
 fn Noise_XK_448_ChaChaPoly_SHA512() {
     let vector = TestVector::try_load("Noise_XK_448_ChaChaPoly_SHA512").unwrap();
-    xk::f::<X448Scalar, [u8; 56], (Hmac<Sha512>, Sha512, B0, ChaCha20Poly1305)>(&vector);
+    xk::f::<(Hmac<Sha512>, Sha512, B0, ChaCha20Poly1305, X448)>(&vector);
 }
 
 #[test]
 #[allow(non_snake_case)]
 fn Noise_XK_448_AESGCM_SHA512() {
     let vector = TestVector::try_load("Noise_XK_448_AESGCM_SHA512").unwrap();
-    xk::f::<X448Scalar, [u8; 56], (Hmac<Sha512>, Sha512, B1, Aes256Gcm)>(&vector);
+    xk::f::<(Hmac<Sha512>, Sha512, B1, Aes256Gcm, X448)>(&vector);
 }
 
 #[test]
 #[allow(non_snake_case)]
 fn Noise_XK_448_ChaChaPoly_SHA256() {
     let vector = TestVector::try_load("Noise_XK_448_ChaChaPoly_SHA256").unwrap();
-    xk::f::<X448Scalar, [u8; 56], (Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305)>(&vector);
+    xk::f::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X448)>(&vector);
 }
 
 #[test]
 #[allow(non_snake_case)]
 fn Noise_XK_448_AESGCM_SHA256() {
     let vector = TestVector::try_load("Noise_XK_448_AESGCM_SHA256").unwrap();
-    xk::f::<X448Scalar, [u8; 56], (Hmac<Sha256>, Sha256, B1, Aes256Gcm)>(&vector);
+    xk::f::<(Hmac<Sha256>, Sha256, B1, Aes256Gcm, X448)>(&vector);
 }
 
 #[test]
 #[allow(non_snake_case)]
 fn Noise_XK_448_ChaChaPoly_BLAKE2b() {
     let vector = TestVector::try_load("Noise_XK_448_ChaChaPoly_BLAKE2b").unwrap();
-    xk::f::<X448Scalar, [u8; 56], (SimpleHmac<Blake2b512>, Blake2b512, B0, ChaCha20Poly1305)>(
-        &vector,
-    );
+    xk::f::<(
+        SimpleHmac<Blake2b512>,
+        Blake2b512,
+        B0,
+        ChaCha20Poly1305,
+        X448,
+    )>(&vector);
 }
 
 #[test]
 #[allow(non_snake_case)]
 fn Noise_XK_448_AESGCM_BLAKE2b() {
     let vector = TestVector::try_load("Noise_XK_448_AESGCM_BLAKE2b").unwrap();
-    xk::f::<X448Scalar, [u8; 56], (SimpleHmac<Blake2b512>, Blake2b512, B1, aes_gcm::Aes256Gcm)>(
-        &vector,
-    );
+    xk::f::<(
+        SimpleHmac<Blake2b512>,
+        Blake2b512,
+        B1,
+        aes_gcm::Aes256Gcm,
+        X448,
+    )>(&vector);
 }
 
 #[test]
 #[allow(non_snake_case)]
 fn Noise_XK_448_ChaChaPoly_BLAKE2s() {
     let vector = TestVector::try_load("Noise_XK_448_ChaChaPoly_BLAKE2s").unwrap();
-    xk::f::<X448Scalar, [u8; 56], (SimpleHmac<Blake2s256>, Blake2s256, B0, ChaCha20Poly1305)>(
-        &vector,
-    );
+    xk::f::<(
+        SimpleHmac<Blake2s256>,
+        Blake2s256,
+        B0,
+        ChaCha20Poly1305,
+        X448,
+    )>(&vector);
 }
 
 #[test]
 #[allow(non_snake_case)]
 fn Noise_XK_448_AESGCM_BLAKE2s() {
     let vector = TestVector::try_load("Noise_XK_448_AESGCM_BLAKE2s").unwrap();
-    xk::f::<X448Scalar, [u8; 56], (SimpleHmac<Blake2s256>, Blake2s256, B1, aes_gcm::Aes256Gcm)>(
-        &vector,
-    );
+    xk::f::<(
+        SimpleHmac<Blake2s256>,
+        Blake2s256,
+        B1,
+        aes_gcm::Aes256Gcm,
+        X448,
+    )>(&vector);
 }
 
 //
@@ -148,5 +199,442 @@ fn Noise_XK_448_AESGCM_BLAKE2s() {
 #[allow(non_snake_case)]
 fn Noise_XNpsk3_25519_ChaChaPoly_SHA512() {
     let vector = TestVector::try_load("Noise_XNpsk3_25519_ChaChaPoly_SHA512").unwrap();
-    xn_psk3::f::<C25519Scalar, [u8; 32], (Hmac<Sha512>, Sha512, B0, ChaCha20Poly1305)>(&vector);
+    xn_psk3::f::<(Hmac<Sha512>, Sha512, B0, ChaCha20Poly1305, X25519)>(&vector);
+}
+
+//
+
+#[test]
+fn dh_x25519() {
+    use crate::Dh;
+
+    dh::agreement::<X25519>();
+    // low order point
+    let secret = X25519::generate(&mut rand_core::OsRng);
+    assert!(X25519::dh(&secret, &[0; 32]).is_none());
+}
+
+#[test]
+fn dh_x448() {
+    dh::agreement::<X448>();
+    // low order point
+    assert!(<X448 as crate::Dh>::public_from_bytes(&[0; 56]).is_none());
+}
+
+#[test]
+fn dh_secp256k1() {
+    use crate::Dh;
+
+    dh::agreement::<Secp256k1>();
+    // uncompressed point, compact point, x = 0 is not on the curve
+    let public = Secp256k1::public(&Secp256k1::generate(&mut rand_core::OsRng));
+    let mut point = [0; 65];
+    point[0] = 4;
+    assert!(Secp256k1::public_from_bytes(&point).is_none());
+    let mut point = public;
+    point[0] = 5;
+    assert!(Secp256k1::public_from_bytes(&point).is_none());
+    let mut point = [0; 33];
+    point[0] = 2;
+    assert!(Secp256k1::public_from_bytes(&point).is_none());
+    // zero and the group order
+    assert!(Secp256k1::secret_from_bytes(&[0; 32]).is_none());
+    let order = "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141";
+    assert!(Secp256k1::secret_from_bytes(&hex::decode(order).unwrap()).is_none());
+}
+
+//
+
+#[test]
+fn protocol_name_round_trip() {
+    protocol_name::round_trip();
+}
+
+#[test]
+fn protocol_name_modifiers() {
+    protocol_name::modifiers();
+}
+
+#[test]
+fn protocol_name_errors() {
+    protocol_name::errors();
+}
+
+#[test]
+fn protocol_name_checked() {
+    protocol_name::checked::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519)>(
+        "Noise_XK_25519_ChaChaPoly_SHA256",
+    );
+    protocol_name::checked::<(
+        SimpleHmac<Blake2b512>,
+        Blake2b512,
+        B0,
+        ChaCha20Poly1305,
+        X25519,
+    )>("Noise_NNpsk0+psk2_25519_ChaChaPoly_BLAKE2b");
+}
+
+//
+
+#[test]
+#[allow(non_snake_case)]
+fn handshake_Noise_N_25519_ChaChaPoly_SHA256() {
+    let vector = TestVector::try_load("Noise_N_25519_ChaChaPoly_SHA256").unwrap();
+    handshake::f::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519)>(
+        &vector,
+        crate::patterns::N,
+    );
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn handshake_Noise_NN_25519_AESGCM_BLAKE2s() {
+    let vector = TestVector::try_load("Noise_NN_25519_AESGCM_BLAKE2s").unwrap();
+    handshake::f::<(SimpleHmac<Blake2s256>, Blake2s256, B1, Aes256Gcm, X25519)>(
+        &vector,
+        crate::patterns::NN,
+    );
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn handshake_Noise_NNpsk0_448_ChaChaPoly_BLAKE2b() {
+    let vector = TestVector::try_load("Noise_NNpsk0_448_ChaChaPoly_BLAKE2b").unwrap();
+    handshake::f::<(
+        SimpleHmac<Blake2b512>,
+        Blake2b512,
+        B0,
+        ChaCha20Poly1305,
+        X448,
+    )>(&vector, crate::patterns::NN_PSK0);
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn handshake_Noise_XK_25519_ChaChaPoly_SHA256() {
+    let vector = TestVector::try_load("Noise_XK_25519_ChaChaPoly_SHA256").unwrap();
+    handshake::f::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519)>(
+        &vector,
+        crate::patterns::XK,
+    );
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn handshake_Noise_XK_448_AESGCM_SHA512() {
+    let vector = TestVector::try_load("Noise_XK_448_AESGCM_SHA512").unwrap();
+    handshake::f::<(Hmac<Sha512>, Sha512, B1, Aes256Gcm, X448)>(&vector, crate::patterns::XK);
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn handshake_Noise_XNpsk3_25519_ChaChaPoly_SHA512() {
+    let vector = TestVector::try_load("Noise_XNpsk3_25519_ChaChaPoly_SHA512").unwrap();
+    handshake::f::<(Hmac<Sha512>, Sha512, B0, ChaCha20Poly1305, X25519)>(
+        &vector,
+        crate::patterns::XN_PSK3,
+    );
+}
+
+#[test]
+fn handshake_cacophony() {
+    handshake::all();
+}
+
+#[test]
+fn handshake_errors() {
+    let vector = TestVector::try_load("Noise_XK_25519_ChaChaPoly_SHA256").unwrap();
+    handshake::errors::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519)>(
+        &vector,
+        crate::patterns::XK,
+    );
+}
+
+#[test]
+fn pattern_valid() {
+    pattern::valid();
+}
+
+#[test]
+fn pattern_invalid() {
+    pattern::invalid();
+}
+
+#[test]
+fn pattern_payload_security() {
+    pattern::payload_security();
+}
+
+#[test]
+fn cipher_rekey() {
+    cipher::rekey::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305)>();
+    cipher::rekey::<(Hmac<Sha256>, Sha256, B1, Aes256Gcm)>();
+}
+
+#[test]
+fn cipher_rekey_interval() {
+    cipher::rekey_interval::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305)>();
+}
+
+#[test]
+fn cipher_nonce_exhausted() {
+    cipher::nonce_exhausted::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305)>();
+}
+
+#[test]
+fn cipher_export_import() {
+    cipher::export_import::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305)>();
+    cipher::export_import::<(Hmac<Sha256>, Sha256, B1, Aes256Gcm)>();
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn cipher_export_serde() {
+    cipher::export_serde::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305)>();
+}
+
+#[test]
+fn cipher_replay_window() {
+    cipher::replay_window::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305)>();
+}
+
+#[test]
+fn framing() {
+    framing::f::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305)>();
+    framing::f::<(Hmac<Sha256>, Sha256, B1, Aes256Gcm)>();
+}
+
+#[test]
+fn stream() {
+    stream::f::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519)>();
+}
+
+#[test]
+fn tokio_io() {
+    tokio_io::f::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519)>();
+}
+
+#[test]
+fn futures_io() {
+    futures_io::f::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519)>();
+}
+
+#[test]
+fn libp2p_initiator() {
+    libp2p::transcript(include_str!("libp2p_initiator.json"));
+}
+
+#[test]
+fn libp2p_responder() {
+    libp2p::transcript(include_str!("libp2p_responder.json"));
+}
+
+#[test]
+fn libp2p_pair() {
+    libp2p::pair();
+}
+
+#[test]
+fn bolt8_vectors() {
+    bolt8::vectors();
+}
+
+#[test]
+fn bolt8_errors() {
+    bolt8::errors();
+}
+
+#[test]
+fn bolt8_random() {
+    bolt8::random();
+}
+
+#[test]
+fn bolt8_handshake_state() {
+    bolt8::handshake_state();
+}
+
+#[test]
+fn wireguard_vectors() {
+    wireguard::vectors();
+}
+
+#[test]
+fn wireguard_handshake_state() {
+    wireguard::handshake_state();
+}
+
+#[test]
+fn wireguard_errors() {
+    wireguard::errors();
+}
+
+#[test]
+fn pipes_vectors() {
+    pipes::vectors();
+}
+
+#[test]
+fn pipes_fallback() {
+    pipes::fallback();
+}
+
+#[test]
+fn pipes() {
+    pipes::pipes();
+}
+
+#[test]
+fn pipes_errors() {
+    pipes::errors();
+}
+
+#[test]
+fn hfs_vectors() {
+    hfs::vectors();
+}
+
+#[test]
+fn hfs_patterns() {
+    hfs::patterns();
+}
+
+#[test]
+fn hfs_errors() {
+    hfs::errors();
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn typestate_Noise_XK_25519_ChaChaPoly_SHA256() {
+    let vector = TestVector::try_load("Noise_XK_25519_ChaChaPoly_SHA256").unwrap();
+    typestate::xk::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519)>(&vector);
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn typestate_Noise_XK_448_AESGCM_SHA512() {
+    let vector = TestVector::try_load("Noise_XK_448_AESGCM_SHA512").unwrap();
+    typestate::xk::<(Hmac<Sha512>, Sha512, B1, Aes256Gcm, X448)>(&vector);
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn typestate_Noise_IK_25519_ChaChaPoly_SHA256() {
+    let vector = TestVector::try_load("Noise_IK_25519_ChaChaPoly_SHA256").unwrap();
+    typestate::ik::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519)>(&vector);
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn typestate_Noise_XX_25519_ChaChaPoly_SHA256() {
+    let vector = TestVector::try_load("Noise_XX_25519_ChaChaPoly_SHA256").unwrap();
+    typestate::xx::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519)>(&vector);
+}
+
+#[test]
+fn typestate_errors() {
+    let vector = TestVector::try_load("Noise_XK_25519_ChaChaPoly_SHA256").unwrap();
+    typestate::errors::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519)>(&vector);
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn dyn_Noise_XK_25519_ChaChaPoly_SHA256() {
+    let vector = TestVector::try_load("Noise_XK_25519_ChaChaPoly_SHA256").unwrap();
+    dyn_symmetric_state::xk::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519)>(&vector);
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn dyn_Noise_XK_448_AESGCM_BLAKE2b() {
+    let vector = TestVector::try_load("Noise_XK_448_AESGCM_BLAKE2b").unwrap();
+    dyn_symmetric_state::xk::<(SimpleHmac<Blake2b512>, Blake2b512, B1, Aes256Gcm, X448)>(&vector);
+}
+
+#[test]
+fn dyn_conversion() {
+    let vector = TestVector::try_load("Noise_XK_25519_ChaChaPoly_SHA256").unwrap();
+    dyn_symmetric_state::conversion::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519)>(
+        &vector,
+    );
+}
+
+#[test]
+fn dyn_errors() {
+    let vector = TestVector::try_load("Noise_XK_25519_ChaChaPoly_SHA256").unwrap();
+    dyn_symmetric_state::errors::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519)>(&vector);
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn persist_keyed() {
+    let vector = TestVector::try_load("Noise_XK_25519_ChaChaPoly_SHA256").unwrap();
+    persist::keyed::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519)>(&vector);
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn persist_keyed_aes() {
+    let vector = TestVector::try_load("Noise_XK_448_AESGCM_SHA512").unwrap();
+    persist::keyed::<(Hmac<Sha512>, Sha512, B1, Aes256Gcm, X448)>(&vector);
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn persist_empty() {
+    let vector = TestVector::try_load("Noise_XK_25519_ChaChaPoly_SHA256").unwrap();
+    persist::empty::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519)>(&vector);
+}
+
+#[test]
+fn binary_round_trip() {
+    binary::round_trip::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305)>(
+        "Noise_XK_25519_ChaChaPoly_SHA256",
+    );
+    binary::round_trip::<(SimpleHmac<Blake2b512>, Blake2b512, B1, Aes256Gcm)>(
+        "Noise_XK_448_AESGCM_BLAKE2b",
+    );
+}
+
+#[test]
+fn binary_config_mismatch() {
+    binary::config_mismatch::<
+        (Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305),
+        (Hmac<Sha256>, Sha256, B0, Aes256Gcm),
+    >("Noise_XK_25519_ChaChaPoly_SHA256");
+    binary::config_mismatch::<
+        (Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305),
+        (Hmac<Sha512>, Sha512, B0, ChaCha20Poly1305),
+    >("Noise_XK_25519_ChaChaPoly_SHA256");
+    binary::config_mismatch::<
+        (Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305),
+        (Hmac<Sha256>, Sha256, B1, ChaCha20Poly1305),
+    >("Noise_XK_25519_ChaChaPoly_SHA256");
+    binary::config_mismatch::<
+        (Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519),
+        (Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X448),
+    >("Noise_XK_25519_ChaChaPoly_SHA256");
+    binary::config_mismatch::<
+        (Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305),
+        (Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519),
+    >("Noise_XK_25519_ChaChaPoly_SHA256");
+    binary::config_mismatch::<
+        (Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519),
+        (
+            Hmac<Sha256>,
+            Sha256,
+            B0,
+            ChaCha20Poly1305,
+            X25519,
+            hfs::DhKem,
+        ),
+    >("Noise_XK_25519_ChaChaPoly_SHA256");
+}
+
+#[test]
+fn binary_errors() {
+    binary::errors::<(Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305)>(
+        "Noise_XK_25519_ChaChaPoly_SHA256",
+    );
 }
@@ -0,0 +1,249 @@
+use crate::{
+    patterns, Token, HandshakePattern, TokenPosition, PatternErrorKind, PatternError,
+    PayloadSecurity,
+};
+
+pub fn valid() {
+    for pattern in patterns::ALL {
+        assert_eq!(pattern.validate(), Ok(()), "{}", pattern.name);
+    }
+}
+
+fn check(
+    initiator_pre: &'static [Token],
+    responder_pre: &'static [Token],
+    messages: &'static [&'static [Token]],
+    kind: PatternErrorKind,
+    token: Token,
+    position: TokenPosition,
+) {
+    let pattern = HandshakePattern {
+        name: "custom",
+        initiator_pre,
+        responder_pre,
+        messages,
+    };
+    let expected = PatternError {
+        kind,
+        token,
+        position,
+    };
+    assert_eq!(pattern.validate(), Err(expected));
+}
+
+const fn message(message: usize, index: usize) -> TokenPosition {
+    TokenPosition::Message { message, index }
+}
+
+pub fn invalid() {
+    use Token::*;
+
+    check(
+        &[],
+        &[Ee],
+        &[&[E]],
+        PatternErrorKind::InvalidPreMessage,
+        Ee,
+        TokenPosition::PreMessage {
+            initiator: false,
+            index: 0,
+        },
+    );
+    check(
+        &[S, E],
+        &[],
+        &[&[E]],
+        PatternErrorKind::InvalidPreMessage,
+        E,
+        TokenPosition::PreMessage {
+            initiator: true,
+            index: 1,
+        },
+    );
+    check(
+        &[],
+        &[S],
+        &[&[E, Es], &[E, Ee, S]],
+        PatternErrorKind::DuplicateKey,
+        S,
+        message(1, 2),
+    );
+    check(
+        &[],
+        &[],
+        &[&[E], &[E, Ee], &[E]],
+        PatternErrorKind::DuplicateKey,
+        E,
+        message(2, 0),
+    );
+    check(
+        &[],
+        &[],
+        &[&[E], &[E, Ee, Ee]],
+        PatternErrorKind::DuplicateDh,
+        Ee,
+        message(1, 2),
+    );
+    check(
+        &[],
+        &[],
+        &[&[E, Es], &[E, Ee]],
+        PatternErrorKind::MissingKey,
+        Es,
+        message(0, 1),
+    );
+    check(
+        &[],
+        &[],
+        &[&[E], &[E, Ee, Se]],
+        PatternErrorKind::MissingKey,
+        Se,
+        message(1, 2),
+    );
+    // the responder encrypts under its static key without `ee`
+    check(
+        &[],
+        &[S],
+        &[&[E, Es], &[E]],
+        PatternErrorKind::MissingEphemeralDh,
+        Es,
+        message(0, 1),
+    );
+    // the initiator sends a transport payload after `ss` without `es`
+    check(
+        &[S],
+        &[S],
+        &[&[Ss]],
+        PatternErrorKind::MissingEphemeralDh,
+        Ss,
+        message(0, 0),
+    );
+    check(
+        &[],
+        &[],
+        &[&[Psk], &[E]],
+        PatternErrorKind::PskWithoutEphemeral,
+        Psk,
+        message(0, 0),
+    );
+    check(
+        &[],
+        &[S],
+        &[&[Psk, S, E]],
+        PatternErrorKind::PskWithoutEphemeral,
+        S,
+        message(0, 1),
+    );
+    // `e1` before `e`, `ekem1` without `e1`, twice `ekem1`
+    check(
+        &[],
+        &[],
+        &[&[E1, E], &[E, Ee, Ekem1]],
+        PatternErrorKind::MissingKey,
+        E1,
+        message(0, 0),
+    );
+    check(
+        &[],
+        &[],
+        &[&[E], &[E, Ee, Ekem1]],
+        PatternErrorKind::MissingKey,
+        Ekem1,
+        message(1, 2),
+    );
+    check(
+        &[],
+        &[],
+        &[&[E, E1], &[E, Ee, Ekem1, Ekem1]],
+        PatternErrorKind::DuplicateDh,
+        Ekem1,
+        message(1, 3),
+    );
+    // `e1` is encrypted under the initiator's static key without `ee`
+    check(
+        &[S],
+        &[],
+        &[&[E], &[E], &[Se, E1, Ee]],
+        PatternErrorKind::MissingEphemeralDh,
+        Se,
+        message(2, 0),
+    );
+    check(
+        &[],
+        &[E1],
+        &[&[E]],
+        PatternErrorKind::InvalidPreMessage,
+        E1,
+        TokenPosition::PreMessage {
+            initiator: false,
+            index: 0,
+        },
+    );
+}
+
+// the tables of the section 7.7 of the Noise spec
+pub fn payload_security() {
+    #[rustfmt::skip]
+    let tables: &[(HandshakePattern, &[(u8, u8)])] = &[
+        (patterns::N, &[(0, 2)]),
+        (patterns::K, &[(1, 2)]),
+        (patterns::X, &[(1, 2)]),
+        (patterns::NN, &[(0, 0), (0, 1), (0, 1)]),
+        (patterns::NK, &[(0, 2), (2, 1), (0, 5)]),
+        (patterns::NX, &[(0, 0), (2, 1), (0, 5)]),
+        (patterns::XN, &[(0, 0), (0, 1), (2, 1), (0, 5)]),
+        (patterns::XK, &[(0, 2), (2, 1), (2, 5), (2, 5)]),
+        (patterns::XX, &[(0, 0), (2, 1), (2, 5), (2, 5)]),
+        (patterns::KN, &[(0, 0), (0, 3), (2, 1), (0, 5)]),
+        (patterns::KK, &[(1, 2), (2, 4), (2, 5), (2, 5)]),
+        (patterns::KX, &[(0, 0), (2, 3), (2, 5), (2, 5)]),
+        (patterns::IN, &[(0, 0), (0, 3), (2, 1), (0, 5)]),
+        (patterns::IK, &[(1, 2), (2, 4), (2, 5), (2, 5)]),
+        (patterns::IX, &[(0, 0), (2, 3), (2, 5), (2, 5)]),
+        (patterns::NK1, &[(0, 0), (2, 1), (0, 5)]),
+        (patterns::NX1, &[(0, 0), (0, 1), (0, 3), (2, 1), (0, 5)]),
+        (patterns::X1N, &[(0, 0), (0, 1), (0, 1), (0, 3), (2, 1), (0, 5)]),
+        (patterns::X1K, &[(0, 2), (2, 1), (0, 5), (2, 3), (2, 5), (2, 5)]),
+        (patterns::XK1, &[(0, 0), (2, 1), (2, 5), (2, 5)]),
+        (patterns::X1K1, &[(0, 0), (2, 1), (0, 5), (2, 3), (2, 5), (2, 5)]),
+        (patterns::X1X, &[(0, 0), (2, 1), (0, 5), (2, 3), (2, 5), (2, 5)]),
+        (patterns::XX1, &[(0, 0), (0, 1), (2, 3), (2, 5), (2, 5)]),
+        (patterns::X1X1, &[(0, 0), (0, 1), (0, 3), (2, 3), (2, 5), (2, 5)]),
+        (patterns::K1N, &[(0, 0), (0, 1), (2, 1), (0, 5)]),
+        (patterns::K1K, &[(0, 2), (2, 1), (2, 5), (2, 5)]),
+        (patterns::KK1, &[(0, 0), (2, 3), (2, 5), (2, 5)]),
+        (patterns::K1K1, &[(0, 0), (2, 1), (2, 5), (2, 5)]),
+        (patterns::K1X, &[(0, 0), (2, 1), (2, 5), (2, 5)]),
+        (patterns::KX1, &[(0, 0), (0, 3), (2, 3), (2, 5), (2, 5)]),
+        (patterns::K1X1, &[(0, 0), (0, 1), (2, 3), (2, 5), (2, 5)]),
+        (patterns::I1N, &[(0, 0), (0, 1), (2, 1), (0, 5)]),
+        (patterns::I1K, &[(0, 2), (2, 1), (2, 5), (2, 5)]),
+        (patterns::IK1, &[(0, 0), (2, 3), (2, 5), (2, 5)]),
+        (patterns::I1K1, &[(0, 0), (2, 1), (2, 5), (2, 5)]),
+        (patterns::I1X, &[(0, 0), (2, 1), (2, 5), (2, 5)]),
+        (patterns::IX1, &[(0, 0), (0, 3), (2, 3), (2, 5), (2, 5)]),
+        (patterns::I1X1, &[(0, 0), (0, 1), (2, 3), (2, 5), (2, 5)]),
+    ];
+    for (pattern, table) in tables {
+        for (n, &(authentication, confidentiality)) in table.iter().enumerate() {
+            let expected = PayloadSecurity {
+                authentication,
+                confidentiality,
+            };
+            assert_eq!(
+                pattern.payload_security(n),
+                expected,
+                "{} message {}",
+                pattern.name,
+                n
+            );
+        }
+    }
+
+    let required = PayloadSecurity {
+        authentication: 2,
+        confidentiality: 5,
+    };
+    assert!(patterns::XX.payload_security(2).at_least(&required));
+    assert!(!patterns::XX.payload_security(1).at_least(&required));
+}
@@ -0,0 +1,114 @@
+use alloc::{format, vec::Vec};
+
+use generic_array::typenum::{U0, U1};
+
+use super::test_vector::TestVector;
+use crate::{DynSymmetricState, SymmetricState, ConfigExt, Dh, Key, CipherError};
+
+fn pair<D>(hex: &str) -> (D::Secret, D::Public)
+where
+    D: Dh,
+{
+    let secret = D::secret_from_bytes(&hex::decode(hex).unwrap()).unwrap();
+    let public = D::public(&secret);
+    (secret, public)
+}
+
+/// The initiator of `XK` is saved after each message and restored to continue.
+pub fn keyed<C>(v: &TestVector<'_>)
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+{
+    let dh = |secret, public| C::Dh::dh(secret, public).unwrap();
+    let init_ephemeral = pair::<C::Dh>(v.init_ephemeral);
+    let resp_ephemeral = pair::<C::Dh>(v.resp_ephemeral.unwrap());
+    let init_static = pair::<C::Dh>(v.init_static.unwrap());
+    let resp_static = pair::<C::Dh>(v.resp_static.unwrap());
+    let payload = |n: usize| hex::decode(v.messages[n].payload).unwrap();
+
+    // -> e, es
+    let mut payload0 = payload(0);
+    let state = SymmetricState::<C, _>::new(v.name)
+        .mix_hash(&hex::decode(v.prologue).unwrap())
+        .mix_hash(resp_static.1.as_ref())
+        .mix_hash(init_ephemeral.1.as_ref())
+        .mix_shared_secret(dh(&init_ephemeral.0, &resp_static.1))
+        .encrypt_ext(&mut payload0);
+    let hash = state.hash();
+    let saved = serde_json::to_string(&state).unwrap();
+    // the nonce is wrong
+    assert!(serde_json::from_str::<SymmetricState<C, Key<C, U0>>>(&saved).is_err());
+    let state = serde_json::from_str::<SymmetricState<C, Key<C, U1>>>(&saved).unwrap();
+    assert_eq!(state.hash(), hash);
+
+    // <- e, ee
+    let mut payload1 = payload(1);
+    let state = state
+        .mix_hash(resp_ephemeral.1.as_ref())
+        .mix_shared_secret(dh(&init_ephemeral.0, &resp_ephemeral.1))
+        .encrypt_ext(&mut payload1);
+    let ct = [resp_ephemeral.1.as_ref(), &payload1].concat();
+    assert_eq!(v.messages[1].ciphertext, hex::encode(ct));
+
+    // the keys are not printed
+    let printed = format!("{:?}", state);
+    assert!(printed.contains("nonce: 1"));
+    assert!(!printed.contains("key"));
+
+    // the dynamic state keeps the nonce
+    let state = DynSymmetricState::from(state);
+    let saved = serde_json::to_string(&state).unwrap();
+    let state = serde_json::from_str::<DynSymmetricState<C>>(&saved).unwrap();
+    assert_eq!(state.nonce(), Some(1));
+
+    // -> s, se
+    let mut s = init_static.1.as_ref().to_vec();
+    let (state, tag) = state.encrypt(&mut s).unwrap();
+    s.extend_from_slice(&tag.unwrap());
+    let state = state.mix_shared_secret(dh(&init_static.0, &resp_ephemeral.1));
+    let saved = serde_json::to_string(&state).unwrap();
+    let state = serde_json::from_str::<DynSymmetricState<C>>(&saved).unwrap();
+    let mut payload2 = payload(2);
+    let (state, tag) = state.encrypt(&mut payload2).unwrap();
+    payload2.extend_from_slice(&tag.unwrap());
+    let ct = [s, payload2].concat();
+    assert_eq!(v.messages[2].ciphertext, hex::encode(ct));
+
+    let output = state.finish::<1, false>();
+    assert_eq!(v.handshake_hash, hex::encode(output.hash));
+}
+
+pub fn empty<C>(v: &TestVector<'_>)
+where
+    C: ConfigExt,
+{
+    let state = DynSymmetricState::<C>::new(v.name).mix_hash(b"prologue");
+    let saved = serde_json::to_string(&state).unwrap();
+    let restored = serde_json::from_str::<DynSymmetricState<C>>(&saved).unwrap();
+    assert!(!restored.has_key());
+    assert_eq!(restored.hash(), state.hash());
+    // same encoding as the typed state
+    let typed = SymmetricState::<C, _>::new(v.name).mix_hash(b"prologue");
+    assert_eq!(saved, serde_json::to_string(&typed).unwrap());
+
+    let truncated = saved.replacen("\"hash\":\"", "\"hash\":\"00", 1);
+    assert!(serde_json::from_str::<DynSymmetricState<C>>(&truncated).is_err());
+    let states = [state.mix_psk([1; 32]), restored]
+        .iter()
+        .map(|state| serde_json::to_string(state).unwrap())
+        .collect::<Vec<_>>();
+    assert!(states[0].contains("\"nonce\":0") && !states[1].contains("nonce"));
+
+    // 2^64-1 is reserved, the state stops before it
+    let nonce = |n: u64| states[0].replace("\"nonce\":0", &format!("\"nonce\":{}", n));
+    assert!(serde_json::from_str::<DynSymmetricState<C>>(&nonce(u64::MAX)).is_err());
+    let state = serde_json::from_str::<DynSymmetricState<C>>(&nonce(u64::MAX - 1)).unwrap();
+    let (state, tag) = state.encrypt(&mut [0; 4]).unwrap();
+    assert!(tag.is_some());
+    assert_eq!(state.nonce(), Some(u64::MAX));
+    assert!(matches!(
+        state.encrypt(&mut [0; 4]),
+        Err(CipherError::NonceExhausted)
+    ));
+}
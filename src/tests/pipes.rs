@@ -0,0 +1,240 @@
+use rand_core::OsRng;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use generic_array::typenum::B0;
+use hkdf::hmac::Hmac;
+use sha2::Sha256;
+
+use crate::{
+    patterns, Dh, HandshakeError, HandshakeKeys, HandshakeState, NoisePipe, PipeMode, X25519,
+    X25519Secret,
+};
+
+type C = (Hmac<Sha256>, Sha256, B0, ChaCha20Poly1305, X25519);
+
+// recorded with an independent implementation of the section 10.2 of the Noise spec,
+// Alice has the keys 1 and 2, Bob has the keys 3 and 4, the prologue is "pipes"
+const MSG0: &str = "ac01b2209e86354fb853237b5de0f4fab13c7fcbf433a61c019369617fecf10b2a394e537849726c85190760a0bb6ffcdf2f35c4e8604574590f33697d27ce9b817462c5a7c33d026c1016a4b34be2d42d1b3492d8f2ab3ffc650283ae242eceb96cf2";
+const MSG1: &str = "4eca5513f6b2372472b3ea9f2b4b29ca3345de0ad2f7f860c7695d55b6721a3748b5e537701f18267dd18f4148b184d1c4f7c67224c6348ba9a23f6248176bb41abbe54fed";
+const HASH: &str = "aa274c3208d125cdcaddbb140ac7d3f073b228601f395641d08ea3802484d70d";
+// "ping" sent by Bob
+const PING: &str = "f88053562893621814970ae12033582c0d6078b9";
+
+const PROLOGUE: &[u8] = b"pipes";
+
+fn secret(byte: u8) -> X25519Secret {
+    X25519::secret_from_bytes(&[byte; 32]).unwrap()
+}
+
+fn transfer<'m>(
+    sender: &mut NoisePipe<'_, C>,
+    receiver: &mut NoisePipe<'_, C>,
+    payload: &[u8],
+    buffer: &'m mut [u8],
+) -> Option<&'m mut [u8]> {
+    let len = sender.write_message(payload, buffer).unwrap();
+    receiver
+        .read_message(&mut buffer[..len], &mut OsRng)
+        .unwrap()
+}
+
+fn check_transport(initiator: NoisePipe<'_, C>, responder: NoisePipe<'_, C>) {
+    let mut initiator = initiator.finish::<1>().unwrap();
+    let mut responder = responder.finish::<1>().unwrap();
+    assert_eq!(initiator.hash, responder.hash);
+
+    let mut buffer = *b"ping";
+    let tag = initiator.sender.encrypt(&[], &mut buffer).unwrap();
+    responder.receiver.decrypt(&[], &mut buffer, &tag).unwrap();
+    assert_eq!(&buffer, b"ping");
+}
+
+/// Bob re-initializes from the ephemeral key of Alice, Alice keeps her ephemeral key.
+pub fn vectors() {
+    let keys = HandshakeKeys::default()
+        .local_static(secret(3))
+        .local_ephemeral(secret(4))
+        .remote_ephemeral(X25519::public(&secret(2)));
+    let mut bob =
+        HandshakeState::<C>::new(patterns::XX_FALLBACK, true, PROLOGUE, keys, &mut OsRng).unwrap();
+    let keys = HandshakeKeys::default()
+        .local_static(secret(1))
+        .local_ephemeral(secret(2));
+    let mut alice =
+        HandshakeState::<C>::new(patterns::XX_FALLBACK, false, PROLOGUE, keys, &mut OsRng).unwrap();
+
+    let mut buffer = [0; 256];
+    let len = bob.write_message(b"bob", &mut buffer).unwrap();
+    assert_eq!(hex::encode(&buffer[..len]), MSG0);
+    assert_eq!(alice.read_message(&mut buffer[..len]).unwrap(), b"bob");
+    let len = alice.write_message(b"alice", &mut buffer).unwrap();
+    assert_eq!(hex::encode(&buffer[..len]), MSG1);
+    assert_eq!(bob.read_message(&mut buffer[..len]).unwrap(), b"alice");
+    assert_eq!(alice.remote_static(), Some(&X25519::public(&secret(3))));
+    assert_eq!(bob.remote_static(), Some(&X25519::public(&secret(1))));
+
+    let mut bob = bob.finish::<1>().unwrap();
+    let alice = alice.finish::<1>().unwrap();
+    assert_eq!(hex::encode(bob.hash), HASH);
+    assert_eq!(alice.hash, bob.hash);
+    let mut buffer = *b"ping";
+    let tag = bob.sender.encrypt(&[], &mut buffer).unwrap();
+    assert_eq!(hex::encode([&buffer[..], &tag].concat()), PING);
+}
+
+/// The failed `IK` handshake falls back to the same transcript.
+pub fn fallback() {
+    let keys = HandshakeKeys::default()
+        .local_static(secret(1))
+        .local_ephemeral(secret(2))
+        // Bob has changed his static key
+        .remote_static(X25519::public(&secret(5)));
+    let mut alice =
+        HandshakeState::<C>::new(patterns::IK, true, PROLOGUE, keys, &mut OsRng).unwrap();
+    let keys = HandshakeKeys::default().local_static(secret(3));
+    let mut bob =
+        HandshakeState::<C>::new(patterns::IK, false, PROLOGUE, keys, &mut OsRng).unwrap();
+
+    let mut buffer = [0; 256];
+    let len = alice.write_message(b"early data", &mut buffer).unwrap();
+    assert_eq!(
+        bob.read_message(&mut buffer[..len]),
+        Err(HandshakeError::MacMismatch)
+    );
+
+    let mut bob = bob
+        .fallback(patterns::XX_FALLBACK, PROLOGUE, &mut OsRng)
+        .unwrap();
+    let mut alice = alice
+        .fallback(patterns::XX_FALLBACK, PROLOGUE, &mut OsRng)
+        .unwrap();
+    assert!(bob.is_initiator() && !alice.is_initiator());
+    let len = bob.write_message(b"bob", &mut buffer).unwrap();
+    assert_eq!(alice.read_message(&mut buffer[..len]).unwrap(), b"bob");
+    let len = alice.write_message(b"alice", &mut buffer).unwrap();
+    assert_eq!(bob.read_message(&mut buffer[..len]).unwrap(), b"alice");
+    assert_eq!(
+        bob.finish::<1>().unwrap().hash,
+        alice.finish::<1>().unwrap().hash
+    );
+}
+
+pub fn pipes() {
+    let bob_public = X25519::public(&secret(3));
+    let mut buffer = [0; 256];
+
+    // no cached key
+    let mut alice = NoisePipe::<C>::initiator(PROLOGUE, secret(1), None, &mut OsRng).unwrap();
+    assert_eq!(alice.mode(), PipeMode::Full);
+    let mut bob = NoisePipe::<C>::responder(PROLOGUE, false, secret(3), &mut OsRng).unwrap();
+    assert_eq!(
+        transfer(&mut alice, &mut bob, b"", &mut buffer).unwrap(),
+        b""
+    );
+    assert_eq!(
+        transfer(&mut bob, &mut alice, b"", &mut buffer).unwrap(),
+        b""
+    );
+    assert_eq!(
+        transfer(&mut alice, &mut bob, b"", &mut buffer).unwrap(),
+        b""
+    );
+    assert_eq!(alice.remote_static(), Some(&bob_public));
+    check_transport(alice, bob);
+
+    // the cached key is valid
+    let mut alice =
+        NoisePipe::<C>::initiator(PROLOGUE, secret(1), Some(bob_public), &mut OsRng).unwrap();
+    assert_eq!(alice.mode(), PipeMode::ZeroRtt);
+    let mut bob = NoisePipe::<C>::responder(PROLOGUE, true, secret(3), &mut OsRng).unwrap();
+    let payload = transfer(&mut alice, &mut bob, b"early data", &mut buffer);
+    assert_eq!(payload.unwrap(), b"early data");
+    assert_eq!(
+        transfer(&mut bob, &mut alice, b"", &mut buffer).unwrap(),
+        b""
+    );
+    assert!(alice.is_finished() && bob.is_finished());
+    check_transport(alice, bob);
+
+    // the cached key is stale
+    let stale = X25519::public(&secret(5));
+    let mut alice =
+        NoisePipe::<C>::initiator(PROLOGUE, secret(1), Some(stale), &mut OsRng).unwrap();
+    let mut bob = NoisePipe::<C>::responder(PROLOGUE, true, secret(3), &mut OsRng).unwrap();
+    assert!(transfer(&mut alice, &mut bob, b"early data", &mut buffer).is_none());
+    assert_eq!(bob.mode(), PipeMode::Fallback);
+    assert!(!bob.is_initiator() && bob.is_write_turn());
+    // Bob tells Alice about the fallback
+    alice.fallback(&mut OsRng).unwrap();
+    assert_eq!(alice.mode(), PipeMode::Fallback);
+    assert!(alice.is_initiator() && !alice.is_write_turn());
+    assert_eq!(
+        transfer(&mut bob, &mut alice, b"bob", &mut buffer).unwrap(),
+        b"bob"
+    );
+    assert_eq!(alice.remote_static(), Some(&bob_public));
+    assert_eq!(
+        transfer(&mut alice, &mut bob, b"", &mut buffer).unwrap(),
+        b""
+    );
+    assert_eq!(bob.remote_static(), Some(&X25519::public(&secret(1))));
+    assert!(alice.is_finished() && bob.is_finished());
+    check_transport(alice, bob);
+}
+
+pub fn errors() {
+    let new = |pattern, initiator| {
+        let keys = HandshakeKeys::default()
+            .local_static(secret(1))
+            .remote_static(X25519::public(&secret(3)));
+        HandshakeState::<C>::new(pattern, initiator, PROLOGUE, keys, &mut OsRng).unwrap()
+    };
+    assert!(patterns::XX_FALLBACK.is_fallback() && !patterns::XX.is_fallback());
+    assert_eq!(
+        new(patterns::IK, true)
+            .fallback(patterns::XX, PROLOGUE, &mut OsRng)
+            .err(),
+        Some(HandshakeError::NotFallback)
+    );
+    // the responder has not received the ephemeral key yet
+    assert_eq!(
+        new(patterns::IK, false)
+            .fallback(patterns::XX_FALLBACK, PROLOGUE, &mut OsRng)
+            .err(),
+        Some(HandshakeError::MissingRemoteEphemeral)
+    );
+
+    let mut buffer = [0; 256];
+    let mut alice = new(patterns::NN, true);
+    let mut bob = new(patterns::NN, false);
+    let len = alice.write_message(&[], &mut buffer).unwrap();
+    bob.read_message(&mut buffer[..len]).unwrap();
+    let len = bob.write_message(&[], &mut buffer).unwrap();
+    alice.read_message(&mut buffer[..len]).unwrap();
+    assert_eq!(
+        alice
+            .fallback(patterns::XX_FALLBACK, PROLOGUE, &mut OsRng)
+            .err(),
+        Some(HandshakeError::Finished)
+    );
+
+    let mut alice = NoisePipe::<C>::initiator(PROLOGUE, secret(1), None, &mut OsRng).unwrap();
+    assert_eq!(alice.fallback(&mut OsRng), Err(HandshakeError::NotFallback));
+    let stale = Some(X25519::public(&secret(5)));
+    let mut alice = NoisePipe::<C>::initiator(PROLOGUE, secret(1), stale, &mut OsRng).unwrap();
+    assert_eq!(alice.fallback(&mut OsRng), Err(HandshakeError::WrongTurn));
+    let mut bob = NoisePipe::<C>::responder(PROLOGUE, true, secret(3), &mut OsRng).unwrap();
+    assert_eq!(bob.fallback(&mut OsRng), Err(HandshakeError::NotFallback));
+
+    // only the decryption failure falls back
+    let len = alice.write_message(&[], &mut buffer).unwrap();
+    assert_eq!(
+        bob.read_message(&mut buffer[..16], &mut OsRng),
+        Err(HandshakeError::MessageTooShort)
+    );
+    assert_eq!(bob.mode(), PipeMode::ZeroRtt);
+    assert_eq!(
+        bob.read_message(&mut buffer[..len], &mut OsRng),
+        Err(HandshakeError::Aborted)
+    );
+}
@@ -0,0 +1,115 @@
+use alloc::{string::ToString, vec::Vec};
+
+use super::test_vector::TestVector;
+use crate::{SymmetricState, Modifier, ProtocolName, ProtocolNameError, NoiseName, ConfigExt};
+
+pub fn round_trip() {
+    for name in TestVector::names() {
+        let parsed = ProtocolName::parse(name).unwrap();
+        assert_eq!(parsed.to_string(), name);
+        assert!(name.contains(parsed.base_pattern()));
+    }
+}
+
+pub fn modifiers() {
+    let name = ProtocolName::parse("Noise_XXfallback+psk0_25519_AESGCM_SHA256").unwrap();
+    assert_eq!(name.base_pattern(), "XX");
+    assert_eq!(
+        name.modifiers().collect::<Vec<_>>(),
+        [Modifier::Fallback, Modifier::Psk(0)]
+    );
+    let name = ProtocolName::parse("Noise_X1K1_448_ChaChaPoly_BLAKE2b").unwrap();
+    assert_eq!(name.base_pattern(), "X1K1");
+    assert_eq!(name.modifiers().count(), 0);
+    let name = ProtocolName::parse("Noise_XXhfs_25519+Kyber1024_ChaChaPoly_SHA256").unwrap();
+    assert_eq!(name.modifiers().collect::<Vec<_>>(), [Modifier::Hfs]);
+    assert_eq!((name.dh, name.kem), ("25519", Some("Kyber1024")));
+    assert_eq!(
+        name.to_string(),
+        "Noise_XXhfs_25519+Kyber1024_ChaChaPoly_SHA256"
+    );
+}
+
+pub fn errors() {
+    let cases = [
+        ("Nois_XK_25519_ChaChaPoly_SHA256", ProtocolNameError::Prefix),
+        ("Noise_XK_25519_ChaChaPoly", ProtocolNameError::Parts),
+        ("Noise_XK_25519__SHA256", ProtocolNameError::Parts),
+        (
+            "Noise_XK_25519_ChaChaPoly_SHA256_",
+            ProtocolNameError::Parts,
+        ),
+        (
+            "Noise_xk_25519_ChaChaPoly_SHA256",
+            ProtocolNameError::Pattern,
+        ),
+        (
+            "Noise_psk0_25519_ChaChaPoly_SHA256",
+            ProtocolNameError::Pattern,
+        ),
+        (
+            "Noise_XKpsk_25519_ChaChaPoly_SHA256",
+            ProtocolNameError::Modifier,
+        ),
+        (
+            "Noise_XKpsk1+_25519_ChaChaPoly_SHA256",
+            ProtocolNameError::Modifier,
+        ),
+        (
+            "Noise_XKfoo_25519_ChaChaPoly_SHA256",
+            ProtocolNameError::Modifier,
+        ),
+        (
+            "Noise_XXhfs_25519+_ChaChaPoly_SHA256",
+            ProtocolNameError::Parts,
+        ),
+        (
+            "Noise_XXhfs_25519_ChaChaPoly_SHA256",
+            ProtocolNameError::Kem,
+        ),
+        (
+            "Noise_XX_25519+Kyber1024_ChaChaPoly_SHA256",
+            ProtocolNameError::Kem,
+        ),
+    ];
+    for (name, error) in cases {
+        assert_eq!(ProtocolName::parse(name), Err(error), "{}", name);
+    }
+}
+
+pub fn checked<C>(name: &str)
+where
+    C: ConfigExt,
+    C::Dh: NoiseName,
+    C::Aead: NoiseName,
+    C::MixHash: NoiseName,
+{
+    let state = SymmetricState::<C, _>::new_checked(name).unwrap();
+    assert_eq!(state.hash(), SymmetricState::<C, _>::new(name).hash());
+    let parsed = ProtocolName::parse(name).unwrap();
+    let from_name = SymmetricState::<C, _>::from_protocol_name(&parsed);
+    assert_eq!(state.hash(), from_name.hash());
+
+    let cases = [
+        (
+            "Noise_XK_448_ChaChaPoly_SHA256",
+            ProtocolNameError::DhMismatch,
+        ),
+        (
+            "Noise_XK_25519_AESGCM_SHA256",
+            ProtocolNameError::CipherMismatch,
+        ),
+        (
+            "Noise_XK_25519_ChaChaPoly_BLAKE2s",
+            ProtocolNameError::HashMismatch,
+        ),
+    ];
+    for (name, error) in cases {
+        assert_eq!(
+            SymmetricState::<C, _>::new_checked(name).err(),
+            Some(error),
+            "{}",
+            name
+        );
+    }
+}
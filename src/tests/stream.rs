@@ -0,0 +1,48 @@
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+};
+
+use alloc::{vec, vec::Vec};
+
+use rand_core::OsRng;
+
+use crate::{patterns, ConfigExt, Dh, NoiseName, NoiseStream, HandshakeKeys, HandshakeState};
+
+pub fn f<C>()
+where
+    C: ConfigExt + 'static,
+    C::Dh: Dh + NoiseName,
+    C::Aead: NoiseName + Send,
+    C::MixHash: NoiseName,
+    HandshakeState<C>: Send,
+{
+    let new = |initiator| {
+        let keys = HandshakeKeys::default().local_static(C::Dh::generate(&mut OsRng));
+        HandshakeState::<C>::new(patterns::XX, initiator, b"prologue", keys, &mut OsRng).unwrap()
+    };
+    let initiator = new(true);
+    let responder = new(false);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let a = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (b, _) = listener.accept().unwrap();
+
+    let data = (0..200_000).map(|i| (i % 251) as u8).collect::<Vec<u8>>();
+    let expected = data.clone();
+    let responder = thread::spawn(move || {
+        let mut stream = NoiseStream::<C, _>::handshake(b, responder).unwrap();
+        let mut received = vec![0; expected.len()];
+        stream.read_exact(&mut received).unwrap();
+        assert_eq!(received, expected);
+        stream.write_all(b"bye").unwrap();
+        stream.hash().clone()
+    });
+
+    let mut stream = NoiseStream::<C, _>::handshake(a, initiator).unwrap();
+    stream.write_all(&data).unwrap();
+    let mut received = Vec::new();
+    stream.read_to_end(&mut received).unwrap();
+    assert_eq!(received, b"bye");
+    assert_eq!(*stream.hash(), responder.join().unwrap());
+}
@@ -2,7 +2,7 @@ use alloc::vec::Vec;
 
 use serde::{Serialize, Deserialize};
 
-const DATA: &'static str = include_str!("cacophony.json");
+const DATA: &str = include_str!("cacophony.json");
 
 #[derive(Serialize, Deserialize)]
 pub struct TestVector<'a> {
@@ -14,10 +14,10 @@ pub struct TestVector<'a> {
     #[serde(rename = "init_psks", default)]
     pub psks: Vec<&'a str>,
 
-    init_remote_static: Option<&'a str>,
+    pub init_remote_static: Option<&'a str>,
     pub init_static: Option<&'a str>,
     pub init_ephemeral: &'a str,
-    resp_remote_static: Option<&'a str>,
+    pub resp_remote_static: Option<&'a str>,
     pub resp_static: Option<&'a str>,
     pub resp_ephemeral: Option<&'a str>,
 
@@ -28,6 +28,14 @@ pub struct TestVector<'a> {
 }
 
 impl TestVector<'static> {
+    pub fn names() -> impl Iterator<Item = &'static str> {
+        let key = "\"protocol_name\":\"";
+        DATA.match_indices(key).map(move |(pos, _)| {
+            let name = &DATA[(pos + key.len())..];
+            &name[..name.find('"').unwrap()]
+        })
+    }
+
     pub fn try_load(name: &str) -> Option<Self> {
         let pos = DATA.find(name)? - 18;
         let mut de = serde_json::Deserializer::from_str(&DATA[pos..]);
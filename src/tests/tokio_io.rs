@@ -0,0 +1,53 @@
+use alloc::vec::Vec;
+
+use rand_core::OsRng;
+use tokio::io::{self, AsyncReadExt, AsyncWriteExt};
+
+use crate::{patterns, ConfigExt, Dh, NoiseName, TokioNoiseStream, HandshakeKeys, HandshakeState};
+
+pub fn f<C>()
+where
+    C: ConfigExt,
+    C::Dh: Dh + NoiseName,
+    C::Aead: NoiseName,
+    C::MixHash: NoiseName,
+{
+    let new = |initiator| {
+        let keys = HandshakeKeys::default().local_static(C::Dh::generate(&mut OsRng));
+        HandshakeState::<C>::new(patterns::XX, initiator, b"prologue", keys, &mut OsRng).unwrap()
+    };
+    let initiator = new(true);
+    let responder = new(false);
+    // the small buffer splits the frames and holds the writer back
+    let (a, b) = io::duplex(100);
+
+    let data = (0..200_000).map(|i| (i % 251) as u8).collect::<Vec<u8>>();
+    let initiator = async {
+        let mut stream = TokioNoiseStream::<C, _>::handshake(a, initiator)
+            .await
+            .unwrap();
+        stream.write_all(&data).await.unwrap();
+        stream.shutdown().await.unwrap();
+        let mut received = Vec::new();
+        stream.read_to_end(&mut received).await.unwrap();
+        assert_eq!(received, b"bye");
+        stream.hash().clone()
+    };
+    let responder = async {
+        let mut stream = TokioNoiseStream::<C, _>::handshake(b, responder)
+            .await
+            .unwrap();
+        let mut received = Vec::new();
+        stream.read_to_end(&mut received).await.unwrap();
+        assert_eq!(received, data);
+        stream.write_all(b"bye").await.unwrap();
+        stream.shutdown().await.unwrap();
+        stream.hash().clone()
+    };
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let (a, b) = runtime.block_on(async { tokio::join!(initiator, responder) });
+    assert_eq!(a, b);
+}
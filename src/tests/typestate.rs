@@ -0,0 +1,234 @@
+use alloc::vec::Vec;
+
+use rand_core::OsRng;
+
+use super::test_vector::{TestVector, Pair};
+use crate::{
+    typestate::{Initiator, Responder, Msg1, Xk, Ik, Xx},
+    ConfigExt, Dh, NoiseName, Output, HandshakeError,
+};
+
+fn secret<D>(hex: &str) -> D::Secret
+where
+    D: Dh,
+{
+    D::secret_from_bytes(&hex::decode(hex).unwrap()).unwrap()
+}
+
+fn public<D>(hex: &str) -> D::Public
+where
+    D: Dh,
+{
+    D::public_from_bytes(&hex::decode(hex).unwrap()).unwrap()
+}
+
+fn payload(pair: &Pair<'_>) -> Vec<u8> {
+    hex::decode(pair.payload).unwrap()
+}
+
+fn check(pair: &Pair<'_>, message: &[u8]) -> Vec<u8> {
+    assert_eq!(pair.ciphertext, hex::encode(message));
+    message.to_vec()
+}
+
+fn transport<C>(
+    v: &TestVector<'_>,
+    handshake_len: usize,
+    mut initiator: Output<C, 1>,
+    mut responder: Output<C, 1>,
+) where
+    C: ConfigExt,
+{
+    assert_eq!(v.handshake_hash, hex::encode(&initiator.hash));
+    assert_eq!(initiator.hash, responder.hash);
+
+    for (n, pair) in v.messages[handshake_len..].iter().enumerate() {
+        let (sender, receiver) = if (handshake_len + n) % 2 == 0 {
+            (&mut initiator.sender, &mut responder.receiver)
+        } else {
+            (&mut responder.sender, &mut initiator.receiver)
+        };
+        let payload = payload(pair);
+        let mut buffer = payload.clone();
+        let tag = sender.encrypt(&[], &mut buffer).unwrap();
+        assert_eq!(pair.ciphertext, hex::encode([&buffer[..], &tag].concat()));
+        receiver.decrypt(&[], &mut buffer, &tag).unwrap();
+        assert_eq!(payload, buffer);
+    }
+}
+
+pub fn xk<C>(v: &TestVector<'_>)
+where
+    C: ConfigExt,
+    C::Dh: Dh + NoiseName,
+    C::Aead: NoiseName,
+    C::MixHash: NoiseName,
+{
+    let prologue = hex::decode(v.prologue).unwrap();
+    let initiator = Xk::<C, Initiator, Msg1>::with_ephemeral(
+        &prologue,
+        secret::<C::Dh>(v.init_static.unwrap()),
+        secret::<C::Dh>(v.init_ephemeral),
+        public::<C::Dh>(v.init_remote_static.unwrap()),
+    );
+    let responder = Xk::<C, Responder, Msg1>::with_ephemeral(
+        &prologue,
+        secret::<C::Dh>(v.resp_static.unwrap()),
+        secret::<C::Dh>(v.resp_ephemeral.unwrap()),
+    );
+
+    let mut buffer = [0; 1024];
+    let (len, initiator) = initiator
+        .write_message(&payload(&v.messages[0]), &mut buffer)
+        .unwrap();
+    let mut message = check(&v.messages[0], &buffer[..len]);
+    let (responder, received) = responder.read_message(&mut message).unwrap();
+    assert_eq!(received, payload(&v.messages[0]));
+
+    let (len, responder) = responder
+        .write_message(&payload(&v.messages[1]), &mut buffer)
+        .unwrap();
+    let mut message = check(&v.messages[1], &buffer[..len]);
+    let (initiator, received) = initiator.read_message(&mut message).unwrap();
+    assert_eq!(received, payload(&v.messages[1]));
+
+    let (len, initiator) = initiator
+        .write_message(&payload(&v.messages[2]), &mut buffer)
+        .unwrap();
+    let mut message = check(&v.messages[2], &buffer[..len]);
+    let (responder, received) = responder.read_message(&mut message).unwrap();
+    assert_eq!(received, payload(&v.messages[2]));
+    assert_eq!(
+        responder.remote_static().unwrap().as_ref(),
+        C::Dh::public(&secret::<C::Dh>(v.init_static.unwrap())).as_ref()
+    );
+    assert_eq!(initiator.hash(), responder.hash());
+
+    transport(v, 3, initiator.finish(), responder.finish());
+}
+
+pub fn ik<C>(v: &TestVector<'_>)
+where
+    C: ConfigExt,
+    C::Dh: Dh + NoiseName,
+    C::Aead: NoiseName,
+    C::MixHash: NoiseName,
+{
+    let prologue = hex::decode(v.prologue).unwrap();
+    let initiator = Ik::<C, Initiator, Msg1>::with_ephemeral(
+        &prologue,
+        secret::<C::Dh>(v.init_static.unwrap()),
+        secret::<C::Dh>(v.init_ephemeral),
+        public::<C::Dh>(v.init_remote_static.unwrap()),
+    );
+    let responder = Ik::<C, Responder, Msg1>::with_ephemeral(
+        &prologue,
+        secret::<C::Dh>(v.resp_static.unwrap()),
+        secret::<C::Dh>(v.resp_ephemeral.unwrap()),
+    );
+
+    let mut buffer = [0; 1024];
+    let (len, initiator) = initiator
+        .write_message(&payload(&v.messages[0]), &mut buffer)
+        .unwrap();
+    let mut message = check(&v.messages[0], &buffer[..len]);
+    let (responder, received) = responder.read_message(&mut message).unwrap();
+    assert_eq!(received, payload(&v.messages[0]));
+    assert_eq!(
+        responder.remote_static().unwrap().as_ref(),
+        C::Dh::public(&secret::<C::Dh>(v.init_static.unwrap())).as_ref()
+    );
+
+    let (len, responder) = responder
+        .write_message(&payload(&v.messages[1]), &mut buffer)
+        .unwrap();
+    let mut message = check(&v.messages[1], &buffer[..len]);
+    let (initiator, received) = initiator.read_message(&mut message).unwrap();
+    assert_eq!(received, payload(&v.messages[1]));
+
+    transport(v, 2, initiator.finish(), responder.finish());
+}
+
+pub fn xx<C>(v: &TestVector<'_>)
+where
+    C: ConfigExt,
+    C::Dh: Dh + NoiseName,
+    C::Aead: NoiseName,
+    C::MixHash: NoiseName,
+{
+    let prologue = hex::decode(v.prologue).unwrap();
+    let initiator = Xx::<C, Initiator, Msg1>::with_ephemeral(
+        &prologue,
+        secret::<C::Dh>(v.init_static.unwrap()),
+        secret::<C::Dh>(v.init_ephemeral),
+    );
+    let responder = Xx::<C, Responder, Msg1>::with_ephemeral(
+        &prologue,
+        secret::<C::Dh>(v.resp_static.unwrap()),
+        secret::<C::Dh>(v.resp_ephemeral.unwrap()),
+    );
+
+    let mut buffer = [0; 1024];
+    let (len, initiator) = initiator
+        .write_message(&payload(&v.messages[0]), &mut buffer)
+        .unwrap();
+    let mut message = check(&v.messages[0], &buffer[..len]);
+    let (responder, received) = responder.read_message(&mut message).unwrap();
+    assert_eq!(received, payload(&v.messages[0]));
+
+    let (len, responder) = responder
+        .write_message(&payload(&v.messages[1]), &mut buffer)
+        .unwrap();
+    let mut message = check(&v.messages[1], &buffer[..len]);
+    let (initiator, received) = initiator.read_message(&mut message).unwrap();
+    assert_eq!(received, payload(&v.messages[1]));
+    let responder_public = C::Dh::public(&secret::<C::Dh>(v.resp_static.unwrap()));
+    assert_eq!(
+        initiator.remote_static().unwrap().as_ref(),
+        responder_public.as_ref()
+    );
+
+    let (len, initiator) = initiator
+        .write_message(&payload(&v.messages[2]), &mut buffer)
+        .unwrap();
+    let mut message = check(&v.messages[2], &buffer[..len]);
+    let (responder, received) = responder.read_message(&mut message).unwrap();
+    assert_eq!(received, payload(&v.messages[2]));
+
+    transport(v, 3, initiator.finish(), responder.finish());
+}
+
+pub fn errors<C>(v: &TestVector<'_>)
+where
+    C: ConfigExt,
+    C::Dh: Dh + NoiseName,
+    C::Aead: NoiseName,
+    C::MixHash: NoiseName,
+{
+    let s = || secret::<C::Dh>(v.init_static.unwrap());
+    let rs = || public::<C::Dh>(v.init_remote_static.unwrap());
+    let responder =
+        || Xk::<C, Responder, Msg1>::new(b"", secret::<C::Dh>(v.resp_static.unwrap()), &mut OsRng);
+    let mut buffer = [0; 1024];
+
+    let initiator = Xk::<C, Initiator, Msg1>::new(b"", s(), rs(), &mut OsRng);
+    assert_eq!(
+        initiator.write_message(&[], &mut buffer[..16]).err(),
+        Some(HandshakeError::BufferTooSmall)
+    );
+
+    let initiator = Xk::<C, Initiator, Msg1>::new(b"", s(), rs(), &mut OsRng);
+    let (len, _) = initiator.write_message(b"payload", &mut buffer).unwrap();
+    assert_eq!(
+        responder().read_message(&mut buffer[..16]).err(),
+        Some(HandshakeError::MessageTooShort)
+    );
+    // another prologue
+    let initiator = Xk::<C, Initiator, Msg1>::new(b"other", s(), rs(), &mut OsRng);
+    let (len_other, _) = initiator.write_message(b"payload", &mut buffer).unwrap();
+    assert_eq!(len, len_other);
+    assert_eq!(
+        responder().read_message(&mut buffer[..len]).err(),
+        Some(HandshakeError::MacMismatch)
+    );
+}
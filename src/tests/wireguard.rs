@@ -0,0 +1,266 @@
+use rand_core::OsRng;
+use zeroize::Zeroizing;
+
+use crate::{
+    patterns, Dh, HandshakeKeys, HandshakeState, Tai64N, WireGuardConfig, WireGuardCookieChecker,
+    WireGuardCookieGenerator, WireGuardError, WireGuardInitiator, WireGuardResponder,
+    WireGuardSession, X25519, X25519Secret, WIREGUARD_IDENTIFIER,
+};
+
+// recorded with an independent implementation of the section 5.4 of the WireGuard whitepaper
+const INITIATION: &str = "0100000044332211ce8d3ad1ccb633ec7b70c17814a5c76ecd029685050d344745ba05870e587d59e071c69040941f890172e72e6662f05122ee15ef25b328c29cd11010e779aa94be3a4c127d2b7cb9f6534df390c0672bfa7aad9b6f56b724226e40344408b0ffa3d37f55dd8c5bda6b171fd7c186eadf95928434ba3e210cf0a484a900000000000000000000000000000000";
+const RESPONSE: &str = "020000008877665544332211ac01b2209e86354fb853237b5de0f4fab13c7fcbf433a61c019369617fecf10b523e133175e25eb16597aa6805c072a6e54c12368de2227421a5e370208958f000000000000000000000000000000000";
+const HASH: &str = "cb65783a70dbfab966a62e9d2fd213fd61839711044a8ad4e3252104886bbcc4";
+// "ping" sent by the initiator and "pong" sent by the responder, the counter is zero
+const PING: &str = "2410219f3c9ddf0f19d328715cca9ac5fbc562b0";
+const PONG: &str = "daf33ea0a3defcdf9a3abeddc7cfd07f909f4f68";
+// the cookie reply to the initiation and the initiation sealed again with the cookie
+const COOKIE_REPLY: &str = "0300000044332211070707070707070707070707070707070707070707070707714aa4f20d229265bd021e3da0473e68957d90489ce08575fdf5e7da581daae4";
+const SEALED: &str = "0100000044332211ce8d3ad1ccb633ec7b70c17814a5c76ecd029685050d344745ba05870e587d59e071c69040941f890172e72e6662f05122ee15ef25b328c29cd11010e779aa94be3a4c127d2b7cb9f6534df390c0672bfa7aad9b6f56b724226e40344408b0ffa3d37f55dd8c5bda6b171fd7c186eadf95928434ba3e210cf0a484a985cbf10d0f331eca79b2d7e8ea10de69";
+
+const PSK: [u8; 32] = [5; 32];
+const INITIATOR_INDEX: u32 = 0x1122_3344;
+const RESPONDER_INDEX: u32 = 0x5566_7788;
+const COOKIE_SECRET: [u8; 32] = [6; 32];
+const SOURCE: [u8; 6] = [192, 168, 0, 1, 0xca, 0x6c];
+
+fn secret(byte: u8) -> X25519Secret {
+    X25519::secret_from_bytes(&[byte; 32]).unwrap()
+}
+
+fn timestamp() -> Tai64N {
+    Tai64N::from_unix(1_700_000_000, 123_456_789)
+}
+
+fn check_transport(initiator: &mut WireGuardSession, responder: &mut WireGuardSession) {
+    let mut buffer = *b"ping";
+    let tag = initiator
+        .transport
+        .sender
+        .encrypt(&[], &mut buffer)
+        .unwrap();
+    assert_eq!(hex::encode([&buffer[..], &tag].concat()), PING);
+    responder
+        .transport
+        .receiver
+        .decrypt(&[], &mut buffer, &tag)
+        .unwrap();
+    assert_eq!(&buffer, b"ping");
+
+    let mut buffer = *b"pong";
+    let tag = responder
+        .transport
+        .sender
+        .encrypt(&[], &mut buffer)
+        .unwrap();
+    assert_eq!(hex::encode([&buffer[..], &tag].concat()), PONG);
+}
+
+pub fn vectors() {
+    let initiator_public = X25519::public(&secret(1));
+    let responder_public = X25519::public(&secret(3));
+    let mut generator = WireGuardCookieGenerator::new(&responder_public);
+    let checker = WireGuardCookieChecker::new(&responder_public);
+
+    let (initiator, mut initiation) = WireGuardInitiator::new(
+        &secret(1),
+        secret(2),
+        &responder_public,
+        &PSK,
+        INITIATOR_INDEX,
+        &timestamp(),
+    )
+    .unwrap();
+    generator.add_macs(&mut initiation).unwrap();
+    assert_eq!(hex::encode(initiation), INITIATION);
+    checker.check_mac1(&initiation).unwrap();
+
+    let (responder, received) =
+        WireGuardResponder::read_initiation(&secret(3), &initiation).unwrap();
+    assert_eq!(received.remote_index, INITIATOR_INDEX);
+    assert_eq!(received.remote_static, initiator_public);
+    assert_eq!(received.timestamp, timestamp());
+
+    let (mut response, mut responder) = responder
+        .write_response(&secret(4), &PSK, RESPONDER_INDEX)
+        .unwrap();
+    WireGuardCookieGenerator::new(&initiator_public)
+        .add_macs(&mut response)
+        .unwrap();
+    assert_eq!(hex::encode(response), RESPONSE);
+    WireGuardCookieChecker::new(&initiator_public)
+        .check_mac1(&response)
+        .unwrap();
+
+    let mut initiator = initiator.read_response(&response).unwrap();
+    assert_eq!(initiator.remote_index, RESPONDER_INDEX);
+    assert_eq!(responder.remote_index, INITIATOR_INDEX);
+    assert_eq!(hex::encode(initiator.transport.hash), HASH);
+    assert_eq!(initiator.transport.hash, responder.transport.hash);
+    check_transport(&mut initiator, &mut responder);
+
+    // under load
+    assert_eq!(
+        checker.check_mac2(&initiation, &COOKIE_SECRET, &SOURCE),
+        Err(WireGuardError::InvalidMac2)
+    );
+    let reply = checker
+        .cookie_reply_with_nonce(&initiation, &COOKIE_SECRET, &SOURCE, &[7; 24])
+        .unwrap();
+    assert_eq!(hex::encode(reply), COOKIE_REPLY);
+    generator.consume_cookie_reply(&reply).unwrap();
+    generator.add_macs(&mut initiation).unwrap();
+    assert_eq!(hex::encode(initiation), SEALED);
+    checker.check_mac1(&initiation).unwrap();
+    checker
+        .check_mac2(&initiation, &COOKIE_SECRET, &SOURCE)
+        .unwrap();
+    assert_eq!(
+        checker.check_mac2(&initiation, &COOKIE_SECRET, &[10, 0, 0, 1, 0xca, 0x6c]),
+        Err(WireGuardError::InvalidMac2)
+    );
+}
+
+/// The WireGuard handshake is the generic IKpsk2 with the identifier as the prologue.
+pub fn handshake_state() {
+    let keys = HandshakeKeys::default()
+        .local_static(secret(1))
+        .local_ephemeral(secret(2))
+        .remote_static(X25519::public(&secret(3)))
+        .psk(Zeroizing::new(PSK))
+        .unwrap();
+    let mut initiator = HandshakeState::<WireGuardConfig>::new(
+        patterns::IK_PSK2,
+        true,
+        WIREGUARD_IDENTIFIER,
+        keys,
+        &mut OsRng,
+    )
+    .unwrap();
+    let keys = HandshakeKeys::default()
+        .local_static(secret(3))
+        .local_ephemeral(secret(4))
+        .psk(Zeroizing::new(PSK))
+        .unwrap();
+    let mut responder = HandshakeState::<WireGuardConfig>::new(
+        patterns::IK_PSK2,
+        false,
+        WIREGUARD_IDENTIFIER,
+        keys,
+        &mut OsRng,
+    )
+    .unwrap();
+
+    let mut buffer = [0; 128];
+    let len = initiator
+        .write_message(timestamp().as_bytes(), &mut buffer)
+        .unwrap();
+    assert_eq!(hex::encode(&buffer[..len]), INITIATION[16..(16 + len * 2)]);
+    responder.read_message(&mut buffer[..len]).unwrap();
+    let len = responder.write_message(&[], &mut buffer).unwrap();
+    assert_eq!(hex::encode(&buffer[..len]), RESPONSE[24..(24 + len * 2)]);
+    initiator.read_message(&mut buffer[..len]).unwrap();
+
+    let initiator = initiator.finish::<1>().unwrap();
+    assert_eq!(hex::encode(initiator.hash), HASH);
+}
+
+pub fn errors() {
+    let responder_public = X25519::public(&secret(3));
+    let checker = WireGuardCookieChecker::new(&responder_public);
+    let mut generator = WireGuardCookieGenerator::new(&responder_public);
+    let initiation: [u8; 148] = hex::decode(INITIATION).unwrap().try_into().unwrap();
+    let reply: [u8; 64] = hex::decode(COOKIE_REPLY).unwrap().try_into().unwrap();
+
+    // no initiation is sent yet
+    assert_eq!(
+        generator.consume_cookie_reply(&reply),
+        Err(WireGuardError::InvalidMessage)
+    );
+
+    let mut message = initiation;
+    message[1] = 1;
+    assert_eq!(
+        WireGuardResponder::read_initiation(&secret(3), &message).err(),
+        Some(WireGuardError::InvalidMessage)
+    );
+    let mut message = initiation;
+    message[100] ^= 1;
+    assert_eq!(
+        checker.check_mac1(&message),
+        Err(WireGuardError::InvalidMac1)
+    );
+    assert_eq!(
+        WireGuardResponder::read_initiation(&secret(3), &message).err(),
+        Some(WireGuardError::MacMismatch)
+    );
+    // wrong responder
+    assert_eq!(
+        WireGuardResponder::read_initiation(&secret(4), &initiation).err(),
+        Some(WireGuardError::MacMismatch)
+    );
+
+    // zero shared secret
+    assert_eq!(
+        WireGuardInitiator::new(&secret(1), secret(2), &[0; 32], &PSK, 0, &timestamp()).err(),
+        Some(WireGuardError::InvalidPublicKey)
+    );
+
+    // wrong psk, wrong receiver index
+    let respond = |psk: &[u8; 32]| {
+        let (initiator, initiation) = WireGuardInitiator::new(
+            &secret(1),
+            secret(2),
+            &responder_public,
+            &PSK,
+            1,
+            &timestamp(),
+        )
+        .unwrap();
+        let (responder, _) = WireGuardResponder::read_initiation(&secret(3), &initiation).unwrap();
+        let (response, _) = responder.write_response(&secret(4), psk, 2).unwrap();
+        (initiator, response)
+    };
+    let (initiator, response) = respond(&[0; 32]);
+    assert_eq!(
+        initiator.read_response(&response).err(),
+        Some(WireGuardError::MacMismatch)
+    );
+    let (initiator, mut response) = respond(&PSK);
+    response[8] ^= 1;
+    assert_eq!(
+        initiator.read_response(&response).err(),
+        Some(WireGuardError::UnknownIndex)
+    );
+
+    assert_eq!(
+        generator.add_macs(&mut [0; 31]),
+        Err(WireGuardError::InvalidMessage)
+    );
+    // the cookie reply is bound to mac1 of the last message
+    generator.add_macs(&mut [0; 148]).unwrap();
+    assert_eq!(
+        generator.consume_cookie_reply(&reply),
+        Err(WireGuardError::MacMismatch)
+    );
+
+    let now = Tai64N::now().unwrap();
+    assert!(timestamp() < now && now <= Tai64N::now().unwrap());
+    let reply = checker
+        .cookie_reply(&initiation, &COOKIE_SECRET, &SOURCE, &mut OsRng)
+        .unwrap();
+    let mut generator = WireGuardCookieGenerator::new(&responder_public);
+    let mut message = initiation;
+    generator.add_macs(&mut message).unwrap();
+    generator.consume_cookie_reply(&reply).unwrap();
+    generator.add_macs(&mut message).unwrap();
+    checker
+        .check_mac2(&message, &COOKIE_SECRET, &SOURCE)
+        .unwrap();
+    generator.clear_cookie();
+    generator.add_macs(&mut message).unwrap();
+    assert_eq!(
+        checker.check_mac2(&message, &COOKIE_SECRET, &SOURCE),
+        Err(WireGuardError::InvalidMac2)
+    );
+}
@@ -1,35 +1,31 @@
-use core::ops::Mul;
-
 use alloc::vec::Vec;
 
-use super::{test_vector::TestVector, elliptic::EllipticSecret};
-use crate::{SymmetricState, ConfigExt, Output};
+use super::test_vector::TestVector;
+use crate::{SymmetricState, ConfigExt, Output, Dh};
 
-pub fn f<E, S, C>(v: &TestVector<'_>)
+pub fn f<C>(v: &TestVector<'_>)
 where
     C: ConfigExt,
-    E: EllipticSecret,
-    E::Public: AsRef<S>,
-    S: AsRef<[u8]> + zeroize::Zeroize + Copy,
-    for<'a, 'b> &'b E::Public: Mul<&'a E, Output = E::Public>,
+    C::Dh: Dh,
 {
-    fn pair<E>(hex: &str) -> (E, E::Public)
+    fn pair<D>(hex: &str) -> (D::Secret, D::Public)
     where
-        E: EllipticSecret,
+        D: Dh,
     {
-        let secret = E::from_bytes(&hex::decode(hex).unwrap());
-        let public = secret.public();
+        let secret = D::secret_from_bytes(&hex::decode(hex).unwrap()).unwrap();
+        let public = D::public(&secret);
         (secret, public)
     }
+    let dh = |secret, public| C::Dh::dh(secret, public).unwrap();
 
-    let init_ephemeral = pair::<E>(v.init_ephemeral);
-    let resp_ephemeral = pair::<E>(v.resp_ephemeral.unwrap());
-    let init_static = pair::<E>(v.init_static.unwrap());
-    let resp_static = pair::<E>(v.resp_static.unwrap());
+    let init_ephemeral = pair::<C::Dh>(v.init_ephemeral);
+    let resp_ephemeral = pair::<C::Dh>(v.resp_ephemeral.unwrap());
+    let init_static = pair::<C::Dh>(v.init_static.unwrap());
+    let resp_static = pair::<C::Dh>(v.resp_static.unwrap());
 
     let mut payload0 = hex::decode(v.messages[0].payload).unwrap();
     let mut payload1 = hex::decode(v.messages[1].payload).unwrap();
-    let mut init_static_compressed = init_static.1.as_ref().as_ref().to_vec();
+    let mut init_static_compressed = init_static.1.as_ref().to_vec();
     let mut payload2 = hex::decode(v.messages[2].payload).unwrap();
 
     let Output {
@@ -39,29 +35,29 @@ where
     } = SymmetricState::<C, _>::new(v.name)
         .mix_hash(hex::decode(v.prologue).unwrap().as_slice())
         // <- s
-        .mix_hash(resp_static.1.as_ref().as_ref())
+        .mix_hash(resp_static.1.as_ref())
         // -> e, es
-        .mix_hash(init_ephemeral.1.as_ref().as_ref())
-        .mix_shared_secret(*(&resp_static.1 * &init_ephemeral.0).as_ref())
+        .mix_hash(init_ephemeral.1.as_ref())
+        .mix_shared_secret(dh(&init_ephemeral.0, &resp_static.1))
         .encrypt_ext(&mut payload0)
         // <- e, ee
-        .mix_hash(resp_ephemeral.1.as_ref().as_ref())
-        .mix_shared_secret(*(&resp_ephemeral.1 * &init_ephemeral.0).as_ref())
-        .encrypt_ext(&mut payload1.as_mut())
+        .mix_hash(resp_ephemeral.1.as_ref())
+        .mix_shared_secret(dh(&init_ephemeral.0, &resp_ephemeral.1))
+        .encrypt_ext(&mut payload1)
         // -> s, se
         .encrypt_ext(&mut init_static_compressed)
-        .mix_shared_secret(*(&resp_ephemeral.1 * &init_static.0).as_ref())
+        .mix_shared_secret(dh(&init_static.0, &resp_ephemeral.1))
         .encrypt_ext(&mut payload2)
         .finish::<1, true>();
 
     let mut ct = Vec::new();
-    ct.extend_from_slice(init_ephemeral.1.as_ref().as_ref());
+    ct.extend_from_slice(init_ephemeral.1.as_ref());
     ct.extend_from_slice(payload0.as_ref());
     let ct = hex::encode(ct);
     assert_eq!(v.messages[0].ciphertext, ct);
 
     let mut ct = Vec::new();
-    ct.extend_from_slice(resp_ephemeral.1.as_ref().as_ref());
+    ct.extend_from_slice(resp_ephemeral.1.as_ref());
     ct.extend_from_slice(payload1.as_ref());
     let ct = hex::encode(ct);
     assert_eq!(v.messages[1].ciphertext, ct);
@@ -78,7 +74,7 @@ where
         .iter()
         .fold((sender, receiver), |(mut sender, receiver), pair| {
             let mut buffer = hex::decode(pair.payload).unwrap();
-            let tag = sender.encrypt(&[], buffer.as_mut());
+            let tag = sender.encrypt(&[], buffer.as_mut()).unwrap();
             buffer.extend_from_slice(tag.as_ref());
             assert_eq!(pair.ciphertext, hex::encode(buffer));
             (receiver.swap(), sender.swap())
@@ -0,0 +1,132 @@
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::io;
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use super::{
+    config::ConfigExt,
+    dh::Dh,
+    handshake_state::HandshakeState,
+    symmetric_state::Hash,
+    async_transport::{PollIo, Transport},
+};
+
+struct Io<T>(T);
+
+impl<T> PollIo for Io<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_read(&mut self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let mut buf = ReadBuf::new(buf);
+        Pin::new(&mut self.0)
+            .poll_read(cx, &mut buf)
+            .map_ok(|()| buf.filled().len())
+    }
+
+    fn poll_write(&mut self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_close(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
+
+/// The stream for tokio, the handshake and transport messages are length-prefixed.
+pub struct TokioNoiseStream<C, T, const STEP: u64 = 1>
+where
+    C: ConfigExt,
+{
+    io: Io<T>,
+    transport: Transport<C, STEP>,
+}
+
+impl<C, T, const STEP: u64> TokioNoiseStream<C, T, STEP>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Runs the handshake over `inner`, the handshake payloads are empty.
+    ///
+    /// # Errors
+    /// io error or the handshake failed
+    pub async fn handshake(inner: T, handshake: HandshakeState<C>) -> io::Result<Self> {
+        let mut io = Io(inner);
+        let transport = Transport::handshake(&mut io, handshake).await?;
+        Ok(TokioNoiseStream { io, transport })
+    }
+}
+
+impl<C, T, const STEP: u64> TokioNoiseStream<C, T, STEP>
+where
+    C: ConfigExt,
+{
+    /// The handshake hash, the channel binding value.
+    pub fn hash(&self) -> &Hash<C> {
+        self.transport.hash()
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.io.0
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.io.0
+    }
+
+    pub fn into_inner(self) -> T {
+        self.io.0
+    }
+}
+
+impl<C, T, const STEP: u64> AsyncRead for TokioNoiseStream<C, T, STEP>
+where
+    C: ConfigExt,
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let unfilled = buf.initialize_unfilled();
+        this.transport
+            .poll_read(&mut this.io, cx, unfilled)
+            .map_ok(|len| buf.advance(len))
+    }
+}
+
+impl<C, T, const STEP: u64> AsyncWrite for TokioNoiseStream<C, T, STEP>
+where
+    C: ConfigExt,
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        this.transport.poll_write(&mut this.io, cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        this.transport.poll_flush(&mut this.io, cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        this.transport.poll_close(&mut this.io, cx)
+    }
+}
@@ -0,0 +1,1024 @@
+//! Handshakes of the fixed patterns encoded in types. Each step exposes only the operation
+//! legal at this point of the pattern, so the wrong token order does not compile.
+//! The symmetric state keeps counting nonces at the type level.
+//!
+//! ```
+//! use rand_core::{CryptoRng, RngCore};
+//! use vru_noise::{
+//!     typestate::{Initiator, Msg1, Msg2, Xk},
+//!     ConfigExt, Dh, HandshakeError, NoiseName,
+//! };
+//!
+//! fn start<C, R>(
+//!     s: <C::Dh as Dh>::Secret,
+//!     rs: <C::Dh as Dh>::Public,
+//!     rng: &mut R,
+//!     message: &mut [u8],
+//! ) -> Result<(usize, Xk<C, Initiator, Msg2>), HandshakeError>
+//! where
+//!     C: ConfigExt,
+//!     C::Dh: Dh + NoiseName,
+//!     C::Aead: NoiseName,
+//!     C::MixHash: NoiseName,
+//!     R: RngCore + CryptoRng,
+//! {
+//!     Xk::<C, Initiator, Msg1>::new(b"prologue", s, rs, rng).write_message(b"hello", message)
+//! }
+//! ```
+//!
+//! The initiator of `XK` writes the first message, it cannot read it:
+//!
+//! ```compile_fail
+//! use vru_noise::{
+//!     typestate::{Initiator, Msg1, Msg2, Xk},
+//!     ConfigExt, Dh, HandshakeError,
+//! };
+//!
+//! fn start<C>(
+//!     initiator: Xk<C, Initiator, Msg1>,
+//!     message: &mut [u8],
+//! ) -> Result<(Xk<C, Initiator, Msg2>, &mut [u8]), HandshakeError>
+//! where
+//!     C: ConfigExt,
+//!     C::Dh: Dh,
+//! {
+//!     initiator.read_message(message)
+//! }
+//! ```
+//!
+//! The initiator of `XK` cannot start without the responder's static key:
+//!
+//! ```compile_fail
+//! use rand_core::{CryptoRng, RngCore};
+//! use vru_noise::{
+//!     typestate::{Initiator, Msg1, Xk},
+//!     ConfigExt, Dh, NoiseName,
+//! };
+//!
+//! fn start<C, R>(s: <C::Dh as Dh>::Secret, rng: &mut R) -> Xk<C, Initiator, Msg1>
+//! where
+//!     C: ConfigExt,
+//!     C::Dh: Dh + NoiseName,
+//!     C::Aead: NoiseName,
+//!     C::MixHash: NoiseName,
+//!     R: RngCore + CryptoRng,
+//! {
+//!     Xk::<C, Initiator, Msg1>::new(b"prologue", s, rng)
+//! }
+//! ```
+
+use core::{marker::PhantomData, ops::Add};
+
+use {
+    aead::AeadCore,
+    generic_array::{
+        GenericArray,
+        typenum::{Unsigned, U1},
+    },
+    rand_core::{RngCore, CryptoRng},
+};
+
+use super::{
+    config::{Config, ConfigExt},
+    dh::Dh,
+    handshake_state::{HandshakeError, MAX_MESSAGE_LEN},
+    pattern::HandshakePattern,
+    patterns,
+    protocol_name::{NoiseName, ProtocolName},
+    symmetric_state::{ChainingKey, Hash, Key, Output, SymmetricState},
+};
+
+pub struct Initiator;
+
+pub struct Responder;
+
+pub trait Role {
+    const INITIATOR: bool;
+}
+
+impl Role for Initiator {
+    const INITIATOR: bool = true;
+}
+
+impl Role for Responder {
+    const INITIATOR: bool = false;
+}
+
+/// Before the first handshake message.
+pub struct Msg1;
+
+pub struct Msg2;
+
+pub struct Msg3;
+
+/// The handshake is finished.
+pub struct Done;
+
+/// The key of the symmetric state before the step `S` of the pattern.
+pub trait Step<C, S>
+where
+    C: Config,
+{
+    type Key;
+}
+
+/// `XK`, the initiator knows the responder's static key, three messages.
+pub enum XkPattern {}
+
+/// `IK`, the initiator sends its static key in the first message, two messages.
+pub enum IkPattern {}
+
+/// `XX`, both parties send their static keys, three messages.
+pub enum XxPattern {}
+
+impl<C> Step<C, Msg1> for XkPattern
+where
+    C: Config,
+{
+    type Key = ChainingKey<C>;
+}
+
+impl<C> Step<C, Msg2> for XkPattern
+where
+    C: Config,
+{
+    type Key = Key<C, U1>;
+}
+
+impl<C> Step<C, Msg3> for XkPattern
+where
+    C: Config,
+{
+    type Key = Key<C, U1>;
+}
+
+impl<C> Step<C, Msg1> for IkPattern
+where
+    C: Config,
+{
+    type Key = ChainingKey<C>;
+}
+
+impl<C> Step<C, Msg2> for IkPattern
+where
+    C: Config,
+{
+    type Key = Key<C, U1>;
+}
+
+impl<C> Step<C, Msg1> for XxPattern
+where
+    C: Config,
+{
+    type Key = ChainingKey<C>;
+}
+
+impl<C> Step<C, Msg2> for XxPattern
+where
+    C: Config,
+{
+    type Key = ChainingKey<C>;
+}
+
+impl<C> Step<C, Msg3> for XxPattern
+where
+    C: Config,
+{
+    type Key = Key<C, U1>;
+}
+
+impl<C, P> Step<C, Done> for P
+where
+    C: Config,
+    P: Pattern,
+{
+    type Key = Key<C, U1>;
+}
+
+/// The pattern of the typed handshake.
+pub trait Pattern {
+    const PATTERN: HandshakePattern;
+}
+
+impl Pattern for XkPattern {
+    const PATTERN: HandshakePattern = patterns::XK;
+}
+
+impl Pattern for IkPattern {
+    const PATTERN: HandshakePattern = patterns::IK;
+}
+
+impl Pattern for XxPattern {
+    const PATTERN: HandshakePattern = patterns::XX;
+}
+
+pub type Xk<C, R, S> = Handshake<C, XkPattern, R, S>;
+
+pub type Ik<C, R, S> = Handshake<C, IkPattern, R, S>;
+
+pub type Xx<C, R, S> = Handshake<C, XxPattern, R, S>;
+
+type Secret<C> = <<C as Config>::Dh as Dh>::Secret;
+type Public<C> = <<C as Config>::Dh as Dh>::Public;
+type SharedSecret<C> = <<C as Config>::Dh as Dh>::SharedSecret;
+type Next<N> = <N as Add<U1>>::Output;
+
+struct Keys<C>
+where
+    C: Config,
+    C::Dh: Dh,
+{
+    s: Secret<C>,
+    e: Secret<C>,
+    rs: Option<Public<C>>,
+    re: Option<Public<C>>,
+}
+
+impl<C> Keys<C>
+where
+    C: Config,
+    C::Dh: Dh,
+{
+    fn dh(
+        &self,
+        local_static: bool,
+        remote_static: bool,
+    ) -> Result<SharedSecret<C>, HandshakeError> {
+        let local = if local_static { &self.s } else { &self.e };
+        let remote = if remote_static {
+            self.rs
+                .as_ref()
+                .ok_or(HandshakeError::MissingRemoteStatic)?
+        } else {
+            self.re
+                .as_ref()
+                .ok_or(HandshakeError::MissingRemoteEphemeral)?
+        };
+        C::Dh::dh(local, remote).ok_or(HandshakeError::InvalidPublicKey)
+    }
+}
+
+/// The handshake of the pattern `P` run by `R` before the step `S`.
+pub struct Handshake<C, P, R, S>
+where
+    C: Config,
+    C::Dh: Dh,
+    P: Step<C, S>,
+{
+    state: SymmetricState<C, P::Key>,
+    keys: Keys<C>,
+    marker: PhantomData<(P, R, S)>,
+}
+
+impl<C, P, R, S> Handshake<C, P, R, S>
+where
+    C: Config,
+    C::Dh: Dh,
+    P: Step<C, S>,
+{
+    const fn from_parts(state: SymmetricState<C, P::Key>, keys: Keys<C>) -> Self {
+        Handshake {
+            state,
+            keys,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn hash(&self) -> Hash<C> {
+        self.state.hash()
+    }
+
+    pub const fn remote_static(&self) -> Option<&Public<C>> {
+        self.keys.rs.as_ref()
+    }
+}
+
+impl<C, P, R> Handshake<C, P, R, Done>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+    P: Pattern,
+    R: Role,
+{
+    pub fn finish<const STEP: u64>(self) -> Output<C, STEP> {
+        if R::INITIATOR {
+            self.state.finish::<STEP, false>()
+        } else {
+            self.state.finish::<STEP, true>()
+        }
+    }
+}
+
+// the position in the message being written or read
+struct Cursor<'a> {
+    message: &'a mut [u8],
+    pos: usize,
+    error: HandshakeError,
+}
+
+impl<'a> Cursor<'a> {
+    fn write(message: &'a mut [u8]) -> Self {
+        Cursor {
+            message,
+            pos: 0,
+            error: HandshakeError::BufferTooSmall,
+        }
+    }
+
+    fn read(message: &'a mut [u8]) -> Result<Self, HandshakeError> {
+        if message.len() > MAX_MESSAGE_LEN {
+            return Err(HandshakeError::MessageTooLong);
+        }
+        Ok(Cursor {
+            message,
+            pos: 0,
+            error: HandshakeError::MessageTooShort,
+        })
+    }
+
+    fn next(&mut self, len: usize) -> Result<&mut [u8], HandshakeError> {
+        let bytes = self
+            .message
+            .get_mut(self.pos..(self.pos + len))
+            .ok_or(self.error)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn rest(self) -> &'a mut [u8] {
+        &mut self.message[self.pos..]
+    }
+}
+
+fn tag_len<C>() -> usize
+where
+    C: Config,
+{
+    <<C::Aead as AeadCore>::TagSize as Unsigned>::USIZE
+}
+
+fn write_e<C, K>(
+    state: SymmetricState<C, K>,
+    keys: &Keys<C>,
+    cursor: &mut Cursor<'_>,
+) -> Result<SymmetricState<C, K>, HandshakeError>
+where
+    C: Config,
+    C::Dh: Dh,
+{
+    let e = C::Dh::public(&keys.e);
+    cursor.next(C::Dh::DHLEN)?.clone_from_slice(e.as_ref());
+    Ok(state.mix_hash(e.as_ref()))
+}
+
+fn read_e<C, K>(
+    state: SymmetricState<C, K>,
+    keys: &mut Keys<C>,
+    cursor: &mut Cursor<'_>,
+) -> Result<SymmetricState<C, K>, HandshakeError>
+where
+    C: Config,
+    C::Dh: Dh,
+{
+    let bytes = cursor.next(C::Dh::DHLEN)?;
+    let re = C::Dh::public_from_bytes(bytes).ok_or(HandshakeError::InvalidPublicKey)?;
+    keys.re = Some(re);
+    Ok(state.mix_hash(bytes))
+}
+
+fn write_encrypted<C, N>(
+    state: SymmetricState<C, Key<C, N>>,
+    data: &[u8],
+    cursor: &mut Cursor<'_>,
+) -> Result<SymmetricState<C, Key<C, Next<N>>>, HandshakeError>
+where
+    C: ConfigExt,
+    N: Unsigned + Add<U1>,
+    Next<N>: Unsigned,
+{
+    let bytes = cursor.next(data.len() + tag_len::<C>())?;
+    let (bytes, tag_space) = bytes.split_at_mut(data.len());
+    bytes.clone_from_slice(data);
+    let (state, tag) = state.encrypt(bytes);
+    tag_space.clone_from_slice(&tag);
+    Ok(state)
+}
+
+fn read_encrypted<C, N>(
+    state: SymmetricState<C, Key<C, N>>,
+    bytes: &mut [u8],
+) -> Result<(SymmetricState<C, Key<C, Next<N>>>, &mut [u8]), HandshakeError>
+where
+    C: ConfigExt,
+    N: Unsigned + Add<U1>,
+    Next<N>: Unsigned,
+{
+    let len = bytes
+        .len()
+        .checked_sub(tag_len::<C>())
+        .ok_or(HandshakeError::MessageTooShort)?;
+    let (data, tag) = bytes.split_at_mut(len);
+    let state = state.decrypt(data, GenericArray::from_slice(tag))?;
+    Ok((state, data))
+}
+
+fn write_s<C, N>(
+    state: SymmetricState<C, Key<C, N>>,
+    keys: &Keys<C>,
+    cursor: &mut Cursor<'_>,
+) -> Result<SymmetricState<C, Key<C, Next<N>>>, HandshakeError>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+    N: Unsigned + Add<U1>,
+    Next<N>: Unsigned,
+{
+    write_encrypted(state, C::Dh::public(&keys.s).as_ref(), cursor)
+}
+
+fn read_s<C, N>(
+    state: SymmetricState<C, Key<C, N>>,
+    keys: &mut Keys<C>,
+    cursor: &mut Cursor<'_>,
+) -> Result<SymmetricState<C, Key<C, Next<N>>>, HandshakeError>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+    N: Unsigned + Add<U1>,
+    Next<N>: Unsigned,
+{
+    let bytes = cursor.next(C::Dh::DHLEN + tag_len::<C>())?;
+    let (state, bytes) = read_encrypted(state, bytes)?;
+    let rs = C::Dh::public_from_bytes(bytes).ok_or(HandshakeError::InvalidPublicKey)?;
+    keys.rs = Some(rs);
+    Ok(state)
+}
+
+fn write_payload<C, N>(
+    state: SymmetricState<C, Key<C, N>>,
+    payload: &[u8],
+    mut cursor: Cursor<'_>,
+) -> Result<(usize, SymmetricState<C, Key<C, Next<N>>>), HandshakeError>
+where
+    C: ConfigExt,
+    N: Unsigned + Add<U1>,
+    Next<N>: Unsigned,
+{
+    let state = write_encrypted(state, payload, &mut cursor)?;
+    if cursor.pos > MAX_MESSAGE_LEN {
+        return Err(HandshakeError::MessageTooLong);
+    }
+    Ok((cursor.pos, state))
+}
+
+fn read_payload<'a, C, N>(
+    state: SymmetricState<C, Key<C, N>>,
+    cursor: Cursor<'a>,
+) -> Result<(SymmetricState<C, Key<C, Next<N>>>, &'a mut [u8]), HandshakeError>
+where
+    C: ConfigExt,
+    N: Unsigned + Add<U1>,
+    Next<N>: Unsigned,
+{
+    read_encrypted(state, cursor.rest())
+}
+
+impl<C, P, R> Handshake<C, P, R, Msg1>
+where
+    C: Config,
+    C::Dh: Dh + NoiseName,
+    C::Aead: NoiseName,
+    C::MixHash: NoiseName,
+    P: Step<C, Msg1, Key = ChainingKey<C>> + Pattern,
+    R: Role,
+{
+    fn init(prologue: &[u8], keys: Keys<C>) -> Self {
+        let name = ProtocolName::new::<C>(&P::PATTERN);
+        let mut state = SymmetricState::from_protocol_name(&name).mix_hash(prologue);
+        // only the responder's static key appears in the pre-messages of the shipped patterns
+        if !P::PATTERN.responder_pre.is_empty() {
+            let responder_static = if R::INITIATOR {
+                keys.rs.clone()
+            } else {
+                Some(C::Dh::public(&keys.s))
+            };
+            if let Some(rs) = responder_static {
+                state = state.mix_hash(rs.as_ref());
+            }
+        }
+        Self::from_parts(state, keys)
+    }
+}
+
+impl<C, P> Handshake<C, P, Responder, Msg1>
+where
+    C: Config,
+    C::Dh: Dh + NoiseName,
+    C::Aead: NoiseName,
+    C::MixHash: NoiseName,
+    P: Step<C, Msg1, Key = ChainingKey<C>> + Pattern,
+{
+    pub fn new<Rng>(prologue: &[u8], s: Secret<C>, rng: &mut Rng) -> Self
+    where
+        Rng: RngCore + CryptoRng,
+    {
+        Self::with_ephemeral(prologue, s, C::Dh::generate(rng))
+    }
+
+    /// Normally the ephemeral key is generated, set it only for testing.
+    pub fn with_ephemeral(prologue: &[u8], s: Secret<C>, e: Secret<C>) -> Self {
+        Self::init(
+            prologue,
+            Keys {
+                s,
+                e,
+                rs: None,
+                re: None,
+            },
+        )
+    }
+}
+
+// XK
+// -> e, es
+// <- e, ee
+// -> s, se
+
+impl<C> Xk<C, Initiator, Msg1>
+where
+    C: Config,
+    C::Dh: Dh + NoiseName,
+    C::Aead: NoiseName,
+    C::MixHash: NoiseName,
+{
+    /// The responder's static key `rs` is known in advance.
+    pub fn new<Rng>(prologue: &[u8], s: Secret<C>, rs: Public<C>, rng: &mut Rng) -> Self
+    where
+        Rng: RngCore + CryptoRng,
+    {
+        Self::with_ephemeral(prologue, s, C::Dh::generate(rng), rs)
+    }
+
+    /// Normally the ephemeral key is generated, set it only for testing.
+    pub fn with_ephemeral(prologue: &[u8], s: Secret<C>, e: Secret<C>, rs: Public<C>) -> Self {
+        let keys = Keys {
+            s,
+            e,
+            rs: Some(rs),
+            re: None,
+        };
+        Self::init(prologue, keys)
+    }
+}
+
+impl<C> Xk<C, Initiator, Msg1>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+{
+    /// Writes `e, es` and the payload, returns the message length.
+    ///
+    /// # Errors
+    /// the buffer is too small or the remote static key is invalid
+    pub fn write_message(
+        self,
+        payload: &[u8],
+        message: &mut [u8],
+    ) -> Result<(usize, Xk<C, Initiator, Msg2>), HandshakeError> {
+        let Handshake { state, keys, .. } = self;
+        let mut cursor = Cursor::write(message);
+        let state = write_e(state, &keys, &mut cursor)?;
+        let state = state.mix_shared_secret(keys.dh(false, true)?);
+        let (len, state) = write_payload(state, payload, cursor)?;
+        Ok((len, Handshake::from_parts(state, keys)))
+    }
+}
+
+impl<C> Xk<C, Responder, Msg1>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+{
+    /// Reads `e, es`, decrypts the payload in place.
+    ///
+    /// # Errors
+    /// the message is malformed or mac mismatch
+    pub fn read_message(
+        self,
+        message: &mut [u8],
+    ) -> Result<(Xk<C, Responder, Msg2>, &mut [u8]), HandshakeError> {
+        let Handshake {
+            state, mut keys, ..
+        } = self;
+        let mut cursor = Cursor::read(message)?;
+        let state = read_e(state, &mut keys, &mut cursor)?;
+        let state = state.mix_shared_secret(keys.dh(true, false)?);
+        let (state, payload) = read_payload(state, cursor)?;
+        Ok((Handshake::from_parts(state, keys), payload))
+    }
+}
+
+impl<C> Xk<C, Responder, Msg2>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+{
+    /// Writes `e, ee` and the payload, returns the message length.
+    ///
+    /// # Errors
+    /// the buffer is too small or the remote ephemeral key is invalid
+    pub fn write_message(
+        self,
+        payload: &[u8],
+        message: &mut [u8],
+    ) -> Result<(usize, Xk<C, Responder, Msg3>), HandshakeError> {
+        let Handshake { state, keys, .. } = self;
+        let mut cursor = Cursor::write(message);
+        let state = write_e(state, &keys, &mut cursor)?;
+        let state = state.mix_shared_secret(keys.dh(false, false)?);
+        let (len, state) = write_payload(state, payload, cursor)?;
+        Ok((len, Handshake::from_parts(state, keys)))
+    }
+}
+
+impl<C> Xk<C, Initiator, Msg2>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+{
+    /// Reads `e, ee`, decrypts the payload in place.
+    ///
+    /// # Errors
+    /// the message is malformed or mac mismatch
+    pub fn read_message(
+        self,
+        message: &mut [u8],
+    ) -> Result<(Xk<C, Initiator, Msg3>, &mut [u8]), HandshakeError> {
+        let Handshake {
+            state, mut keys, ..
+        } = self;
+        let mut cursor = Cursor::read(message)?;
+        let state = read_e(state, &mut keys, &mut cursor)?;
+        let state = state.mix_shared_secret(keys.dh(false, false)?);
+        let (state, payload) = read_payload(state, cursor)?;
+        Ok((Handshake::from_parts(state, keys), payload))
+    }
+}
+
+impl<C> Xk<C, Initiator, Msg3>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+{
+    /// Writes `s, se` and the payload, returns the message length.
+    ///
+    /// # Errors
+    /// the buffer is too small or the remote ephemeral key is invalid
+    pub fn write_message(
+        self,
+        payload: &[u8],
+        message: &mut [u8],
+    ) -> Result<(usize, Xk<C, Initiator, Done>), HandshakeError> {
+        let Handshake { state, keys, .. } = self;
+        let mut cursor = Cursor::write(message);
+        let state = write_s(state, &keys, &mut cursor)?;
+        let state = state.mix_shared_secret(keys.dh(true, false)?);
+        let (len, state) = write_payload(state, payload, cursor)?;
+        Ok((len, Handshake::from_parts(state, keys)))
+    }
+}
+
+impl<C> Xk<C, Responder, Msg3>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+{
+    /// Reads `s, se`, decrypts the payload in place.
+    ///
+    /// # Errors
+    /// the message is malformed or mac mismatch
+    pub fn read_message(
+        self,
+        message: &mut [u8],
+    ) -> Result<(Xk<C, Responder, Done>, &mut [u8]), HandshakeError> {
+        let Handshake {
+            state, mut keys, ..
+        } = self;
+        let mut cursor = Cursor::read(message)?;
+        let state = read_s(state, &mut keys, &mut cursor)?;
+        let state = state.mix_shared_secret(keys.dh(false, true)?);
+        let (state, payload) = read_payload(state, cursor)?;
+        Ok((Handshake::from_parts(state, keys), payload))
+    }
+}
+
+// IK
+// -> e, es, s, ss
+// <- e, ee, se
+
+impl<C> Ik<C, Initiator, Msg1>
+where
+    C: Config,
+    C::Dh: Dh + NoiseName,
+    C::Aead: NoiseName,
+    C::MixHash: NoiseName,
+{
+    /// The responder's static key `rs` is known in advance.
+    pub fn new<Rng>(prologue: &[u8], s: Secret<C>, rs: Public<C>, rng: &mut Rng) -> Self
+    where
+        Rng: RngCore + CryptoRng,
+    {
+        Self::with_ephemeral(prologue, s, C::Dh::generate(rng), rs)
+    }
+
+    /// Normally the ephemeral key is generated, set it only for testing.
+    pub fn with_ephemeral(prologue: &[u8], s: Secret<C>, e: Secret<C>, rs: Public<C>) -> Self {
+        let keys = Keys {
+            s,
+            e,
+            rs: Some(rs),
+            re: None,
+        };
+        Self::init(prologue, keys)
+    }
+}
+
+impl<C> Ik<C, Initiator, Msg1>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+{
+    /// Writes `e, es, s, ss` and the payload, returns the message length.
+    ///
+    /// # Errors
+    /// the buffer is too small or the remote static key is invalid
+    pub fn write_message(
+        self,
+        payload: &[u8],
+        message: &mut [u8],
+    ) -> Result<(usize, Ik<C, Initiator, Msg2>), HandshakeError> {
+        let Handshake { state, keys, .. } = self;
+        let mut cursor = Cursor::write(message);
+        let state = write_e(state, &keys, &mut cursor)?;
+        let state = state.mix_shared_secret(keys.dh(false, true)?);
+        let state = write_s(state, &keys, &mut cursor)?;
+        let state = state.mix_shared_secret(keys.dh(true, true)?);
+        let (len, state) = write_payload(state, payload, cursor)?;
+        Ok((len, Handshake::from_parts(state, keys)))
+    }
+}
+
+impl<C> Ik<C, Responder, Msg1>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+{
+    /// Reads `e, es, s, ss`, decrypts the payload in place.
+    ///
+    /// # Errors
+    /// the message is malformed or mac mismatch
+    pub fn read_message(
+        self,
+        message: &mut [u8],
+    ) -> Result<(Ik<C, Responder, Msg2>, &mut [u8]), HandshakeError> {
+        let Handshake {
+            state, mut keys, ..
+        } = self;
+        let mut cursor = Cursor::read(message)?;
+        let state = read_e(state, &mut keys, &mut cursor)?;
+        let state = state.mix_shared_secret(keys.dh(true, false)?);
+        let state = read_s(state, &mut keys, &mut cursor)?;
+        let state = state.mix_shared_secret(keys.dh(true, true)?);
+        let (state, payload) = read_payload(state, cursor)?;
+        Ok((Handshake::from_parts(state, keys), payload))
+    }
+}
+
+impl<C> Ik<C, Responder, Msg2>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+{
+    /// Writes `e, ee, se` and the payload, returns the message length.
+    ///
+    /// # Errors
+    /// the buffer is too small or a remote key is invalid
+    pub fn write_message(
+        self,
+        payload: &[u8],
+        message: &mut [u8],
+    ) -> Result<(usize, Ik<C, Responder, Done>), HandshakeError> {
+        let Handshake { state, keys, .. } = self;
+        let mut cursor = Cursor::write(message);
+        let state = write_e(state, &keys, &mut cursor)?;
+        let state = state.mix_shared_secret(keys.dh(false, false)?);
+        let state = state.mix_shared_secret(keys.dh(false, true)?);
+        let (len, state) = write_payload(state, payload, cursor)?;
+        Ok((len, Handshake::from_parts(state, keys)))
+    }
+}
+
+impl<C> Ik<C, Initiator, Msg2>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+{
+    /// Reads `e, ee, se`, decrypts the payload in place.
+    ///
+    /// # Errors
+    /// the message is malformed or mac mismatch
+    pub fn read_message(
+        self,
+        message: &mut [u8],
+    ) -> Result<(Ik<C, Initiator, Done>, &mut [u8]), HandshakeError> {
+        let Handshake {
+            state, mut keys, ..
+        } = self;
+        let mut cursor = Cursor::read(message)?;
+        let state = read_e(state, &mut keys, &mut cursor)?;
+        let state = state.mix_shared_secret(keys.dh(false, false)?);
+        let state = state.mix_shared_secret(keys.dh(true, false)?);
+        let (state, payload) = read_payload(state, cursor)?;
+        Ok((Handshake::from_parts(state, keys), payload))
+    }
+}
+
+// XX
+// -> e
+// <- e, ee, s, es
+// -> s, se
+
+impl<C> Xx<C, Initiator, Msg1>
+where
+    C: Config,
+    C::Dh: Dh + NoiseName,
+    C::Aead: NoiseName,
+    C::MixHash: NoiseName,
+{
+    /// The responder's static key is received in the second message.
+    pub fn new<Rng>(prologue: &[u8], s: Secret<C>, rng: &mut Rng) -> Self
+    where
+        Rng: RngCore + CryptoRng,
+    {
+        Self::with_ephemeral(prologue, s, C::Dh::generate(rng))
+    }
+
+    /// Normally the ephemeral key is generated, set it only for testing.
+    pub fn with_ephemeral(prologue: &[u8], s: Secret<C>, e: Secret<C>) -> Self {
+        let keys = Keys {
+            s,
+            e,
+            rs: None,
+            re: None,
+        };
+        Self::init(prologue, keys)
+    }
+}
+
+impl<C> Xx<C, Initiator, Msg1>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+{
+    /// Writes `e` and the plaintext payload, returns the message length.
+    ///
+    /// # Errors
+    /// the buffer is too small
+    pub fn write_message(
+        self,
+        payload: &[u8],
+        message: &mut [u8],
+    ) -> Result<(usize, Xx<C, Initiator, Msg2>), HandshakeError> {
+        let Handshake { state, keys, .. } = self;
+        let mut cursor = Cursor::write(message);
+        let state = write_e(state, &keys, &mut cursor)?;
+        cursor.next(payload.len())?.clone_from_slice(payload);
+        if cursor.pos > MAX_MESSAGE_LEN {
+            return Err(HandshakeError::MessageTooLong);
+        }
+        let state = state.mix_hash(payload);
+        Ok((cursor.pos, Handshake::from_parts(state, keys)))
+    }
+}
+
+impl<C> Xx<C, Responder, Msg1>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+{
+    /// Reads `e`, the payload is plaintext.
+    ///
+    /// # Errors
+    /// the message is malformed
+    pub fn read_message(
+        self,
+        message: &mut [u8],
+    ) -> Result<(Xx<C, Responder, Msg2>, &mut [u8]), HandshakeError> {
+        let Handshake {
+            state, mut keys, ..
+        } = self;
+        let mut cursor = Cursor::read(message)?;
+        let state = read_e(state, &mut keys, &mut cursor)?;
+        let payload = cursor.rest();
+        let state = state.mix_hash(payload);
+        Ok((Handshake::from_parts(state, keys), payload))
+    }
+}
+
+impl<C> Xx<C, Responder, Msg2>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+{
+    /// Writes `e, ee, s, es` and the payload, returns the message length.
+    ///
+    /// # Errors
+    /// the buffer is too small or the remote ephemeral key is invalid
+    pub fn write_message(
+        self,
+        payload: &[u8],
+        message: &mut [u8],
+    ) -> Result<(usize, Xx<C, Responder, Msg3>), HandshakeError> {
+        let Handshake { state, keys, .. } = self;
+        let mut cursor = Cursor::write(message);
+        let state = write_e(state, &keys, &mut cursor)?;
+        let state = state.mix_shared_secret(keys.dh(false, false)?);
+        let state = write_s(state, &keys, &mut cursor)?;
+        let state = state.mix_shared_secret(keys.dh(true, false)?);
+        let (len, state) = write_payload(state, payload, cursor)?;
+        Ok((len, Handshake::from_parts(state, keys)))
+    }
+}
+
+impl<C> Xx<C, Initiator, Msg2>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+{
+    /// Reads `e, ee, s, es`, decrypts the payload in place.
+    ///
+    /// # Errors
+    /// the message is malformed or mac mismatch
+    pub fn read_message(
+        self,
+        message: &mut [u8],
+    ) -> Result<(Xx<C, Initiator, Msg3>, &mut [u8]), HandshakeError> {
+        let Handshake {
+            state, mut keys, ..
+        } = self;
+        let mut cursor = Cursor::read(message)?;
+        let state = read_e(state, &mut keys, &mut cursor)?;
+        let state = state.mix_shared_secret(keys.dh(false, false)?);
+        let state = read_s(state, &mut keys, &mut cursor)?;
+        let state = state.mix_shared_secret(keys.dh(false, true)?);
+        let (state, payload) = read_payload(state, cursor)?;
+        Ok((Handshake::from_parts(state, keys), payload))
+    }
+}
+
+impl<C> Xx<C, Initiator, Msg3>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+{
+    /// Writes `s, se` and the payload, returns the message length.
+    ///
+    /// # Errors
+    /// the buffer is too small or the remote ephemeral key is invalid
+    pub fn write_message(
+        self,
+        payload: &[u8],
+        message: &mut [u8],
+    ) -> Result<(usize, Xx<C, Initiator, Done>), HandshakeError> {
+        let Handshake { state, keys, .. } = self;
+        let mut cursor = Cursor::write(message);
+        let state = write_s(state, &keys, &mut cursor)?;
+        let state = state.mix_shared_secret(keys.dh(true, false)?);
+        let (len, state) = write_payload(state, payload, cursor)?;
+        Ok((len, Handshake::from_parts(state, keys)))
+    }
+}
+
+impl<C> Xx<C, Responder, Msg3>
+where
+    C: ConfigExt,
+    C::Dh: Dh,
+{
+    /// Reads `s, se`, decrypts the payload in place.
+    ///
+    /// # Errors
+    /// the message is malformed or mac mismatch
+    pub fn read_message(
+        self,
+        message: &mut [u8],
+    ) -> Result<(Xx<C, Responder, Done>, &mut [u8]), HandshakeError> {
+        let Handshake {
+            state, mut keys, ..
+        } = self;
+        let mut cursor = Cursor::read(message)?;
+        let state = read_s(state, &mut keys, &mut cursor)?;
+        let state = state.mix_shared_secret(keys.dh(false, true)?);
+        let (state, payload) = read_payload(state, cursor)?;
+        Ok((Handshake::from_parts(state, keys), payload))
+    }
+}
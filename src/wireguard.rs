@@ -0,0 +1,543 @@
+use core::fmt;
+
+use {
+    aead::{AeadInPlace, KeyInit},
+    blake2::{
+        Blake2s256, Blake2sMac,
+        digest::{Digest, Mac},
+    },
+    chacha20poly1305::{ChaCha20Poly1305, XChaCha20Poly1305},
+    generic_array::{
+        GenericArray,
+        typenum::{B0, U1, U16},
+    },
+    hkdf::hmac::SimpleHmac,
+    rand_core::{RngCore, CryptoRng},
+    subtle::ConstantTimeEq,
+    zeroize::Zeroizing,
+};
+
+use super::{
+    cipher_state::MacMismatch,
+    curve25519::{X25519, X25519Secret},
+    dh::Dh,
+    symmetric_state::{ChainingKey, Key, Output, SymmetricState},
+};
+
+/// `Noise_IKpsk2_25519_ChaChaPoly_BLAKE2s` as WireGuard has.
+pub type WireGuardConfig = (
+    SimpleHmac<Blake2s256>,
+    Blake2s256,
+    B0,
+    ChaCha20Poly1305,
+    X25519,
+);
+
+pub const WIREGUARD_CONSTRUCTION: &str = "Noise_IKpsk2_25519_ChaChaPoly_BLAKE2s";
+
+/// Mixed into the hash as the prologue.
+pub const WIREGUARD_IDENTIFIER: &[u8] = b"WireGuard v1 zx2c4 Jason@zx2c4.com";
+
+const LABEL_MAC1: &[u8] = b"mac1----";
+const LABEL_COOKIE: &[u8] = b"cookie--";
+
+pub const WIREGUARD_INITIATION_LEN: usize = 148;
+pub const WIREGUARD_RESPONSE_LEN: usize = 92;
+pub const WIREGUARD_COOKIE_REPLY_LEN: usize = 64;
+
+const TYPE_INITIATION: u8 = 1;
+const TYPE_RESPONSE: u8 = 2;
+const TYPE_COOKIE_REPLY: u8 = 3;
+
+const MAC_LEN: usize = 16;
+
+type State = SymmetricState<WireGuardConfig, Key<WireGuardConfig, U1>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireGuardError {
+    /// wrong type, reserved bytes or length
+    InvalidMessage,
+    /// the receiver index is not ours
+    UnknownIndex,
+    InvalidMac1,
+    InvalidMac2,
+    /// the shared secret is zero
+    InvalidPublicKey,
+    MacMismatch,
+}
+
+impl From<MacMismatch> for WireGuardError {
+    fn from(_: MacMismatch) -> Self {
+        WireGuardError::MacMismatch
+    }
+}
+
+impl fmt::Display for WireGuardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WireGuardError::InvalidMessage => write!(f, "invalid message"),
+            WireGuardError::UnknownIndex => write!(f, "unknown receiver index"),
+            WireGuardError::InvalidMac1 => write!(f, "invalid mac1"),
+            WireGuardError::InvalidMac2 => write!(f, "invalid mac2"),
+            WireGuardError::InvalidPublicKey => write!(f, "invalid public key"),
+            WireGuardError::MacMismatch => write!(f, "mac mismatch"),
+        }
+    }
+}
+
+#[cfg(any(feature = "std", test))]
+impl std::error::Error for WireGuardError {}
+
+/// The TAI64N timestamp, big-endian, so the byte order is the chronological order.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Tai64N([u8; 12]);
+
+impl Tai64N {
+    // 2^62 and 10 leap seconds like WireGuard has
+    const UNIX_EPOCH: u64 = 0x4000_0000_0000_000a;
+
+    #[must_use]
+    pub fn from_unix(secs: u64, nanos: u32) -> Self {
+        let mut bytes = [0; 12];
+        bytes[..8].clone_from_slice(&(Self::UNIX_EPOCH + secs).to_be_bytes());
+        bytes[8..].clone_from_slice(&nanos.to_be_bytes());
+        Tai64N(bytes)
+    }
+
+    /// The nanoseconds are rounded down to 2^24 so the timestamp does not leak the precise time.
+    ///
+    /// # Errors
+    /// the system time is before the unix epoch
+    #[cfg(any(feature = "std", test))]
+    pub fn now() -> Result<Self, std::time::SystemTimeError> {
+        use std::time::SystemTime;
+
+        let time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?;
+        Ok(Self::from_unix(
+            time.as_secs(),
+            time.subsec_nanos() & !0xff_ffff,
+        ))
+    }
+
+    pub const fn from_bytes(bytes: [u8; 12]) -> Self {
+        Tai64N(bytes)
+    }
+
+    pub const fn as_bytes(&self) -> &[u8; 12] {
+        &self.0
+    }
+}
+
+fn hash(parts: &[&[u8]]) -> [u8; 32] {
+    let mut d = Blake2s256::new();
+    for part in parts {
+        d.update(part);
+    }
+    d.finalize().into()
+}
+
+// the keyed BLAKE2s with 16 bytes output
+fn mac(key: &[u8], data: &[u8]) -> [u8; MAC_LEN] {
+    let mut m = <Blake2sMac<U16> as KeyInit>::new_from_slice(key).expect("key is at most 32 bytes");
+    Mac::update(&mut m, data);
+    m.finalize().into_bytes().into()
+}
+
+fn dh(secret: &X25519Secret, public: &[u8; 32]) -> Result<[u8; 32], WireGuardError> {
+    X25519::dh(secret, public).ok_or(WireGuardError::InvalidPublicKey)
+}
+
+fn check_header(message: &[u8], ty: u8) -> Result<(), WireGuardError> {
+    if message[..4] == [ty, 0, 0, 0] {
+        Ok(())
+    } else {
+        Err(WireGuardError::InvalidMessage)
+    }
+}
+
+// the `N` bytes of the message at `start`, the offsets are constant
+fn array<const N: usize>(message: &[u8], start: usize) -> [u8; N] {
+    let mut bytes = [0; N];
+    bytes.clone_from_slice(&message[start..(start + N)]);
+    bytes
+}
+
+fn read_u32(message: &[u8], start: usize) -> u32 {
+    u32::from_le_bytes(array(message, start))
+}
+
+fn init(
+    responder_static: &[u8; 32],
+) -> SymmetricState<WireGuardConfig, ChainingKey<WireGuardConfig>> {
+    SymmetricState::new(WIREGUARD_CONSTRUCTION)
+        .mix_hash(WIREGUARD_IDENTIFIER)
+        .mix_hash(responder_static)
+}
+
+/// The keys after the handshake, the indices identify the session in the transport messages.
+pub struct WireGuardSession {
+    pub local_index: u32,
+    pub remote_index: u32,
+    pub transport: Output<WireGuardConfig, 1>,
+}
+
+/// The initiator waiting for the response.
+pub struct WireGuardInitiator {
+    state: State,
+    local_static: X25519Secret,
+    local_ephemeral: X25519Secret,
+    psk: Zeroizing<[u8; 32]>,
+    local_index: u32,
+}
+
+impl WireGuardInitiator {
+    /// Returns the state and the initiation message, its MACs are zero,
+    /// use `WireGuardCookieGenerator::add_macs` before sending it.
+    ///
+    /// # Errors
+    /// invalid remote static key
+    pub fn new(
+        local_static: &X25519Secret,
+        local_ephemeral: X25519Secret,
+        remote_static: &[u8; 32],
+        psk: &[u8; 32],
+        local_index: u32,
+        timestamp: &Tai64N,
+    ) -> Result<(Self, [u8; WIREGUARD_INITIATION_LEN]), WireGuardError> {
+        let mut message = [0; WIREGUARD_INITIATION_LEN];
+        message[0] = TYPE_INITIATION;
+        message[4..8].clone_from_slice(&local_index.to_le_bytes());
+        let (e, rest) = message[8..].split_at_mut(32);
+        let (s, rest) = rest.split_at_mut(48);
+        let (t, _) = rest.split_at_mut(28);
+
+        e.clone_from_slice(&X25519::public(&local_ephemeral));
+        let es = dh(&local_ephemeral, remote_static)?;
+        let ss = dh(local_static, remote_static)?;
+        s[..32].clone_from_slice(&X25519::public(local_static));
+        t[..12].clone_from_slice(timestamp.as_bytes());
+
+        let (state, tag) = init(remote_static)
+            .mix_hash(e)
+            .mix_key(e)
+            .mix_shared_secret(es)
+            .encrypt(&mut s[..32]);
+        s[32..].clone_from_slice(&tag);
+        let (state, tag) = state.mix_shared_secret(ss).encrypt(&mut t[..12]);
+        t[12..].clone_from_slice(&tag);
+
+        let initiator = WireGuardInitiator {
+            state,
+            local_static: local_static.clone(),
+            local_ephemeral,
+            psk: Zeroizing::new(*psk),
+            local_index,
+        };
+        Ok((initiator, message))
+    }
+
+    /// The MACs of the response should be checked by `WireGuardCookieChecker` before.
+    ///
+    /// # Errors
+    /// invalid message, unknown index, invalid public key or mac mismatch
+    pub fn read_response(
+        self,
+        message: &[u8; WIREGUARD_RESPONSE_LEN],
+    ) -> Result<WireGuardSession, WireGuardError> {
+        check_header(message, TYPE_RESPONSE)?;
+        let remote_index = read_u32(message, 4);
+        if read_u32(message, 8) != self.local_index {
+            return Err(WireGuardError::UnknownIndex);
+        }
+        let remote_ephemeral = array(message, 12);
+        let tag = GenericArray::from_slice(&message[44..60]);
+
+        let ee = dh(&self.local_ephemeral, &remote_ephemeral)?;
+        let se = dh(&self.local_static, &remote_ephemeral)?;
+        let state = self
+            .state
+            .mix_hash(&remote_ephemeral)
+            .mix_key(&remote_ephemeral)
+            .mix_shared_secret(ee)
+            .mix_shared_secret(se)
+            .mix_psk(*self.psk)
+            .decrypt(&mut [], tag)?;
+
+        Ok(WireGuardSession {
+            local_index: self.local_index,
+            remote_index,
+            transport: state.finish::<1, false>(),
+        })
+    }
+}
+
+/// The initiation as the responder sees it.
+pub struct WireGuardInitiation {
+    pub remote_index: u32,
+    pub remote_static: [u8; 32],
+    /// Should be greater than the timestamp of the previous initiation of this peer.
+    pub timestamp: Tai64N,
+}
+
+/// The responder after the initiation is read, the caller looks up the peer
+/// by the static key, checks the timestamp and responds.
+pub struct WireGuardResponder {
+    state: State,
+    remote_ephemeral: [u8; 32],
+    remote_static: [u8; 32],
+    remote_index: u32,
+}
+
+impl WireGuardResponder {
+    /// The MACs of the initiation should be checked by `WireGuardCookieChecker` before.
+    ///
+    /// # Errors
+    /// invalid message, invalid public key or mac mismatch
+    pub fn read_initiation(
+        local_static: &X25519Secret,
+        message: &[u8; WIREGUARD_INITIATION_LEN],
+    ) -> Result<(Self, WireGuardInitiation), WireGuardError> {
+        check_header(message, TYPE_INITIATION)?;
+        let remote_index = read_u32(message, 4);
+        let remote_ephemeral: [u8; 32] = array(message, 8);
+        let mut s: [u8; 48] = array(message, 40);
+        let mut t: [u8; 28] = array(message, 88);
+
+        let es = dh(local_static, &remote_ephemeral)?;
+        let (remote_static, tag) = s.split_at_mut(32);
+        let state = init(&X25519::public(local_static))
+            .mix_hash(&remote_ephemeral)
+            .mix_key(&remote_ephemeral)
+            .mix_shared_secret(es)
+            .decrypt(remote_static, GenericArray::from_slice(tag))?;
+        let remote_static = array(remote_static, 0);
+        let ss = dh(local_static, &remote_static)?;
+        let (timestamp, tag) = t.split_at_mut(12);
+        let state = state
+            .mix_shared_secret(ss)
+            .decrypt(timestamp, GenericArray::from_slice(tag))?;
+        let timestamp = Tai64N::from_bytes(array(timestamp, 0));
+
+        let responder = WireGuardResponder {
+            state,
+            remote_ephemeral,
+            remote_static,
+            remote_index,
+        };
+        let initiation = WireGuardInitiation {
+            remote_index,
+            remote_static,
+            timestamp,
+        };
+        Ok((responder, initiation))
+    }
+
+    /// Returns the response message, its MACs are zero,
+    /// use `WireGuardCookieGenerator::add_macs` before sending it.
+    ///
+    /// # Errors
+    /// invalid public key
+    pub fn write_response(
+        self,
+        local_ephemeral: &X25519Secret,
+        psk: &[u8; 32],
+        local_index: u32,
+    ) -> Result<([u8; WIREGUARD_RESPONSE_LEN], WireGuardSession), WireGuardError> {
+        let mut message = [0; WIREGUARD_RESPONSE_LEN];
+        message[0] = TYPE_RESPONSE;
+        message[4..8].clone_from_slice(&local_index.to_le_bytes());
+        message[8..12].clone_from_slice(&self.remote_index.to_le_bytes());
+        let e = X25519::public(local_ephemeral);
+        message[12..44].clone_from_slice(&e);
+
+        let ee = dh(local_ephemeral, &self.remote_ephemeral)?;
+        let se = dh(local_ephemeral, &self.remote_static)?;
+        let (state, tag) = self
+            .state
+            .mix_hash(&e)
+            .mix_key(&e)
+            .mix_shared_secret(ee)
+            .mix_shared_secret(se)
+            .mix_psk(*psk)
+            .encrypt(&mut []);
+        message[44..60].clone_from_slice(&tag);
+
+        let session = WireGuardSession {
+            local_index,
+            remote_index: self.remote_index,
+            transport: state.finish::<1, true>(),
+        };
+        Ok((message, session))
+    }
+}
+
+/// Adds MAC1 and MAC2 to the handshake messages sent to the peer,
+/// keeps the cookie from its cookie reply.
+pub struct WireGuardCookieGenerator {
+    mac1_key: Zeroizing<[u8; 32]>,
+    cookie_key: Zeroizing<[u8; 32]>,
+    last_mac1: Option<[u8; MAC_LEN]>,
+    cookie: Option<[u8; MAC_LEN]>,
+}
+
+impl WireGuardCookieGenerator {
+    pub fn new(remote_static: &[u8; 32]) -> Self {
+        WireGuardCookieGenerator {
+            mac1_key: Zeroizing::new(hash(&[LABEL_MAC1, remote_static])),
+            cookie_key: Zeroizing::new(hash(&[LABEL_COOKIE, remote_static])),
+            last_mac1: None,
+            cookie: None,
+        }
+    }
+
+    /// Writes the MACs into the last 32 bytes of the initiation or the response,
+    /// MAC2 is zero unless there is a cookie.
+    ///
+    /// # Errors
+    /// the message is shorter than the MACs
+    pub fn add_macs(&mut self, message: &mut [u8]) -> Result<(), WireGuardError> {
+        let len = message
+            .len()
+            .checked_sub(2 * MAC_LEN)
+            .ok_or(WireGuardError::InvalidMessage)?;
+        let mac1 = mac(&*self.mac1_key, &message[..len]);
+        message[len..(len + MAC_LEN)].clone_from_slice(&mac1);
+        self.last_mac1 = Some(mac1);
+        let mac2 = match &self.cookie {
+            Some(cookie) => mac(cookie, &message[..(len + MAC_LEN)]),
+            None => [0; MAC_LEN],
+        };
+        message[(len + MAC_LEN)..].clone_from_slice(&mac2);
+        Ok(())
+    }
+
+    /// Decrypts the cookie, it is used for MAC2 of the next messages.
+    /// The cookie expires in two minutes, then `clear_cookie` should be called.
+    ///
+    /// # Errors
+    /// invalid message or mac mismatch
+    pub fn consume_cookie_reply(
+        &mut self,
+        message: &[u8; WIREGUARD_COOKIE_REPLY_LEN],
+    ) -> Result<(), WireGuardError> {
+        check_header(message, TYPE_COOKIE_REPLY)?;
+        let last_mac1 = self.last_mac1.ok_or(WireGuardError::InvalidMessage)?;
+        let nonce = GenericArray::from_slice(&message[8..32]);
+        let mut cookie: [u8; MAC_LEN] = array(message, 32);
+        let tag = GenericArray::from_slice(&message[48..]);
+        XChaCha20Poly1305::new(GenericArray::from_slice(&*self.cookie_key))
+            .decrypt_in_place_detached(nonce, &last_mac1, &mut cookie, tag)
+            .map_err(|_| WireGuardError::MacMismatch)?;
+        self.cookie = Some(cookie);
+        Ok(())
+    }
+
+    pub fn clear_cookie(&mut self) {
+        self.cookie = None;
+    }
+}
+
+/// Checks MAC1 and MAC2 of the handshake messages sent to us, makes the cookie replies.
+pub struct WireGuardCookieChecker {
+    mac1_key: Zeroizing<[u8; 32]>,
+    cookie_key: Zeroizing<[u8; 32]>,
+}
+
+impl WireGuardCookieChecker {
+    pub fn new(local_static_public: &[u8; 32]) -> Self {
+        WireGuardCookieChecker {
+            mac1_key: Zeroizing::new(hash(&[LABEL_MAC1, local_static_public])),
+            cookie_key: Zeroizing::new(hash(&[LABEL_COOKIE, local_static_public])),
+        }
+    }
+
+    /// # Errors
+    /// invalid mac1 or the message is too short
+    pub fn check_mac1(&self, message: &[u8]) -> Result<(), WireGuardError> {
+        let len = message
+            .len()
+            .checked_sub(2 * MAC_LEN)
+            .ok_or(WireGuardError::InvalidMessage)?;
+        let mac1 = mac(&*self.mac1_key, &message[..len]);
+        if bool::from(mac1.ct_eq(&message[len..(len + MAC_LEN)])) {
+            Ok(())
+        } else {
+            Err(WireGuardError::InvalidMac1)
+        }
+    }
+
+    /// Under load, the message should have MAC2 made with the cookie of the `source`,
+    /// the `secret` should change every two minutes.
+    ///
+    /// # Errors
+    /// invalid mac2 or the message is too short
+    pub fn check_mac2(
+        &self,
+        message: &[u8],
+        secret: &[u8; 32],
+        source: &[u8],
+    ) -> Result<(), WireGuardError> {
+        let len = message
+            .len()
+            .checked_sub(MAC_LEN)
+            .ok_or(WireGuardError::InvalidMessage)?;
+        let cookie = mac(secret, source);
+        let mac2 = mac(&cookie, &message[..len]);
+        if bool::from(mac2.ct_eq(&message[len..])) {
+            Ok(())
+        } else {
+            Err(WireGuardError::InvalidMac2)
+        }
+    }
+
+    /// The reply to the initiation or the response `message` under load.
+    ///
+    /// # Errors
+    /// the message is too short
+    pub fn cookie_reply<R>(
+        &self,
+        message: &[u8],
+        secret: &[u8; 32],
+        source: &[u8],
+        rng: &mut R,
+    ) -> Result<[u8; WIREGUARD_COOKIE_REPLY_LEN], WireGuardError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut nonce = [0; 24];
+        rng.fill_bytes(&mut nonce);
+        self.cookie_reply_with_nonce(message, secret, source, &nonce)
+    }
+
+    /// Same as `cookie_reply`, but the nonce is given, for test vectors.
+    ///
+    /// # Errors
+    /// the message is too short
+    pub fn cookie_reply_with_nonce(
+        &self,
+        message: &[u8],
+        secret: &[u8; 32],
+        source: &[u8],
+        nonce: &[u8; 24],
+    ) -> Result<[u8; WIREGUARD_COOKIE_REPLY_LEN], WireGuardError> {
+        let len = message
+            .len()
+            .checked_sub(2 * MAC_LEN)
+            .filter(|len| *len >= 8)
+            .ok_or(WireGuardError::InvalidMessage)?;
+        let mac1 = &message[len..(len + MAC_LEN)];
+
+        let mut reply = [0; WIREGUARD_COOKIE_REPLY_LEN];
+        reply[0] = TYPE_COOKIE_REPLY;
+        // the sender index of the message
+        reply[4..8].clone_from_slice(&message[4..8]);
+        reply[8..32].clone_from_slice(nonce);
+        let (cookie, tag) = reply[32..].split_at_mut(MAC_LEN);
+        cookie.clone_from_slice(&mac(secret, source));
+        let t = XChaCha20Poly1305::new(GenericArray::from_slice(&*self.cookie_key))
+            .encrypt_in_place_detached(GenericArray::from_slice(nonce), mac1, cookie)
+            .expect("cookie is short");
+        tag.clone_from_slice(&t);
+        Ok(reply)
+    }
+}